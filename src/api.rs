@@ -0,0 +1,45 @@
+//! The library interface, for tools like mdbook preprocessors that want the
+//! documentation without going through the command line interface.
+
+#[cfg(test)]
+mod tests;
+
+use color_eyre::eyre::Result;
+
+use crate::{extract_feature_docs::ExtractFeatureDocsOptions, markdown::SectionDelimiters};
+
+pub use crate::{
+    config::LinkResolutionMode,
+    extract_crate_docs::{ExtractOptions, rewrite_markdown::RewriteMarkdownOptions},
+    markdown::Section,
+};
+
+/// Returns the feature documentation of a `Cargo.toml`.
+///
+/// `label` is the formatting of the feature label like `` `{feature}` ``,
+/// see the `feature-label` setting.
+pub fn extract_feature_docs(toml: &str, label: &str) -> Result<String> {
+    crate::extract_feature_docs::extract(
+        toml,
+        None,
+        &ExtractFeatureDocsOptions { feature_label: label, ..Default::default() },
+    )
+}
+
+/// Returns the crate documentation with its intra-doc links resolved.
+pub fn extract_crate_docs(options: ExtractOptions) -> Result<String> {
+    crate::extract_crate_docs::extract_from_crate(options)
+}
+
+/// Rewrites crate documentation for a readme, like shrinking headings and
+/// removing hidden lines of code blocks.
+pub fn rewrite_markdown(docs: &str, options: RewriteMarkdownOptions) -> String {
+    crate::extract_crate_docs::rewrite_markdown::rewrite_markdown(docs, &options)
+}
+
+/// Finds the section between `<!-- {name} start -->` and `<!-- {name} end -->`.
+///
+/// Errors if the markers are unbalanced. Returns `None` if there are no markers.
+pub fn find_section(markdown: &str, name: &str) -> Result<Option<Section>> {
+    crate::markdown::find_section(markdown, name, &SectionDelimiters::default())
+}
@@ -0,0 +1,87 @@
+use std::fs;
+
+use cargo_metadata::MetadataCommand;
+use indoc::indoc;
+use rustdoc_types::Crate;
+
+use crate::rustdoc_json;
+
+use super::{
+    ExtractOptions, RewriteMarkdownOptions, extract_crate_docs, extract_feature_docs, find_section,
+    rewrite_markdown,
+};
+
+#[test]
+fn test_extract_feature_docs() {
+    let toml = indoc! {r#"
+        [features]
+        default = ["std"]
+        ## Use the standard library
+        std = []
+    "#};
+
+    assert_eq!(
+        extract_feature_docs(toml, "`{feature}`").unwrap(),
+        "- `std` *(enabled by default)* — Use the standard library\n"
+    );
+}
+
+#[test]
+fn test_extract_crate_docs() {
+    const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+    let metadata =
+        &MetadataCommand::new().manifest_path(format!("{MANIFEST_DIR}/Cargo.toml")).exec().unwrap();
+
+    let package = metadata.packages.iter().find(|p| p.name.as_str() == "test-crate").unwrap();
+    let package_target = package.targets.iter().find(|t| t.is_lib()).unwrap();
+
+    let (_, path) = rustdoc_json::generate(rustdoc_json::Options {
+        metadata,
+        package,
+        package_target,
+        toolchain: Some("nightly-2026-06-24"),
+        no_rustup: false,
+        all_features: false,
+        no_default_features: false,
+        features: &mut None.into_iter(),
+        manifest_path: None,
+        target: None,
+        target_dir: None,
+        quiet: false,
+        document_private_items: false,
+        rustdoc_args: &[],
+        no_deps: false,
+        output: rustdoc_json::CommandOutput::Inherit,
+    })
+    .unwrap();
+
+    let json = fs::read_to_string(path).expect("failed to read generated rustdoc json");
+    let krate: Crate = serde_json::from_str(&json).expect("failed to parse generated rustdoc json");
+
+    let docs = extract_crate_docs(ExtractOptions::new(&krate, metadata)).unwrap();
+
+    assert!(docs.contains("This is kitchen-sink test crate for `cargo-insert-docs`."));
+    assert!(docs.contains("https://docs.rs/test-crate/0.0.0/test_crate/struct.MyStruct.html"));
+}
+
+#[test]
+fn test_rewrite_markdown() {
+    let docs = "# Title\n```\nlet x = 1;\n# let y = 2;\n```\n";
+
+    assert_eq!(
+        rewrite_markdown(docs, RewriteMarkdownOptions { shrink_headings: 1, ..Default::default() }),
+        "## Title\n```rust\nlet x = 1;\n```\n"
+    );
+}
+
+#[test]
+fn test_find_section() {
+    let markdown = "# Title\n<!-- my section start -->\ncontent\n<!-- my section end -->\n";
+
+    let section = find_section(markdown, "my section").unwrap().unwrap();
+    assert_eq!(&markdown[section.content_span], "\ncontent\n");
+
+    assert!(find_section("# Title\n", "my section").unwrap().is_none());
+    assert!(find_section("<!-- my section start -->\n", "my section").is_err());
+}
@@ -0,0 +1,52 @@
+//! Writes files without leaving them half written when the process is killed.
+//!
+//! The contents are written to a temporary file in the same directory, which is then renamed
+//! over the target. The rename is atomic on Unix and uses `MoveFileExW` with
+//! `MOVEFILE_REPLACE_EXISTING` on Windows.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs,
+    io::{self, Write as _},
+    path::Path,
+};
+
+use tempfile::NamedTempFile;
+
+/// Replaces the contents of `path` with `contents`, keeping the permissions of an existing file.
+///
+/// Falls back to writing the file directly if the temporary file can't be renamed
+/// because it is on a different filesystem.
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    // write through symlinks instead of replacing them
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut file = NamedTempFile::new_in(dir)?;
+    file.write_all(contents)?;
+
+    match fs::metadata(&path) {
+        Ok(metadata) => file.as_file().set_permissions(metadata.permissions())?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+        Err(err) => return Err(err),
+    }
+
+    file.as_file().sync_all()?;
+
+    match file.persist(&path) {
+        Ok(_) => Ok(()),
+        Err(err) if err.error.kind() == io::ErrorKind::CrossesDevices => {
+            tracing::warn!(
+                "the temporary file is on a different filesystem, writing to the file directly"
+            );
+            fs::write(&path, contents)
+        }
+        Err(err) => Err(err.error),
+    }
+}
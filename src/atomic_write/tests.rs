@@ -0,0 +1,48 @@
+use std::fs;
+
+use super::write;
+
+#[test]
+fn test_write() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("README.md");
+
+    write(&path, b"first").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+    write(&path, b"second").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+
+    // no temporary files are left behind
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("README.md");
+
+    fs::write(&path, "first").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+    write(&path, b"second").unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_through_symlink() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("README.md");
+    let link = dir.path().join("link.md");
+
+    fs::write(&target, "first").unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    write(&link, b"second").unwrap();
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "second");
+}
@@ -0,0 +1,28 @@
+//! Shields.io badges for the readme.
+
+#[cfg(test)]
+mod tests;
+
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+
+use crate::config::BadgeStyle;
+
+/// The characters to percent-encode in a badge path segment.
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'.').remove(b'-').remove(b'_');
+
+/// Returns a badge like `[![Minimum Rust Version](https://img.shields.io/badge/rustc-1.70%2B-lightgray)](https://releases.rs/docs/1.70/)`.
+pub fn msrv(version: &str, style: BadgeStyle) -> String {
+    // in the badge path a `-` separates the label, message and color, so `-` and `_` are doubled
+    let message = format!("{}+", version.replace('-', "--").replace('_', "__"));
+    let message = utf8_percent_encode(&message, SEGMENT);
+    let release = utf8_percent_encode(version, SEGMENT);
+
+    let query = match style {
+        BadgeStyle::Flat => String::new(),
+        style => format!("?style={}", style.as_str()),
+    };
+
+    format!(
+        "[![Minimum Rust Version](https://img.shields.io/badge/rustc-{message}-lightgray{query})](https://releases.rs/docs/{release}/)"
+    )
+}
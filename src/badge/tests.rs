@@ -0,0 +1,17 @@
+use expect_test::expect;
+
+use crate::config::BadgeStyle;
+
+use super::msrv;
+
+#[test]
+fn test_msrv() {
+    expect!["[![Minimum Rust Version](https://img.shields.io/badge/rustc-1.70%2B-lightgray)](https://releases.rs/docs/1.70/)"]
+        .assert_eq(&msrv("1.70", BadgeStyle::Flat));
+
+    expect!["[![Minimum Rust Version](https://img.shields.io/badge/rustc-1.70.0%2B-lightgray)](https://releases.rs/docs/1.70.0/)"]
+        .assert_eq(&msrv("1.70.0", BadgeStyle::Flat));
+
+    expect!["[![Minimum Rust Version](https://img.shields.io/badge/rustc-1.85.0%2B-lightgray?style=for-the-badge)](https://releases.rs/docs/1.85.0/)"]
+        .assert_eq(&msrv("1.85.0", BadgeStyle::ForTheBadge));
+}
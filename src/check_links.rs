@@ -0,0 +1,152 @@
+//! Checks that the generated docs.rs links exist, for `cargo insert-docs check-links`.
+//!
+//! Without the `check-links` feature the check errors instead.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use color_eyre::eyre::{Result, bail};
+use tracing::{info, warn};
+
+use crate::{
+    markdown,
+    pretty_log::{Mutx as _, PrettyLog},
+};
+
+/// The per-request timeout if `--timeout` is not given.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckLinksOptions {
+    /// Error instead of warn about broken links.
+    pub error_on_broken_links: bool,
+    pub timeout: Duration,
+}
+
+/// Returns the links to docs.rs, in the order they appear and without duplicates.
+pub fn docs_rs_urls(markdown: &str) -> Vec<&str> {
+    let mut urls = vec![];
+
+    for destination in markdown::link_destinations(markdown) {
+        let is_docs_rs = ["https://docs.rs/", "http://docs.rs/"]
+            .iter()
+            .any(|prefix| destination.starts_with(prefix));
+
+        if is_docs_rs && !urls.contains(&destination) {
+            urls.push(destination);
+        }
+    }
+
+    urls
+}
+
+/// Sends a HEAD request to each url, with up to `jobs` requests at once.
+///
+/// Broken links are warnings because docs.rs may be temporarily unavailable,
+/// unless `error_on_broken_links` is set.
+pub fn check(
+    urls: &[String],
+    options: &CheckLinksOptions,
+    jobs: usize,
+    log: &PrettyLog,
+) -> Result<()> {
+    let client = Client::new(options.timeout)?;
+
+    let jobs = match jobs {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+
+    let threads = jobs.min(urls.len());
+    let next = AtomicUsize::new(0);
+    let broken = Mutex::new(vec![]);
+
+    log.begin_progress(urls.len(), "checking links");
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(url) = urls.get(index) else { break };
+                    log.set_progress_message(url);
+
+                    if let Err(reason) = client.head(url) {
+                        broken.lck().push((index, reason));
+                    }
+
+                    log.tick_progress();
+                }
+            });
+        }
+    });
+
+    log.finish_progress();
+
+    let mut broken = broken.into_inner().unwrap_or_else(PoisonError::into_inner);
+    broken.sort_unstable_by_key(|(index, _)| *index);
+
+    for (index, reason) in &broken {
+        warn!("broken link {}: {reason}", urls[*index]);
+    }
+
+    if broken.is_empty() {
+        info!("all {} docs.rs links are live", urls.len());
+    } else if options.error_on_broken_links {
+        bail!("{} of {} docs.rs links are broken", broken.len(), urls.len());
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "check-links")]
+struct Client(reqwest::blocking::Client);
+
+#[cfg(feature = "check-links")]
+impl Client {
+    fn new(timeout: Duration) -> Result<Self> {
+        use color_eyre::eyre::WrapErr as _;
+
+        reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .user_agent(concat!("cargo-insert-docs/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map(Self)
+            .wrap_err("failed to create the http client")
+    }
+
+    /// Returns why the url is broken, if it is.
+    fn head(&self, url: &str) -> Result<(), String> {
+        match self.0.head(url).send() {
+            Ok(response) if response.status() == reqwest::StatusCode::OK => Ok(()),
+            Ok(response) => Err(format!("status {}", response.status())),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+}
+
+#[cfg(not(feature = "check-links"))]
+struct Client;
+
+#[cfg(not(feature = "check-links"))]
+impl Client {
+    fn new(_timeout: Duration) -> Result<Self> {
+        bail!(
+            "cargo-insert-docs was built without the `check-links` feature\n\
+            hint: reinstall it with `cargo install cargo-insert-docs --features check-links`"
+        )
+    }
+
+    fn head(&self, _url: &str) -> Result<(), String> {
+        unreachable!("the client can't be created")
+    }
+}
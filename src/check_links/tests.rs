@@ -0,0 +1,32 @@
+use expect_test::expect;
+use indoc::indoc;
+
+use super::docs_rs_urls;
+
+#[test]
+fn test_docs_rs_urls() {
+    let markdown = indoc! {r#"
+        See [`Foo`](https://docs.rs/my-crate/1.0.0/my_crate/struct.Foo.html) and [`bar`].
+
+        ![Image](https://docs.rs/my-crate/1.0.0/image.png)
+
+        Visit <https://docs.rs/my-crate> or [the repository](https://github.com/me/my-crate).
+
+        ```
+        // [not a link](https://docs.rs/my-crate/1.0.0/my_crate/fn.baz.html)
+        ```
+
+        [`bar`]: https://docs.rs/my-crate/1.0.0/my_crate/fn.bar.html
+        [`Foo` again]: https://docs.rs/my-crate/1.0.0/my_crate/struct.Foo.html
+    "#};
+
+    expect![[r#"
+        [
+            "https://docs.rs/my-crate/1.0.0/my_crate/struct.Foo.html",
+            "https://docs.rs/my-crate/1.0.0/image.png",
+            "https://docs.rs/my-crate",
+            "https://docs.rs/my-crate/1.0.0/my_crate/fn.bar.html",
+        ]
+    "#]]
+    .assert_debug_eq(&docs_rs_urls(markdown));
+}
@@ -1,12 +1,24 @@
 use std::{
     ffi::{OsStr, OsString},
+    io,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-use clap::{Parser, ValueEnum};
+use cargo_metadata::semver::Version;
+use clap::{CommandFactory, Parser, ValueEnum};
 use clap_cargo::style::CLAP_STYLING;
-
-use crate::config::{BoolOrString, CliConfig, PackageConfigPatch, WorkspaceConfigPatch};
+use clap_complete::Shell;
+
+use crate::{
+    check_links::{CheckLinksOptions, DEFAULT_TIMEOUT},
+    config::{
+        BadgeStyle, BoolOrString, CliConfig, FeatureFormat, LinkResolutionMode, PackageConfigPatch,
+        PrintConfigFormat, ReadmeTarget, SortFeatures, WorkspaceConfigPatch,
+    },
+    hook::HookOptions,
+    pretty_log::LogFormat,
+};
 
 pub struct Cli {
     pub cfg: CliConfig,
@@ -24,73 +36,224 @@ impl Cli {
             // cli
             print_supported_toolchain,
             color,
+            log_format,
+            ref output_json,
             verbose,
             quiet,
             quiet_cargo,
             ref manifest_path,
             print_config,
+            print_config_format,
+            config_schema,
+            print_links,
+            print_link_index,
+            print_feature_docs,
+            print_diff,
+            show_diff,
+            jobs,
+            force_rebuild,
+            cache_rustdoc_json,
+            ref since_version,
+            ref since_git_tag,
+            exclude_prereleases,
+            dry_run,
+            ref dry_run_output_dir,
             // workspace
             ref package,
             workspace,
             ref exclude,
+            ref exclude_dir,
+            ref workspace_readme,
+            ref workspace_readme_section_name,
+            ref profile,
             // package
-            command,
+            ref command,
             ref feature_label,
+            ref default_marker,
+            sort_features,
+            feature_format,
+            ref feature_comment_prefix,
+            ref in_between_comment_prefix,
+            ref features_file,
+            include_optional_deps,
+            show_feature_deps,
+            show_feature_rdeps,
             ref feature_section_name,
             ref crate_section_name,
+            ref section_heading,
+            ref section_delimiter_template,
             shrink_headings,
+            strip_hidden_tests,
+            warn_unknown_lang,
+            ref source_module,
+            no_link_resolution,
+            no_warn_unresolved_links,
+            error_on_unresolved_links,
             link_to_latest,
+            ref docs_rs_url,
+            ref std_docs_url,
+            no_check_publish,
+            inline_links,
+            msrv_badge,
+            badge_style,
+            show_cfg_annotations,
+            ref cfg_annotation_template,
             document_private_items,
+            ref rustdoc_args,
             no_deps,
             check,
             allow_missing_section,
             allow_dirty,
             allow_staged,
+            no_vcs,
+            keep_going,
             ref features,
             all_features,
             no_default_features,
             ref hidden_features,
             ref target_selection,
             ref toolchain,
+            ref toolchain_file,
+            no_rustup,
+            strict_rustdoc_version,
             ref target,
             ref target_dir,
             ref readme_path,
+            ref readme_paths,
+            ref readme_template,
+            ref section_encoding,
             ..
         } = *args;
 
         Self {
             cfg: CliConfig {
+                completions: match command {
+                    Some(Command::Completions { shell }) => Some(*shell),
+                    _ => None,
+                },
+                init: matches!(command, Some(Command::Init)),
+                clean: matches!(command, Some(Command::Clean)),
+                install_hook: match command {
+                    Some(Command::InstallHook { force, uninstall_hook, hook_args }) => {
+                        Some(HookOptions {
+                            force: *force,
+                            uninstall: *uninstall_hook,
+                            args: hook_args.clone(),
+                        })
+                    }
+                    _ => None,
+                },
+                check_links: match command {
+                    Some(Command::CheckLinks { error_on_broken_links, timeout }) => {
+                        Some(CheckLinksOptions {
+                            error_on_broken_links: *error_on_broken_links,
+                            timeout: timeout.map_or(DEFAULT_TIMEOUT, Duration::from_secs),
+                        })
+                    }
+                    _ => None,
+                },
                 print_supported_toolchain,
                 print_config,
+                print_config_format: print_config_format.unwrap_or_default(),
+                config_schema,
+                print_links,
+                print_link_index,
+                print_feature_docs,
+                print_diff,
+                show_diff,
                 color: match color.unwrap_or(ColorChoice::Auto) {
                     ColorChoice::Auto => anstream::ColorChoice::Auto,
                     ColorChoice::Always => anstream::ColorChoice::Always,
                     ColorChoice::Never => anstream::ColorChoice::Never,
                 },
+                log_format: log_format.unwrap_or_default(),
+                output_json: output_json.clone(),
                 verbose,
                 quiet,
                 quiet_cargo: quiet || quiet_cargo,
                 manifest_path: manifest_path.clone(),
+                jobs: jobs.unwrap_or(0),
+                force_rebuild,
+                cache_rustdoc_json,
+                since_version: since_version.clone(),
+                since_git_tag: since_git_tag.clone(),
+                exclude_prereleases,
+                dry_run,
+                dry_run_output_dir: dry_run_output_dir.clone(),
             },
             workspace_patch: WorkspaceConfigPatch {
                 package: (!package.is_empty()).then(|| package.clone()),
                 workspace: workspace.then_some(true),
                 exclude: (!exclude.is_empty()).then(|| exclude.clone()),
+                exclude_dir: (!exclude_dir.is_empty()).then(|| exclude_dir.clone()),
+                workspace_readme: workspace_readme.clone(),
+                workspace_readme_section_name: workspace_readme_section_name.clone(),
+                default_profile: profile.clone(),
             },
             package_patch: PackageConfigPatch {
-                feature_into_crate: command.map(|c| c == Command::FeatureIntoCrate),
-                crate_into_readme: command.map(|c| c == Command::CrateIntoReadme),
+                feature_into_crate: command
+                    .as_ref()
+                    .filter(|c| {
+                        !matches!(c, Command::Init | Command::Clean | Command::CheckLinks { .. })
+                    })
+                    .map(|c| *c == Command::FeatureIntoCrate),
+                crate_into_readme: command
+                    .as_ref()
+                    .filter(|c| {
+                        !matches!(c, Command::Init | Command::Clean | Command::CheckLinks { .. })
+                    })
+                    .map(|c| *c == Command::CrateIntoReadme),
                 feature_label: feature_label.clone(),
+                default_marker: default_marker.clone(),
+                sort_features,
+                feature_format,
+                feature_comment_prefix: feature_comment_prefix.clone(),
+                in_between_comment_prefix: in_between_comment_prefix.clone(),
+                features_file: features_file.clone(),
+                include_optional_deps: include_optional_deps.then_some(true),
+                show_feature_deps: show_feature_deps.then_some(true),
+                show_feature_rdeps: show_feature_rdeps.then_some(true),
                 feature_section_name: feature_section_name.clone(),
                 crate_section_name: crate_section_name.clone(),
+                section_heading: section_heading.clone(),
+                section_delimiter_template: (!section_delimiter_template.is_empty())
+                    .then(|| section_delimiter_template.clone()),
                 shrink_headings,
+                strip_hidden_tests,
+                warn_unknown_lang: warn_unknown_lang.then_some(true),
+                source_module: source_module.clone(),
+                no_link_resolution: no_link_resolution.then_some(true),
+                unresolved_links: if error_on_unresolved_links {
+                    Some(LinkResolutionMode::Error)
+                } else if no_warn_unresolved_links {
+                    Some(LinkResolutionMode::Ignore)
+                } else {
+                    None
+                },
                 link_to_latest: link_to_latest.then_some(true),
+                docs_rs_url: docs_rs_url.clone(),
+                std_docs_url: std_docs_url.clone(),
+                no_check_publish: no_check_publish.then_some(true),
+                inline_links: inline_links.then_some(true),
+                msrv_badge: msrv_badge.then_some(true),
+                badge_style,
+                show_cfg_annotations,
+                cfg_annotation_template: cfg_annotation_template.clone(),
                 document_private_items: document_private_items.then_some(true),
+                rustdoc_args: (!rustdoc_args.is_empty()).then(|| {
+                    rustdoc_args
+                        .iter()
+                        .flat_map(|args| args.split_whitespace())
+                        .map(String::from)
+                        .collect()
+                }),
                 no_deps: no_deps.then_some(true),
                 check: check.then_some(true),
                 allow_missing_section: allow_missing_section.then_some(true),
                 allow_dirty: allow_dirty.then_some(true),
                 allow_staged: allow_staged.then_some(true),
+                no_vcs: no_vcs.then_some(true),
+                keep_going: keep_going.then_some(true),
                 features: (!features.is_empty()).then(|| {
                     // features are already comma separated, we still need to make them space separated
                     features.iter().flat_map(|f| f.split(' ').map(|s| s.to_string())).collect()
@@ -110,14 +273,29 @@ impl Cli {
                     None => BoolOrString::Bool(true),
                 }),
                 toolchain: toolchain.clone(),
+                toolchain_file: toolchain_file.clone(),
+                no_rustup: no_rustup.then_some(true),
+                strict_rustdoc_version: strict_rustdoc_version.then_some(true),
                 target: target.clone(),
                 target_dir: target_dir.clone(),
                 readme_path: readme_path.clone(),
+                readme_paths: (!readme_paths.is_empty())
+                    .then(|| readme_paths.iter().cloned().map(ReadmeTarget::Path).collect()),
+                readme_template: readme_template.clone(),
+                section_encoding: section_encoding.clone(),
             },
         }
     }
 }
 
+/// Prints the completion script for `shell` to stdout.
+///
+/// The script completes the `cargo-insert-docs` binary. When invoked as `cargo insert-docs`
+/// the completion is up to cargo's own completion script.
+pub fn print_completions(shell: Shell) {
+    clap_complete::generate(shell, &mut Args::command(), "cargo-insert-docs", &mut io::stdout());
+}
+
 /// <https://doc.rust-lang.org/cargo/reference/external-tools.html#custom-subcommands>
 ///
 /// When executing `cargo-insert-docs` as a cargo subcommand
@@ -185,9 +363,67 @@ struct Args {
     /// Formatting of the feature label [default: "**`{feature}`**"]
     ///
     /// When inserting feature documentation into the crate documentation.
-    #[arg(global = true, long)]
+    /// `{feature}` is replaced by the feature name,
+    /// `{default_marker}` by "*" if the feature is enabled by default and
+    /// `{since}` by the version of a `## since: 1.2.0` comment.
+    #[arg(global = true, long, verbatim_doc_comment)]
     feature_label: Option<String>,
 
+    /// Text after the label of a default feature [default: "*(enabled by default)*"]
+    ///
+    /// An empty marker is not shown.
+    #[arg(global = true, long, value_name = "MARKER")]
+    default_marker: Option<String>,
+
+    /// Order of the features in the feature documentation [default: "declaration"]
+    ///
+    /// Documentation comments between features (`#!`) move with the feature that follows them.
+    #[arg(global = true, long, value_name = "ORDER", value_enum)]
+    sort_features: Option<SortFeatures>,
+
+    /// Format of the feature documentation [default: "list"]
+    ///
+    /// A table has a row for each feature. Documentation comments between features (`#!`)
+    /// break the table into multiple tables.
+    #[arg(global = true, long, value_name = "FORMAT", value_enum)]
+    feature_format: Option<FeatureFormat>,
+
+    /// Prefix of the `Cargo.toml` comments that document a feature [default: "##"]
+    ///
+    /// Must start with `#`, like `# doc:`.
+    #[arg(global = true, long, value_name = "PREFIX")]
+    feature_comment_prefix: Option<String>,
+
+    /// Prefix of the `Cargo.toml` comments between features [default: "#!"]
+    ///
+    /// Must start with `#`, like `# prose:`.
+    #[arg(global = true, long, value_name = "PREFIX")]
+    in_between_comment_prefix: Option<String>,
+
+    /// Read the feature documentation from a features file instead of the `Cargo.toml` comments
+    ///
+    /// The file documents features like `[features.my-feature]` with `docs = "..."`.
+    #[arg(global = true, long, value_name = "PATH")]
+    features_file: Option<PathBuf>,
+
+    /// Document optional dependencies as features
+    ///
+    /// Uses the `##` comments in front of the dependency in the `Cargo.toml`.
+    #[arg(global = true, long)]
+    include_optional_deps: bool,
+
+    /// Add a line listing the features a feature enables
+    ///
+    /// Like `*Enables: `bar`, `baz`.*`, optional dependencies (`dep:`) are not listed.
+    #[arg(global = true, long)]
+    show_feature_deps: bool,
+
+    /// Add a line listing the features that enable a feature
+    ///
+    /// Like `*Required by: `full`, `async`.*`.
+    #[arg(global = true, long)]
+    show_feature_rdeps: bool,
+
     /// Feature documentation section name [default: "feature documentation"]
     #[arg(global = true, long, value_name = "NAME")]
     feature_section_name: Option<String>,
@@ -196,6 +432,21 @@ struct Args {
     #[arg(global = true, long, value_name = "NAME")]
     crate_section_name: Option<String>,
 
+    /// Text of the readme heading whose content is the crate documentation section
+    ///
+    /// The section ends at the next heading of the same or a higher level,
+    /// the headings of the crate documentation are shrunk below the heading.
+    /// Section markers in the readme take precedence.
+    #[arg(global = true, long, value_name = "TEXT", conflicts_with = "crate_section_name")]
+    section_heading: Option<String>,
+
+    /// Start and end markers of sections [default: "<!-- {name} start -->" "<!-- {name} end -->"]
+    ///
+    /// `{name}` is replaced by the section name. Markers that are not html comments
+    /// like `[//]: # (begin {name})` must be on their own line.
+    #[arg(global = true, long, num_args = 2, value_names = ["START", "END"])]
+    section_delimiter_template: Vec<String>,
+
     /// Shrink headings by this amount [default: 1]
     ///
     /// Shrinks headings when inserting documentation into the readme by
@@ -203,6 +454,42 @@ struct Args {
     #[arg(global = true, long, value_name = "AMOUNT")]
     shrink_headings: Option<i8>,
 
+    /// Remove hidden lines (`# `) from rust code blocks [default: true]
+    ///
+    /// When inserting documentation into the readme.
+    #[arg(global = true, long, value_name = "BOOL")]
+    strip_hidden_tests: Option<bool>,
+
+    /// Warn about code blocks with an unknown language
+    ///
+    /// A language that is neither rust nor a common other language like `python` or `toml`
+    /// is likely a misspelled `rust`. Such code blocks don't get their hidden lines removed.
+    #[arg(global = true, long)]
+    warn_unknown_lang: bool,
+
+    /// Take the crate documentation from this module instead of the crate root
+    ///
+    /// The path is relative to the crate root, like `user` or `network::client`.
+    #[arg(global = true, long, value_name = "PATH")]
+    source_module: Option<String>,
+
+    /// Don't build the rustdoc json to resolve links
+    ///
+    /// The crate docs are read from the source instead, which doesn't need a nightly toolchain.
+    /// Intra-doc links stay as they are written.
+    #[arg(global = true, long)]
+    no_link_resolution: bool,
+
+    /// Don't warn about intra-doc links that fail to resolve
+    ///
+    /// Like short links that rustdoc resolves but we can't. The links are left as they are.
+    #[arg(global = true, long, conflicts_with = "error_on_unresolved_links")]
+    no_warn_unresolved_links: bool,
+
+    /// Error when an intra-doc link fails to resolve
+    #[arg(global = true, long)]
+    error_on_unresolved_links: bool,
+
     #[expect(rustdoc::bare_urls)]
     /// Link to the "latest" version on docs.rs
     ///
@@ -211,6 +498,57 @@ struct Args {
     #[arg(global = true, long, verbatim_doc_comment)]
     link_to_latest: bool,
 
+    #[expect(rustdoc::bare_urls)]
+    /// Url of the documentation of a crate [default: "https://docs.rs/{package}/{version}/{name}/"]
+    ///
+    /// `{package}`, `{version}` and `{name}` are replaced by the package name, version and crate name.
+    /// `{version}` is "latest" when `--link-to-latest` applies.
+    #[arg(global = true, long, value_name = "URL", verbatim_doc_comment)]
+    docs_rs_url: Option<String>,
+
+    #[expect(rustdoc::bare_urls)]
+    /// Url of the documentation of `core`, `alloc` and `std` [default: "https://doc.rust-lang.org/{name}/"]
+    ///
+    /// `{name}` is replaced by the crate name.
+    #[arg(global = true, long, value_name = "URL")]
+    std_docs_url: Option<String>,
+
+    /// Don't warn about docs.rs links to packages that are not published to crates.io
+    ///
+    /// A package is considered unpublished if its `publish` field doesn't allow crates.io.
+    #[arg(global = true, long)]
+    no_check_publish: bool,
+
+    /// Turn reference-style links into inline links
+    ///
+    /// When inserting documentation into the readme.
+    /// The link reference definitions are removed.
+    #[arg(global = true, long)]
+    inline_links: bool,
+
+    /// Add a minimum supported rust version badge to the readme
+    ///
+    /// The version is the `rust-version` of the package. The badge is added
+    /// at the top of the crate documentation section.
+    #[arg(global = true, long)]
+    msrv_badge: bool,
+
+    /// Style of the shields.io badges [default: "flat"]
+    #[arg(global = true, long, value_name = "STYLE", value_enum)]
+    badge_style: Option<BadgeStyle>,
+
+    /// Add an annotation after links to items with a `#[doc(cfg(...))]` attribute [default: true]
+    ///
+    /// When inserting documentation into the readme.
+    #[arg(global = true, long, value_name = "BOOL")]
+    show_cfg_annotations: Option<bool>,
+
+    /// Formatting of the cfg annotation [default: "*Available on {cfg} only.*"]
+    ///
+    /// `{cfg}` is replaced by the cfg, for example "feature `foo`".
+    #[arg(global = true, long, value_name = "TEMPLATE")]
+    cfg_annotation_template: Option<String>,
+
     /// Prints a supported nightly toolchain
     #[arg(global = true, long)]
     print_supported_toolchain: bool,
@@ -219,10 +557,43 @@ struct Args {
     #[arg(global = true, long)]
     print_config: bool,
 
+    /// Output format of `--print-config` [default: "toml"]
+    #[arg(global = true, long, value_name = "FORMAT", value_enum)]
+    print_config_format: Option<PrintConfigFormat>,
+
+    /// Prints a JSON Schema of the `Cargo.toml` configuration
+    #[arg(global = true, long)]
+    config_schema: bool,
+
+    /// Prints the intra-doc links of the crate docs and what they resolve to
+    ///
+    /// No files are modified. With `--output-json` the links are written as JSON instead.
+    #[arg(global = true, long)]
+    print_links: bool,
+
+    /// Prints the path and url of every item of the crate that links can resolve to
+    ///
+    /// `doc(hidden)` items are left out. No files are modified.
+    /// With `--output-json` the items are written as JSON instead.
+    #[arg(global = true, long)]
+    print_link_index: bool,
+
+    /// Prints the feature documentation of each package
+    ///
+    /// No files are modified. Useful to try out settings like `--feature-label` or `--sort-features`.
+    #[arg(global = true, long)]
+    print_feature_docs: bool,
+
     /// Document private items
     #[arg(global = true, help_heading = heading::CARGO_DOC_OPTIONS, long)]
     document_private_items: bool,
 
+    /// Extra arguments to pass to rustdoc, can be used multiple times
+    ///
+    /// Each value is split on whitespace, so `--rustdoc-args "--cfg docsrs"` passes two arguments.
+    #[arg(global = true, help_heading = heading::CARGO_DOC_OPTIONS, long, value_name = "ARGS", allow_hyphen_values = true)]
+    rustdoc_args: Vec<String>,
+
     /// Don't build documentation for dependencies
     #[arg(global = true, help_heading = heading::CARGO_DOC_OPTIONS, long)]
     no_deps: bool,
@@ -234,6 +605,22 @@ struct Args {
     #[arg(global = true, help_heading = heading::MODE_SELECTION, long, verbatim_doc_comment)]
     check: bool,
 
+    /// Prints a diff of what would change instead of writing to files
+    #[arg(global = true, help_heading = heading::MODE_SELECTION, long)]
+    print_diff: bool,
+
+    /// Prints a diff of stale documentation with `--check` or of the changes written otherwise
+    #[arg(global = true, help_heading = heading::MODE_SELECTION, long, conflicts_with = "print_diff")]
+    show_diff: bool,
+
+    /// Writes the changes into a mirror directory instead of the files themselves
+    #[arg(global = true, help_heading = heading::MODE_SELECTION, long, conflicts_with_all = ["print_diff", "check"])]
+    dry_run: bool,
+
+    /// Directory for the files written by `--dry-run` [default: target/insert-docs/dry-run]
+    #[arg(global = true, help_heading = heading::MODE_SELECTION, long, value_name = "PATH", requires = "dry_run")]
+    dry_run_output_dir: Option<PathBuf>,
+
     /// Don't error when a section is missing
     #[arg(global = true, help_heading = heading::ERROR_BEHAVIOR, long)]
     allow_missing_section: bool,
@@ -246,10 +633,37 @@ struct Args {
     #[arg(global = true, help_heading = heading::ERROR_BEHAVIOR, long)]
     allow_staged: bool,
 
+    /// Skip the version control check entirely
+    ///
+    /// Unlike `--allow-dirty` this doesn't look for a git repository at all.
+    #[arg(global = true, help_heading = heading::ERROR_BEHAVIOR, long)]
+    no_vcs: bool,
+
+    /// Keep going after a package fails and report all errors at the end
+    ///
+    /// Without this flag an error while preparing a package stops the command before any
+    /// package is documented.
+    #[arg(global = true, help_heading = heading::ERROR_BEHAVIOR, long)]
+    keep_going: bool,
+
     /// Coloring [default: "auto"]
     #[arg(global = true, help_heading = heading::MESSAGE_OPTIONS, long, value_name = "WHEN", value_enum)]
     color: Option<ColorChoice>,
 
+    /// Format of the printed messages [default: "pretty"]
+    ///
+    /// With "json" every message is printed as a JSON object on its own line,
+    /// followed by a summary of the error and warning counts.
+    #[arg(global = true, help_heading = heading::MESSAGE_OPTIONS, long, value_name = "FORMAT", value_enum, verbatim_doc_comment)]
+    log_format: Option<LogFormat>,
+
+    /// Write a JSON summary of all packages to this file, or to stdout if it is "-"
+    ///
+    /// This is written after all packages ran or when the run stops early,
+    /// and is independent of `--quiet`.
+    #[arg(global = true, help_heading = heading::MESSAGE_OPTIONS, long, value_name = "FILE")]
+    output_json: Option<PathBuf>,
+
     /// Print more verbose messages
     #[arg(global = true, help_heading = heading::MESSAGE_OPTIONS, short, long, action = clap::ArgAction::Count)]
     verbose: u8,
@@ -274,6 +688,22 @@ struct Args {
     #[arg(global = true, help_heading = heading::PACKAGE_SELECTION, long, value_name = "SPEC", requires = "workspace")]
     exclude: Vec<String>,
 
+    /// Exclude packages in this directory from documenting
+    #[arg(global = true, help_heading = heading::PACKAGE_SELECTION, long, value_name = "DIR", requires = "workspace")]
+    exclude_dir: Vec<PathBuf>,
+
+    /// Only document packages with a version greater than this
+    #[arg(global = true, help_heading = heading::PACKAGE_SELECTION, long, value_name = "VERSION", conflicts_with = "since_git_tag")]
+    since_version: Option<Version>,
+
+    /// Only document packages with a version greater than at this git tag
+    #[arg(global = true, help_heading = heading::PACKAGE_SELECTION, long, value_name = "TAG")]
+    since_git_tag: Option<String>,
+
+    /// Do not document packages with a pre-release version
+    #[arg(global = true, help_heading = heading::PACKAGE_SELECTION, long)]
+    exclude_prereleases: bool,
+
     /// Space or comma separated list of features to activate
     #[arg(global = true, help_heading = heading::FEATURE_SELECTION, long, short = 'F', value_delimiter = ',')]
     features: Vec<String>,
@@ -304,6 +734,23 @@ struct Args {
     #[arg(global = true, help_heading = heading::COMPILATION_OPTIONS, long, verbatim_doc_comment)]
     toolchain: Option<String>,
 
+    /// Read the toolchain from the `channel` of a `rust-toolchain.toml`
+    ///
+    /// Without this flag and `--toolchain` a nightly channel of the
+    /// `rust-toolchain.toml` in the workspace root is used.
+    #[arg(global = true, help_heading = heading::COMPILATION_OPTIONS, long, value_name = "PATH")]
+    toolchain_file: Option<PathBuf>,
+
+    /// Invoke `cargo` directly instead of going through rustup's `+toolchain`
+    #[arg(global = true, help_heading = heading::COMPILATION_OPTIONS, long)]
+    no_rustup: bool,
+
+    /// Error when the rustdoc json version differs from the supported one
+    ///
+    /// By default a rustdoc json version that differs by one is parsed anyway with a warning.
+    #[arg(global = true, help_heading = heading::COMPILATION_OPTIONS, long)]
+    strict_rustdoc_version: bool,
+
     /// Target triple to document
     #[arg(global = true, help_heading = heading::COMPILATION_OPTIONS, long, value_name = "TRIPLE")]
     target: Option<String>,
@@ -312,6 +759,24 @@ struct Args {
     #[arg(global = true, help_heading = heading::COMPILATION_OPTIONS, long, value_name = "DIRECTORY")]
     target_dir: Option<PathBuf>,
 
+    /// Rebuild the rustdoc json even if it is cached
+    #[arg(global = true, help_heading = heading::COMPILATION_OPTIONS, long)]
+    force_rebuild: bool,
+
+    /// Keep the rustdoc json of previous builds and reuse it when the package didn't change
+    ///
+    /// Changes are detected in the files of the package and its path dependencies,
+    /// the cargo configuration, the toolchain version and the settings.
+    /// Files outside of these packages are not tracked.
+    #[arg(global = true, help_heading = heading::COMPILATION_OPTIONS, long)]
+    cache_rustdoc_json: bool,
+
+    /// Number of packages to build rustdoc json for in parallel, 0 uses all CPUs [default: 0]
+    ///
+    /// When building in parallel, cargo's output is only shown if a build fails.
+    #[arg(global = true, help_heading = heading::COMPILATION_OPTIONS, long, short = 'j', value_name = "N")]
+    jobs: Option<usize>,
+
     /// Path to Cargo.toml
     #[arg(global = true, help_heading = heading::MANIFEST_OPTIONS, long, value_name = "PATH")]
     manifest_path: Option<PathBuf>,
@@ -321,14 +786,93 @@ struct Args {
     /// This defaults to the `readme` field as specified in the `Cargo.toml`.
     #[arg(global = true, help_heading = heading::MANIFEST_OPTIONS, long, value_name = "PATH")]
     readme_path: Option<PathBuf>,
+
+    /// Comma separated readme paths relative to the package manifest
+    ///
+    /// The crate documentation is inserted into each of them.
+    #[arg(global = true, help_heading = heading::MANIFEST_OPTIONS, long, value_delimiter = ',', value_name = "PATHS")]
+    readme_paths: Vec<PathBuf>,
+
+    /// Readme template path relative to the package manifest
+    ///
+    /// If set, the readme is generated from this template instead of inserting into its sections.
+    /// The placeholders `{{ crate_docs }}` and `{{ feature_docs }}` will be replaced.
+    /// Other `{{ }}` placeholders are left as they are with a warning.
+    #[arg(global = true, help_heading = heading::MANIFEST_OPTIONS, long, value_name = "PATH")]
+    readme_template: Option<PathBuf>,
+
+    /// Encoding of the readme and readme template [default: "utf-8"]
+    ///
+    /// Any label of the WHATWG Encoding Standard is accepted, like `windows-1252` or `latin1`.
+    #[arg(global = true, help_heading = heading::MANIFEST_OPTIONS, long, value_name = "ENCODING")]
+    section_encoding: Option<String>,
+
+    /// Workspace readme path relative to the workspace root
+    ///
+    /// If set, the crate documentation of all documented packages is inserted into the
+    /// workspace documentation section of this readme, each under a heading with its name.
+    #[arg(global = true, help_heading = heading::MANIFEST_OPTIONS, long, value_name = "PATH")]
+    workspace_readme: Option<PathBuf>,
+
+    /// Workspace documentation section name [default: "workspace documentation"]
+    #[arg(global = true, help_heading = heading::MANIFEST_OPTIONS, long, value_name = "NAME")]
+    workspace_readme_section_name: Option<String>,
+
+    /// Profile from `[workspace.metadata.insert-docs.profiles]` to apply
+    ///
+    /// Its settings overwrite the ones of the manifests but not the ones of the command line.
+    /// Defaults to the `default-profile` setting.
+    #[arg(global = true, help_heading = heading::MANIFEST_OPTIONS, long, value_name = "NAME")]
+    profile: Option<String>,
 }
 
-#[derive(clap::Subcommand, Clone, Copy, PartialEq, Eq)]
+#[derive(clap::Subcommand, Clone, PartialEq, Eq)]
 enum Command {
     /// Only inserts feature documentation into crate documentation
     FeatureIntoCrate,
     /// Only inserts crate documentation into the readme file
     CrateIntoReadme,
+    /// Adds empty sections to the crate documentation and the readme file
+    ///
+    /// Files that already have the section are skipped.
+    /// With `--check` the changes are printed instead of written.
+    Init,
+    /// Removes the content of the sections, leaving only the markers
+    ///
+    /// With `--check` it errors if a section is not empty.
+    Clean,
+    /// Installs a git pre-commit hook that runs `cargo insert-docs --check`
+    ///
+    /// The hook is written to the `core.hooksPath` directory, or the `hooks` directory of the repository.
+    InstallHook {
+        /// Overwrite or remove a pre-commit hook that was not installed by cargo-insert-docs
+        #[arg(long)]
+        force: bool,
+        /// Remove the pre-commit hook instead of installing it
+        #[arg(long)]
+        uninstall_hook: bool,
+        /// Additional arguments for `cargo insert-docs --check` in the hook, like "--workspace"
+        #[arg(long, value_name = "ARGS", allow_hyphen_values = true)]
+        hook_args: Option<String>,
+    },
+    /// Checks that the docs.rs links of the crate documentation exist
+    ///
+    /// Sends a HEAD request for each distinct link, `--jobs` at once.
+    /// Broken links are warnings because docs.rs may be temporarily unavailable.
+    /// Requires the `check-links` feature.
+    CheckLinks {
+        /// Error instead of warn about broken links
+        #[arg(long)]
+        error_on_broken_links: bool,
+        /// Timeout of each request in seconds [default: 10]
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
+    /// Prints a shell completion script
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
 }
 
 #[derive(clap::Args)]
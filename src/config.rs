@@ -3,54 +3,177 @@
 //!
 //! See `../docs/config.md`.
 
+mod schema;
 #[cfg(test)]
 mod tests;
 
 use std::{
-    collections::{HashMap, HashSet},
-    fmt,
-    path::PathBuf,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{self, Write as _},
+    path::{Path, PathBuf},
 };
 
 use anstream::ColorChoice;
-use cargo_metadata::Target;
-use color_eyre::eyre::{Result, WrapErr as _};
+use anstyle::{AnsiColor, Color, Style};
+use cargo_metadata::{Target, semver::Version};
+use clap::ValueEnum;
+use clap_complete::Shell;
+use color_eyre::eyre::{Result, WrapErr as _, bail};
 use macro_rules_attribute::derive;
 use serde::{
     Deserialize, Serialize, Serializer,
     de::{DeserializeOwned, IgnoredAny},
 };
 
+use crate::{
+    check_links::CheckLinksOptions,
+    hook::HookOptions,
+    markdown::{DEFAULT_SECTION_END_TEMPLATE, DEFAULT_SECTION_START_TEMPLATE},
+    pretty_log::LogFormat,
+};
+
+pub use schema::json_schema;
+
 pub const DEFAULT_FEATURE_LABEL: &str = "**`{feature}`**";
+pub const DEFAULT_DEFAULT_MARKER: &str = "*(enabled by default)*";
+pub const DEFAULT_FEATURE_COMMENT_PREFIX: &str = "##";
+pub const DEFAULT_IN_BETWEEN_COMMENT_PREFIX: &str = "#!";
 pub const DEFAULT_FEATURE_SECTION_NAME: &str = "feature documentation";
 pub const DEFAULT_CRATE_SECTION_NAME: &str = "crate documentation";
+pub const DEFAULT_WORKSPACE_README_SECTION_NAME: &str = "workspace documentation";
 pub const DEFAULT_TOOLCHAIN: &str = "nightly-2026-06-24";
 pub const DEFAULT_SHRINK_HEADINGS: i8 = 1;
+pub const DEFAULT_CFG_ANNOTATION_TEMPLATE: &str = "*Available on {cfg} only.*";
+pub const DEFAULT_DOCS_RS_URL: &str = "https://docs.rs/{package}/{version}/{name}/";
+pub const DEFAULT_STD_DOCS_URL: &str = "https://doc.rust-lang.org/{name}/";
 
 macro_rules! Fields {
     (
         $(#[$meta:meta])*
         $vis:vis struct $ident:ident {
-            $($field_vis:vis $field:ident: $field_ty:ty),* $(,)?
+            $(
+                $(#[doc = $doc:literal])*
+                $field_vis:vis $field:ident: $field_ty:ty
+            ),* $(,)?
         }
     ) => {
         impl $ident {
             const FIELDS: &[&str] = &[
                 $(stringify!($field),)*
             ];
+
+            /// Returns the JSON Schema properties of the fields.
+            ///
+            /// `defaults` is the resolved default configuration.
+            fn schema_properties(defaults: &serde_json::Value) -> Vec<(String, schema::Property)> {
+                vec![$({
+                    let mut property = <$field_ty as schema::ConfigSchema>::property();
+                    property.description = schema::description(&[$($doc),*]);
+                    property.default =
+                        defaults.get(stringify!($field)).filter(|v| !v.is_null()).cloned();
+                    (stringify!($field).replace('_', "-"), property)
+                },)*]
+            }
         }
     };
 }
 
 /// The resolved configuration for the command line interface.
 pub struct CliConfig {
+    pub completions: Option<Shell>,
+    pub init: bool,
+    pub clean: bool,
+    pub install_hook: Option<HookOptions>,
+    pub check_links: Option<CheckLinksOptions>,
     pub print_supported_toolchain: bool,
     pub print_config: bool,
+    pub print_config_format: PrintConfigFormat,
+    pub config_schema: bool,
+    pub print_links: bool,
+    pub print_link_index: bool,
+    pub print_feature_docs: bool,
+    pub print_diff: bool,
+    pub show_diff: bool,
     pub color: ColorChoice,
+    pub log_format: LogFormat,
+    pub output_json: Option<PathBuf>,
     pub verbose: u8,
     pub quiet: bool,
     pub quiet_cargo: bool,
     pub manifest_path: Option<PathBuf>,
+    pub jobs: usize,
+    pub force_rebuild: bool,
+    pub cache_rustdoc_json: bool,
+    pub since_version: Option<Version>,
+    pub since_git_tag: Option<String>,
+    pub exclude_prereleases: bool,
+    pub dry_run: bool,
+    pub dry_run_output_dir: Option<PathBuf>,
+}
+
+impl CliConfig {
+    /// Reads the package settings from the `CARGO_INSERT_DOCS_*` environment variables,
+    /// like `CARGO_INSERT_DOCS_TOOLCHAIN` for `toolchain`.
+    pub fn from_env() -> Result<PackageConfigPatch> {
+        package_patch_from_vars(|name| std::env::var(name).ok())
+    }
+}
+
+/// The prefix of the environment variables read by [`CliConfig::from_env`].
+pub const ENV_PREFIX: &str = "CARGO_INSERT_DOCS_";
+
+/// Reads the package settings from variables named like `CARGO_INSERT_DOCS_ALLOW_DIRTY`.
+///
+/// A value is interpreted as the first of these that fits the type of the field:
+/// a JSON value, a bool like `1` or `no`, a comma separated list or a string.
+/// Like in the cli, a `rustdoc-args` value that is not JSON is split on whitespace.
+fn package_patch_from_vars(var: impl Fn(&str) -> Option<String>) -> Result<PackageConfigPatch> {
+    let mut fields = serde_json::Map::new();
+
+    for field in PackageConfigPatch::FIELDS {
+        let name = format!("{ENV_PREFIX}{}", field.to_uppercase());
+
+        let Some(value) = var(&name) else {
+            continue;
+        };
+
+        let as_bool = match value.trim().to_lowercase().as_str() {
+            "1" | "yes" | "on" | "true" => Some(true),
+            "0" | "no" | "off" | "false" => Some(false),
+            _ => None,
+        };
+
+        let candidates = [
+            serde_json::from_str(&value).ok(),
+            (*field == "rustdoc_args").then(|| value.split_whitespace().collect()),
+            as_bool.map(serde_json::Value::Bool),
+            Some(value.split(',').map(str::trim).collect()),
+            Some(serde_json::Value::String(value.clone())),
+        ];
+
+        let key = field.replace('_', "-");
+
+        let Some(candidate) = candidates.into_iter().flatten().find(|candidate| {
+            let object = serde_json::Map::from_iter([(key.clone(), candidate.clone())]);
+            serde_json::from_value::<PackageConfigPatch>(object.into()).is_ok()
+        }) else {
+            bail!("invalid value {value:?} for environment variable {name}");
+        };
+
+        fields.insert(key, candidate);
+    }
+
+    serde_json::from_value(fields.into()).wrap_err("failed to read environment variables")
+}
+
+/// The output format of `--print-config`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PrintConfigFormat {
+    /// Colored toml tables
+    #[default]
+    Toml,
+    /// A single JSON object
+    Json,
 }
 
 /// The resolved configuration for the workspace.
@@ -59,34 +182,151 @@ pub struct WorkspaceConfig {
     pub package: Vec<String>,
     pub workspace: bool,
     pub exclude: Vec<String>,
+    pub exclude_dir: Vec<PathBuf>,
+    pub workspace_readme: Option<PathBuf>,
+    pub workspace_readme_section_name: String,
+    pub default_profile: Option<String>,
 }
 
 /// Reads configuration parameters from [`cargo_metadata::Metadata::workspace_metadata`].
 pub fn read_workspace_config(
     json: &serde_json::Value,
-) -> Result<(WorkspaceConfigPatch, PackageConfigPatch)> {
+) -> Result<(WorkspaceConfigPatch, PackageConfigPatch, Profiles)> {
     let wrk: WorkspaceConfigPatch = metadata_json(json)?;
     let pkg: PackageConfigPatch = metadata_json(json)?;
+    let ProfilesTable { profiles } = metadata_json(json)?;
     let fields: HashMap<String, IgnoredAny> = metadata_json(json)?;
-    warn_about_unused_fields(fields, &[WorkspaceConfigPatch::FIELDS, PackageConfigPatch::FIELDS]);
-    Ok((wrk, pkg))
+    warn_about_unused_fields(
+        "metadata.insert-docs",
+        fields,
+        &[WorkspaceConfigPatch::FIELDS, PackageConfigPatch::FIELDS, &["profiles", "feature-docs"]],
+    );
+    Ok((wrk, pkg, profiles))
+}
+
+/// Named package configurations from `[workspace.metadata.insert-docs.profiles.<name>]`.
+pub type Profiles = BTreeMap<String, PackageConfigPatch>;
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ProfilesTable {
+    profiles: Profiles,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct DefaultProfileTable {
+    default_profile: Option<String>,
+}
+
+/// Reads `default-profile` and `profiles` from [`cargo_metadata::Package::metadata`]
+/// of the package at the workspace root, so a crate without `[workspace]` can have profiles.
+pub fn read_root_package_profiles(
+    json: &serde_json::Value,
+) -> Result<(WorkspaceConfigPatch, Profiles)> {
+    let DefaultProfileTable { default_profile } = metadata_json(json)?;
+    let ProfilesTable { profiles } = metadata_json(json)?;
+    Ok((WorkspaceConfigPatch { default_profile, ..Default::default() }, profiles))
+}
+
+/// Returns the profile called `name` or errors with the names of the available profiles.
+pub fn profile<'a>(profiles: &'a Profiles, name: &str) -> Result<&'a PackageConfigPatch> {
+    if let Some(profile) = profiles.get(name) {
+        return Ok(profile);
+    }
+
+    if profiles.is_empty() {
+        bail!(
+            "unknown profile \"{name}\", there are no profiles in `[workspace.metadata.insert-docs.profiles]`"
+        );
+    }
+
+    let available =
+        profiles.keys().map(|name| format!("\"{name}\"")).collect::<Vec<_>>().join(", ");
+    bail!("unknown profile \"{name}\", available profiles are {available}")
 }
 
 /// Reads configuration parameters from a package manifest's contents (`Cargo.toml`).
 pub fn read_package_config(toml: &str) -> Result<PackageConfigPatch> {
     let pkg: PackageConfigPatch = metadata_toml(toml)?;
     let fields: HashMap<String, IgnoredAny> = metadata_toml(toml)?;
-    warn_about_unused_fields(fields, &[PackageConfigPatch::FIELDS]);
+    warn_about_unused_fields(
+        "metadata.insert-docs",
+        fields,
+        &[PackageConfigPatch::FIELDS, &["feature-config", "default-profile", "profiles"]],
+    );
     Ok(pkg)
 }
 
+/// Settings for a single feature from `[package.metadata.insert-docs.feature-config.<feature>]`.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FeatureConfig {
+    /// Hide the feature like `hidden-features` does.
+    pub hidden: bool,
+    /// Overwrites the version of a `## since: 1.2.0` comment.
+    pub since: Option<String>,
+    /// Adds a line like `*Status: experimental.*` to the feature docs.
+    pub status: Option<String>,
+    /// Overwrites `feature-label` for this feature.
+    pub label: Option<String>,
+}
+
+impl FeatureConfig {
+    const FIELDS: &[&str] = &["hidden", "since", "status", "label"];
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct FeatureConfigTable<T> {
+    feature_config: BTreeMap<String, T>,
+}
+
+/// Reads the per-feature settings from a package manifest's contents (`Cargo.toml`).
+pub fn read_feature_config(toml: &str) -> Result<BTreeMap<String, FeatureConfig>> {
+    let FeatureConfigTable { feature_config } = metadata_toml(toml)?;
+    let FeatureConfigTable::<HashMap<String, IgnoredAny>> { feature_config: fields } =
+        metadata_toml(toml)?;
+
+    for (feature, fields) in fields {
+        let table = format!("metadata.insert-docs.feature-config.{feature}");
+        warn_about_unused_fields(&table, fields, &[FeatureConfig::FIELDS]);
+    }
+
+    Ok(feature_config)
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct FeatureDocsTable {
+    feature_docs: BTreeMap<String, String>,
+}
+
+/// Reads the feature documentation of `[workspace.metadata.insert-docs.feature-docs]`
+/// from a workspace manifest's contents (`Cargo.toml`).
+pub fn read_workspace_feature_docs(toml: &str) -> Result<BTreeMap<String, String>> {
+    let FeatureDocsTable { feature_docs } = workspace_metadata_toml(toml)?;
+    Ok(feature_docs)
+}
+
 /// Parsed configuration parameters for the workspace.
 #[derive(Default, Clone, Deserialize, Serialize, Fields!)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct WorkspaceConfigPatch {
+    /// Package(s) to document
     pub package: Option<Vec<String>>,
+    /// Document all packages in the workspace
     pub workspace: Option<bool>,
+    /// Exclude package(s) from documenting
     pub exclude: Option<Vec<String>>,
+    /// Exclude packages in these directories from documenting, relative to the workspace root
+    pub exclude_dir: Option<Vec<PathBuf>>,
+    /// Workspace readme path relative to the workspace root, the docs of all packages are inserted into it
+    pub workspace_readme: Option<PathBuf>,
+    /// Workspace documentation section name
+    pub workspace_readme_section_name: Option<String>,
+    /// Profile from `profiles` to use when `--profile` is not given
+    pub default_profile: Option<String>,
 }
 
 impl WorkspaceConfigPatch {
@@ -102,16 +342,42 @@ impl WorkspaceConfigPatch {
         if let Some(exclude) = &overwrite.exclude {
             this.exclude = Some(exclude.clone());
         }
+        if let Some(exclude_dir) = &overwrite.exclude_dir {
+            this.exclude_dir = Some(exclude_dir.clone());
+        }
+        if let Some(workspace_readme) = &overwrite.workspace_readme {
+            this.workspace_readme = Some(workspace_readme.clone());
+        }
+        if let Some(workspace_readme_section_name) = &overwrite.workspace_readme_section_name {
+            this.workspace_readme_section_name = Some(workspace_readme_section_name.clone());
+        }
+        if let Some(default_profile) = &overwrite.default_profile {
+            this.default_profile = Some(default_profile.clone());
+        }
 
         this
     }
 
     pub fn finish(self) -> WorkspaceConfig {
-        let Self { package, workspace, exclude } = self;
+        let Self {
+            package,
+            workspace,
+            exclude,
+            exclude_dir,
+            workspace_readme,
+            workspace_readme_section_name,
+            default_profile,
+        } = self;
+
         WorkspaceConfig {
             package: package.unwrap_or_default(),
             workspace: workspace.unwrap_or_default(),
             exclude: exclude.unwrap_or_default(),
+            exclude_dir: exclude_dir.unwrap_or_default(),
+            workspace_readme,
+            workspace_readme_section_name: workspace_readme_section_name
+                .unwrap_or_else(|| DEFAULT_WORKSPACE_README_SECTION_NAME.to_string()),
+            default_profile,
         }
     }
 }
@@ -122,16 +388,43 @@ pub struct PackageConfig {
     pub feature_into_crate: bool,
     pub crate_into_readme: bool,
     pub feature_label: String,
+    pub default_marker: String,
+    pub sort_features: SortFeatures,
+    pub feature_format: FeatureFormat,
+    pub feature_comment_prefix: String,
+    pub in_between_comment_prefix: String,
+    pub features_file: Option<PathBuf>,
+    pub include_optional_deps: bool,
+    pub show_feature_deps: bool,
+    pub show_feature_rdeps: bool,
     pub feature_section_name: String,
     pub crate_section_name: String,
+    pub section_heading: Option<String>,
+    pub section_delimiter_template: Vec<String>,
     pub shrink_headings: i8,
+    pub strip_hidden_tests: bool,
+    pub warn_unknown_lang: bool,
+    pub source_module: Option<String>,
+    pub no_link_resolution: bool,
+    pub unresolved_links: LinkResolutionMode,
     pub link_to_latest: bool,
+    pub docs_rs_url: String,
+    pub std_docs_url: String,
+    pub no_check_publish: bool,
+    pub inline_links: bool,
+    pub msrv_badge: bool,
+    pub badge_style: BadgeStyle,
+    pub show_cfg_annotations: bool,
+    pub cfg_annotation_template: String,
     pub document_private_items: bool,
+    pub rustdoc_args: Vec<String>,
     pub no_deps: bool,
     pub check: bool,
     pub allow_missing_section: bool,
     pub allow_dirty: bool,
     pub allow_staged: bool,
+    pub no_vcs: bool,
+    pub keep_going: bool,
     pub features: Vec<String>,
     pub hidden_features: Vec<String>,
     pub all_features: bool,
@@ -139,38 +432,133 @@ pub struct PackageConfig {
     #[serde(flatten, serialize_with = "serialize_target_selection")]
     pub target_selection: Option<TargetSelection>,
     pub toolchain: String,
+    pub toolchain_file: Option<PathBuf>,
+    pub no_rustup: bool,
+    pub strict_rustdoc_version: bool,
     pub target: Option<String>,
     pub target_dir: Option<PathBuf>,
     pub readme_path: Option<PathBuf>,
+    pub readme_paths: Vec<ReadmeTarget>,
+    pub readme_template: Option<PathBuf>,
+    pub section_encoding: Option<String>,
 }
 
 /// Parsed configuration parameters for packages.
 #[derive(Debug, Default, Clone, Deserialize, Serialize, Fields!)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct PackageConfigPatch {
+    /// Insert feature documentation into the crate docs
     pub feature_into_crate: Option<bool>,
+    /// Insert crate documentation into the readme
     pub crate_into_readme: Option<bool>,
+    /// Formatting of the feature label, `{feature}` is replaced by the feature name
     pub feature_label: Option<String>,
+    /// Text after the label of a default feature
+    pub default_marker: Option<String>,
+    /// Order of the features in the feature documentation
+    pub sort_features: Option<SortFeatures>,
+    /// Format of the feature documentation
+    pub feature_format: Option<FeatureFormat>,
+    /// Prefix of the `Cargo.toml` comments that document a feature
+    pub feature_comment_prefix: Option<String>,
+    /// Prefix of the `Cargo.toml` comments that document the features between features
+    pub in_between_comment_prefix: Option<String>,
+    /// Read the feature documentation from this file instead of the `Cargo.toml` comments, relative to the package manifest
+    pub features_file: Option<PathBuf>,
+    /// Document optional dependencies as features
+    pub include_optional_deps: Option<bool>,
+    /// Add a line listing the features a feature enables
+    pub show_feature_deps: Option<bool>,
+    /// Add a line listing the features that enable a feature
+    pub show_feature_rdeps: Option<bool>,
+    /// Feature documentation section name
     pub feature_section_name: Option<String>,
+    /// Crate documentation section name
     pub crate_section_name: Option<String>,
+    /// Text of the readme heading whose content is the crate documentation section, if the readme has no section markers
+    pub section_heading: Option<String>,
+    /// Start and end markers of sections, `{name}` is replaced by the section name
+    pub section_delimiter_template: Option<Vec<String>>,
+    /// Shrinks headings when inserting documentation into the readme by the given amount
     pub shrink_headings: Option<i8>,
+    /// Remove hidden lines (`# `) from rust code blocks when inserting documentation into the readme
+    pub strip_hidden_tests: Option<bool>,
+    /// Warn about code blocks with a language that is neither rust nor a common other language, like a misspelled `rust`
+    pub warn_unknown_lang: Option<bool>,
+    /// Path of the module to take the crate documentation from instead of the crate root, like `network::client`
+    pub source_module: Option<String>,
+    /// Read the crate docs from the source instead of the rustdoc json, leaving intra-doc links unresolved
+    pub no_link_resolution: Option<bool>,
+    /// What to do about intra-doc links that fail to resolve
+    pub unresolved_links: Option<LinkResolutionMode>,
+    /// Link to the "latest" version on docs.rs, this only affects workspace crates
     pub link_to_latest: Option<bool>,
+    /// Url of the documentation of a crate, `{package}`, `{version}` and `{name}` are replaced by the package name, version and crate name
+    pub docs_rs_url: Option<String>,
+    /// Url of the documentation of `core`, `alloc` and `std`, `{name}` is replaced by the crate name
+    pub std_docs_url: Option<String>,
+    /// Don't warn when linking to docs.rs for a package that is not published to crates.io
+    pub no_check_publish: Option<bool>,
+    /// Turn reference-style links into inline links when inserting documentation into the readme
+    pub inline_links: Option<bool>,
+    /// Add a badge with the minimum supported rust version (`rust-version`) at the top of the crate documentation section
+    pub msrv_badge: Option<bool>,
+    /// Style of the shields.io badges
+    pub badge_style: Option<BadgeStyle>,
+    /// Add an annotation after links to items with a `#[doc(cfg(...))]` attribute
+    pub show_cfg_annotations: Option<bool>,
+    /// Formatting of the cfg annotation, `{cfg}` is replaced by the cfg
+    pub cfg_annotation_template: Option<String>,
+    /// Document private items
     pub document_private_items: Option<bool>,
+    /// Extra arguments to pass to rustdoc, each split on whitespace
+    pub rustdoc_args: Option<Vec<String>>,
+    /// Don't build documentation for dependencies
     pub no_deps: Option<bool>,
+    /// Runs in 'check' mode, not writing to files but erroring if something is out of date
     pub check: Option<bool>,
+    /// Don't error when a section is missing
     pub allow_missing_section: Option<bool>,
+    /// Insert documentation even if the affected file is dirty or has staged changes
     pub allow_dirty: Option<bool>,
+    /// Insert documentation even if the affected file has staged changes
     pub allow_staged: Option<bool>,
+    /// Skip the version control check entirely
+    pub no_vcs: Option<bool>,
+    /// Keep going after a package fails to be prepared and report all errors at the end
+    pub keep_going: Option<bool>,
+    /// List of features to activate
     pub features: Option<Vec<String>>,
+    /// Activate all available features
     pub all_features: Option<bool>,
+    /// List of features to hide from the documentation
     pub hidden_features: Option<Vec<String>>,
+    /// Do not activate the `default` feature
     pub no_default_features: Option<bool>,
+    /// Document only the library targets
     pub lib: Option<bool>,
+    /// Document only the specified binary
     pub bin: Option<BoolOrString>,
+    /// Which rustup toolchain to use when invoking rustdoc
     pub toolchain: Option<String>,
+    /// Read the toolchain from the `channel` of this `rust-toolchain.toml`, relative to the package manifest
+    pub toolchain_file: Option<PathBuf>,
+    /// Invoke `cargo` directly instead of going through rustup's `+toolchain`
+    pub no_rustup: Option<bool>,
+    /// Error when the rustdoc json version differs from the supported one, even if only by one
+    pub strict_rustdoc_version: Option<bool>,
+    /// Target triple to document
     pub target: Option<String>,
+    /// Directory for all generated artifacts
     pub target_dir: Option<PathBuf>,
+    /// Readme path relative to the package manifest
     pub readme_path: Option<PathBuf>,
+    /// Readme paths relative to the package manifest, each either a path or a table with a `path` and a `section-name`
+    pub readme_paths: Option<Vec<ReadmeTarget>>,
+    /// Readme template path relative to the package manifest
+    pub readme_template: Option<PathBuf>,
+    /// Encoding of the readme and readme template, like `windows-1252`, utf-8 if not set
+    pub section_encoding: Option<String>,
 }
 
 impl PackageConfigPatch {
@@ -186,21 +574,96 @@ impl PackageConfigPatch {
         if let Some(feature_label) = &overwrite.feature_label {
             this.feature_label = Some(feature_label.clone());
         }
+        if let Some(default_marker) = &overwrite.default_marker {
+            this.default_marker = Some(default_marker.clone());
+        }
+        if let Some(sort_features) = overwrite.sort_features {
+            this.sort_features = Some(sort_features);
+        }
+        if let Some(feature_format) = overwrite.feature_format {
+            this.feature_format = Some(feature_format);
+        }
+        if let Some(feature_comment_prefix) = &overwrite.feature_comment_prefix {
+            this.feature_comment_prefix = Some(feature_comment_prefix.clone());
+        }
+        if let Some(in_between_comment_prefix) = &overwrite.in_between_comment_prefix {
+            this.in_between_comment_prefix = Some(in_between_comment_prefix.clone());
+        }
+        if let Some(features_file) = &overwrite.features_file {
+            this.features_file = Some(features_file.clone());
+        }
+        if let Some(include_optional_deps) = overwrite.include_optional_deps {
+            this.include_optional_deps = Some(include_optional_deps);
+        }
+        if let Some(show_feature_deps) = overwrite.show_feature_deps {
+            this.show_feature_deps = Some(show_feature_deps);
+        }
+        if let Some(show_feature_rdeps) = overwrite.show_feature_rdeps {
+            this.show_feature_rdeps = Some(show_feature_rdeps);
+        }
         if let Some(feature_section_name) = &overwrite.feature_section_name {
             this.feature_section_name = Some(feature_section_name.clone());
         }
         if let Some(crate_section_name) = &overwrite.crate_section_name {
             this.crate_section_name = Some(crate_section_name.clone());
         }
+        if let Some(section_heading) = &overwrite.section_heading {
+            this.section_heading = Some(section_heading.clone());
+        }
+        if let Some(section_delimiter_template) = &overwrite.section_delimiter_template {
+            this.section_delimiter_template = Some(section_delimiter_template.clone());
+        }
         if let Some(shrink_headings) = overwrite.shrink_headings {
             this.shrink_headings = Some(shrink_headings);
         }
+        if let Some(strip_hidden_tests) = overwrite.strip_hidden_tests {
+            this.strip_hidden_tests = Some(strip_hidden_tests);
+        }
+        if let Some(warn_unknown_lang) = overwrite.warn_unknown_lang {
+            this.warn_unknown_lang = Some(warn_unknown_lang);
+        }
+        if let Some(source_module) = &overwrite.source_module {
+            this.source_module = Some(source_module.clone());
+        }
+        if let Some(no_link_resolution) = overwrite.no_link_resolution {
+            this.no_link_resolution = Some(no_link_resolution);
+        }
+        if let Some(unresolved_links) = overwrite.unresolved_links {
+            this.unresolved_links = Some(unresolved_links);
+        }
         if let Some(link_to_latest) = overwrite.link_to_latest {
             this.link_to_latest = Some(link_to_latest);
         }
+        if let Some(docs_rs_url) = &overwrite.docs_rs_url {
+            this.docs_rs_url = Some(docs_rs_url.clone());
+        }
+        if let Some(std_docs_url) = &overwrite.std_docs_url {
+            this.std_docs_url = Some(std_docs_url.clone());
+        }
+        if let Some(no_check_publish) = overwrite.no_check_publish {
+            this.no_check_publish = Some(no_check_publish);
+        }
+        if let Some(inline_links) = overwrite.inline_links {
+            this.inline_links = Some(inline_links);
+        }
+        if let Some(msrv_badge) = overwrite.msrv_badge {
+            this.msrv_badge = Some(msrv_badge);
+        }
+        if let Some(badge_style) = overwrite.badge_style {
+            this.badge_style = Some(badge_style);
+        }
+        if let Some(show_cfg_annotations) = overwrite.show_cfg_annotations {
+            this.show_cfg_annotations = Some(show_cfg_annotations);
+        }
+        if let Some(cfg_annotation_template) = &overwrite.cfg_annotation_template {
+            this.cfg_annotation_template = Some(cfg_annotation_template.clone());
+        }
         if let Some(document_private_items) = overwrite.document_private_items {
             this.document_private_items = Some(document_private_items);
         }
+        if let Some(rustdoc_args) = &overwrite.rustdoc_args {
+            this.rustdoc_args = Some(rustdoc_args.clone());
+        }
         if let Some(no_deps) = overwrite.no_deps {
             this.no_deps = Some(no_deps);
         }
@@ -216,6 +679,12 @@ impl PackageConfigPatch {
         if let Some(allow_staged) = overwrite.allow_staged {
             this.allow_staged = Some(allow_staged);
         }
+        if let Some(no_vcs) = overwrite.no_vcs {
+            this.no_vcs = Some(no_vcs);
+        }
+        if let Some(keep_going) = overwrite.keep_going {
+            this.keep_going = Some(keep_going);
+        }
         if let Some(features) = &overwrite.features {
             this.features = Some(features.clone());
         }
@@ -235,14 +704,30 @@ impl PackageConfigPatch {
         if let Some(toolchain) = &overwrite.toolchain {
             this.toolchain = Some(toolchain.clone());
         }
+        if let Some(toolchain_file) = &overwrite.toolchain_file {
+            this.toolchain_file = Some(toolchain_file.clone());
+        }
+        if let Some(no_rustup) = overwrite.no_rustup {
+            this.no_rustup = Some(no_rustup);
+        }
+        if let Some(strict_rustdoc_version) = overwrite.strict_rustdoc_version {
+            this.strict_rustdoc_version = Some(strict_rustdoc_version);
+        }
         if let Some(target) = &overwrite.target {
             this.target = Some(target.clone());
         }
         if let Some(target_dir) = &overwrite.target_dir {
             this.target_dir = Some(target_dir.clone());
         }
-        if let Some(readme_path) = &overwrite.readme_path {
-            this.readme_path = Some(readme_path.clone());
+        if overwrite.readme_path.is_some() || overwrite.readme_paths.is_some() {
+            this.readme_path = overwrite.readme_path.clone();
+            this.readme_paths = overwrite.readme_paths.clone();
+        }
+        if let Some(readme_template) = &overwrite.readme_template {
+            this.readme_template = Some(readme_template.clone());
+        }
+        if let Some(section_encoding) = &overwrite.section_encoding {
+            this.section_encoding = Some(section_encoding.clone());
         }
 
         this
@@ -253,25 +738,58 @@ impl PackageConfigPatch {
             feature_into_crate,
             crate_into_readme,
             feature_label,
+            default_marker,
+            sort_features,
+            feature_format,
+            feature_comment_prefix,
+            in_between_comment_prefix,
+            features_file,
+            include_optional_deps,
+            show_feature_deps,
+            show_feature_rdeps,
             feature_section_name,
             crate_section_name,
+            section_heading,
+            section_delimiter_template,
             shrink_headings,
+            strip_hidden_tests,
+            warn_unknown_lang,
+            source_module,
+            no_link_resolution,
+            unresolved_links,
             link_to_latest,
+            docs_rs_url,
+            std_docs_url,
+            no_check_publish,
+            inline_links,
+            msrv_badge,
+            badge_style,
+            show_cfg_annotations,
+            cfg_annotation_template,
             document_private_items,
+            rustdoc_args,
             no_deps,
             check,
             allow_missing_section,
             allow_dirty,
             allow_staged,
+            no_vcs,
+            keep_going,
             features,
             all_features,
             no_default_features,
             toolchain,
+            toolchain_file,
+            no_rustup,
+            strict_rustdoc_version,
             lib,
             bin,
             target,
             target_dir,
             readme_path,
+            readme_paths,
+            readme_template,
+            section_encoding,
             hidden_features,
         } = self;
 
@@ -279,18 +797,53 @@ impl PackageConfigPatch {
             feature_into_crate: feature_into_crate.unwrap_or(true),
             crate_into_readme: crate_into_readme.unwrap_or(true),
             feature_label: feature_label.unwrap_or_else(|| DEFAULT_FEATURE_LABEL.to_string()),
+            default_marker: default_marker.unwrap_or_else(|| DEFAULT_DEFAULT_MARKER.to_string()),
+            sort_features: sort_features.unwrap_or_default(),
+            feature_format: feature_format.unwrap_or_default(),
+            feature_comment_prefix: feature_comment_prefix
+                .unwrap_or_else(|| DEFAULT_FEATURE_COMMENT_PREFIX.to_string()),
+            in_between_comment_prefix: in_between_comment_prefix
+                .unwrap_or_else(|| DEFAULT_IN_BETWEEN_COMMENT_PREFIX.to_string()),
+            features_file,
+            include_optional_deps: include_optional_deps.unwrap_or_default(),
+            show_feature_deps: show_feature_deps.unwrap_or_default(),
+            show_feature_rdeps: show_feature_rdeps.unwrap_or_default(),
             feature_section_name: feature_section_name
                 .unwrap_or_else(|| DEFAULT_FEATURE_SECTION_NAME.to_string()),
             crate_section_name: crate_section_name
                 .unwrap_or_else(|| DEFAULT_CRATE_SECTION_NAME.to_string()),
+            section_heading,
+            section_delimiter_template: section_delimiter_template.unwrap_or_else(|| {
+                vec![
+                    DEFAULT_SECTION_START_TEMPLATE.to_string(),
+                    DEFAULT_SECTION_END_TEMPLATE.to_string(),
+                ]
+            }),
             shrink_headings: shrink_headings.unwrap_or(DEFAULT_SHRINK_HEADINGS),
+            strip_hidden_tests: strip_hidden_tests.unwrap_or(true),
+            warn_unknown_lang: warn_unknown_lang.unwrap_or_default(),
+            source_module,
+            no_link_resolution: no_link_resolution.unwrap_or_default(),
+            unresolved_links: unresolved_links.unwrap_or_default(),
             link_to_latest: link_to_latest.unwrap_or_default(),
+            docs_rs_url: docs_rs_url.unwrap_or_else(|| DEFAULT_DOCS_RS_URL.to_string()),
+            std_docs_url: std_docs_url.unwrap_or_else(|| DEFAULT_STD_DOCS_URL.to_string()),
+            no_check_publish: no_check_publish.unwrap_or_default(),
+            inline_links: inline_links.unwrap_or_default(),
+            msrv_badge: msrv_badge.unwrap_or_default(),
+            badge_style: badge_style.unwrap_or_default(),
+            show_cfg_annotations: show_cfg_annotations.unwrap_or(true),
+            cfg_annotation_template: cfg_annotation_template
+                .unwrap_or_else(|| DEFAULT_CFG_ANNOTATION_TEMPLATE.to_string()),
             document_private_items: document_private_items.unwrap_or_default(),
+            rustdoc_args: rustdoc_args.unwrap_or_default(),
             no_deps: no_deps.unwrap_or_default(),
             check: check.unwrap_or_default(),
             allow_missing_section: allow_missing_section.unwrap_or_default(),
             allow_dirty: allow_dirty.unwrap_or_default(),
             allow_staged: allow_dirty.or(allow_staged).unwrap_or_default(),
+            no_vcs: no_vcs.unwrap_or_default(),
+            keep_going: keep_going.unwrap_or_default(),
             features: features.unwrap_or_default(),
             hidden_features: hidden_features.unwrap_or_default(),
             all_features: all_features.unwrap_or_default(),
@@ -304,9 +857,15 @@ impl PackageConfigPatch {
                 },
             },
             toolchain: toolchain.unwrap_or_else(|| DEFAULT_TOOLCHAIN.to_string()),
+            toolchain_file,
+            no_rustup: no_rustup.unwrap_or_default(),
+            strict_rustdoc_version: strict_rustdoc_version.unwrap_or_default(),
             target,
             target_dir,
             readme_path,
+            readme_paths: readme_paths.unwrap_or_default(),
+            readme_template,
+            section_encoding,
         }
     }
 }
@@ -328,6 +887,97 @@ impl fmt::Display for TargetSelection {
     }
 }
 
+/// The order of the features in the feature documentation.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortFeatures {
+    /// In the order they are declared in the `Cargo.toml`
+    #[default]
+    Declaration,
+    /// Alphabetically by name
+    Alphabetical,
+    /// Default features first, otherwise in declaration order
+    DefaultFirst,
+    /// Non-default features first, otherwise in declaration order
+    NonDefaultFirst,
+    /// Default features first, both parts alphabetically by name
+    AlphabeticalDefaultFirst,
+}
+
+/// The format of the feature documentation.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeatureFormat {
+    /// A list with a `- ` item for each feature
+    #[default]
+    List,
+    /// A table with a row for each feature
+    Table,
+}
+
+/// What to do about an intra-doc link that fails to resolve.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkResolutionMode {
+    /// Warn and leave the link as it is
+    #[default]
+    Warn,
+    /// Silently leave the link as it is
+    Ignore,
+    /// Fail with an error
+    Error,
+}
+
+/// The style of a shields.io badge.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum BadgeStyle {
+    #[default]
+    Flat,
+    FlatSquare,
+    Plastic,
+    ForTheBadge,
+}
+
+impl BadgeStyle {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BadgeStyle::Flat => "flat",
+            BadgeStyle::FlatSquare => "flat-square",
+            BadgeStyle::Plastic => "plastic",
+            BadgeStyle::ForTheBadge => "for-the-badge",
+        }
+    }
+}
+
+/// An entry of `readme-paths`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ReadmeTarget {
+    Path(PathBuf),
+    #[serde(rename_all = "kebab-case")]
+    Table {
+        path: PathBuf,
+        /// Overwrites `crate-section-name` for this readme.
+        section_name: Option<String>,
+    },
+}
+
+impl ReadmeTarget {
+    pub fn path(&self) -> &Path {
+        match self {
+            ReadmeTarget::Path(path) | ReadmeTarget::Table { path, .. } => path,
+        }
+    }
+
+    pub fn section_name(&self) -> Option<&str> {
+        match self {
+            ReadmeTarget::Path(_) => None,
+            ReadmeTarget::Table { section_name, .. } => section_name.as_deref(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum BoolOrString {
@@ -347,6 +997,12 @@ struct Cargo<T: Default> {
     package: Package<T>,
 }
 
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct WorkspaceCargo<T: Default> {
+    workspace: Package<T>,
+}
+
 #[derive(Default, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 struct Package<T: Default> {
@@ -385,6 +1041,94 @@ where
     .serialize(serializer)
 }
 
+/// Syntax-highlights the toml that `--print-config` prints.
+///
+/// This is not a toml formatter, it only recognizes the line shapes that
+/// `toml::to_string` produces for our config types:
+/// keys are cyan, strings green, booleans yellow and numbers magenta.
+pub fn colorize_toml(toml: &str) -> String {
+    let mut out = String::with_capacity(toml.len());
+
+    for line in toml.split_inclusive('\n') {
+        let (line, newline) = match line.strip_suffix('\n') {
+            Some(line) => (line, "\n"),
+            None => (line, ""),
+        };
+
+        match line.split_once(" = ") {
+            Some((key, value)) if !line.starts_with('[') => {
+                write!(out, "{TOML_KEY}{key}{TOML_KEY:#} = ").unwrap();
+                colorize_toml_value(value, &mut out);
+            }
+            _ => out.push_str(line),
+        }
+
+        out.push_str(newline);
+    }
+
+    out
+}
+
+const fn toml_style(color: AnsiColor) -> Style {
+    Style::new().fg_color(Some(Color::Ansi(color)))
+}
+
+const TOML_KEY: Style = toml_style(AnsiColor::Cyan);
+const TOML_STRING: Style = toml_style(AnsiColor::Green);
+const TOML_BOOL: Style = toml_style(AnsiColor::Yellow);
+const TOML_NUMBER: Style = toml_style(AnsiColor::Magenta);
+
+/// Colors the strings, booleans and numbers in a value, which may be an array.
+fn colorize_toml_value(value: &str, out: &mut String) {
+    let mut rest = value;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '"' || c == '\'' {
+            let len = toml_string_len(rest, c);
+            write!(out, "{TOML_STRING}{}{TOML_STRING:#}", &rest[..len]).unwrap();
+            rest = &rest[len..];
+        } else if c.is_ascii_alphanumeric() || c == '-' || c == '+' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.' | '_')))
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+
+            let style = match word {
+                "true" | "false" => Some(TOML_BOOL),
+                _ if word.parse::<f64>().is_ok() => Some(TOML_NUMBER),
+                _ => None,
+            };
+
+            match style {
+                Some(style) => write!(out, "{style}{word}{style:#}").unwrap(),
+                None => out.push_str(word),
+            }
+
+            rest = &rest[len..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+}
+
+/// Returns the byte length of the string literal at the start of `str`, including its quotes.
+fn toml_string_len(str: &str, quote: char) -> usize {
+    let mut escaped = false;
+
+    for (i, c) in str.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote == '"' {
+            escaped = true;
+        } else if c == quote {
+            return i + c.len_utf8();
+        }
+    }
+
+    str.len()
+}
+
 fn metadata_json<T: Default + DeserializeOwned>(json: &serde_json::Value) -> Result<T> {
     let metadata = <Option<Metadata<T>> as Deserialize>::deserialize(json)
         .wrap_err("failed to deserialize metadata")?;
@@ -396,7 +1140,17 @@ fn metadata_toml<T: Default + DeserializeOwned>(toml: &str) -> Result<T> {
     Ok(cargo.package.metadata.insert_docs)
 }
 
-fn warn_about_unused_fields(fields: HashMap<String, IgnoredAny>, available_fields: &[&[&str]]) {
+fn workspace_metadata_toml<T: Default + DeserializeOwned>(toml: &str) -> Result<T> {
+    let cargo =
+        toml::from_str::<WorkspaceCargo<T>>(toml).wrap_err("failed to deserialize metadata")?;
+    Ok(cargo.workspace.metadata.insert_docs)
+}
+
+fn warn_about_unused_fields(
+    table: &str,
+    fields: HashMap<String, IgnoredAny>,
+    available_fields: &[&[&str]],
+) {
     let available_fields = available_fields
         .iter()
         .copied()
@@ -412,7 +1166,7 @@ fn warn_about_unused_fields(fields: HashMap<String, IgnoredAny>, available_field
         .join(", ");
 
     if !unknown_fields.is_empty() {
-        tracing::warn!("metadata.insert-docs contains unknown fields: {unknown_fields}");
+        tracing::warn!("{table} contains unknown fields: {unknown_fields}");
     }
 }
 
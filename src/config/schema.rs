@@ -0,0 +1,251 @@
+//! A JSON Schema (draft-07) for the `insert-docs` metadata table.
+//!
+//! The properties are generated by the `Fields!` derive from the fields
+//! of the configuration patches, their types and doc comments.
+//! The defaults are taken from the resolved default configuration.
+
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::{Serialize, Serializer};
+
+use super::{
+    BadgeStyle, BoolOrString, FeatureFormat, LinkResolutionMode, PackageConfigPatch, ReadmeTarget,
+    SortFeatures, WorkspaceConfigPatch,
+};
+
+/// Returns the pretty printed JSON Schema.
+pub fn json_schema() -> String {
+    let package_defaults = serde_json::to_value(PackageConfigPatch::default().finish())
+        .expect("config is serializable");
+    let workspace_defaults = serde_json::to_value(WorkspaceConfigPatch::default().finish())
+        .expect("config is serializable");
+
+    let mut properties = PackageConfigPatch::schema_properties(&package_defaults);
+    properties.extend(WorkspaceConfigPatch::schema_properties(&workspace_defaults));
+    properties.push((
+        "profiles".to_string(),
+        Property {
+            description: Some(
+                "Named sets of package settings, selected with `--profile` or `default-profile`"
+                    .to_string(),
+            ),
+            ..Property::new("object")
+        },
+    ));
+    properties.push((
+        "feature-config".to_string(),
+        Property {
+            description: Some(
+                "Per-feature settings `hidden`, `since`, `status` and `label`, keyed by feature name"
+                    .to_string(),
+            ),
+            ..Property::new("object")
+        },
+    ));
+
+    properties.push((
+        "feature-docs".to_string(),
+        Property {
+            description: Some(
+                "Documentation for features without `##` comments, keyed by feature name"
+                    .to_string(),
+            ),
+            ..Property::new("object")
+        },
+    ));
+
+    let schema = Schema {
+        schema: "http://json-schema.org/draft-07/schema#",
+        title: "insert-docs",
+        description: "\
+            Configuration of `cargo-insert-docs` in `[package.metadata.insert-docs]` \
+            and `[workspace.metadata.insert-docs]`. The fields `package`, `workspace`, \
+            `exclude`, `exclude-dir`, `workspace-readme`, `workspace-readme-section-name`, \
+            `default-profile`, `profiles` and `feature-docs` only apply to the workspace. \
+            `default-profile` and `profiles` can also be set for the package at the workspace root.",
+        ty: "object",
+        properties: Properties(properties),
+    };
+
+    serde_json::to_string_pretty(&schema).expect("schema is serializable")
+}
+
+#[derive(Serialize)]
+struct Schema {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    title: &'static str,
+    description: &'static str,
+    #[serde(rename = "type")]
+    ty: &'static str,
+    properties: Properties,
+}
+
+/// Serializes as a map in the order of the fields.
+struct Properties(Vec<(String, Property)>);
+
+impl Serialize for Properties {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(name, property)| (name, property)))
+    }
+}
+
+#[derive(Serialize)]
+pub struct Property {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    ty: PropertyType,
+    #[serde(rename = "enum", skip_serializing_if = "Vec::is_empty")]
+    variants: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items: Option<Box<Property>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maximum: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum PropertyType {
+    One(&'static str),
+    Many(&'static [&'static str]),
+}
+
+impl Property {
+    fn new(ty: &'static str) -> Self {
+        Self::with_type(PropertyType::One(ty))
+    }
+
+    fn with_type(ty: PropertyType) -> Self {
+        Self {
+            description: None,
+            ty,
+            variants: vec![],
+            items: None,
+            minimum: None,
+            maximum: None,
+            default: None,
+        }
+    }
+}
+
+/// Joins the lines of a doc comment into a single line description.
+pub fn description(doc: &[&str]) -> Option<String> {
+    let description = doc.iter().map(|line| line.trim()).collect::<Vec<_>>().join(" ");
+    let description = description.trim();
+    (!description.is_empty()).then(|| description.to_string())
+}
+
+/// A type that can be described by a JSON Schema.
+pub trait ConfigSchema {
+    fn property() -> Property;
+}
+
+impl<T: ConfigSchema> ConfigSchema for Option<T> {
+    fn property() -> Property {
+        T::property()
+    }
+}
+
+impl<T: ConfigSchema> ConfigSchema for Vec<T> {
+    fn property() -> Property {
+        Property { items: Some(Box::new(T::property())), ..Property::new("array") }
+    }
+}
+
+impl ConfigSchema for bool {
+    fn property() -> Property {
+        Property::new("boolean")
+    }
+}
+
+impl ConfigSchema for i8 {
+    fn property() -> Property {
+        Property {
+            minimum: Some(i8::MIN.into()),
+            maximum: Some(i8::MAX.into()),
+            ..Property::new("integer")
+        }
+    }
+}
+
+impl ConfigSchema for String {
+    fn property() -> Property {
+        Property::new("string")
+    }
+}
+
+impl ConfigSchema for PathBuf {
+    fn property() -> Property {
+        Property::new("string")
+    }
+}
+
+impl ConfigSchema for BoolOrString {
+    fn property() -> Property {
+        Property::with_type(PropertyType::Many(&["boolean", "string"]))
+    }
+}
+
+impl ConfigSchema for ReadmeTarget {
+    fn property() -> Property {
+        Property::with_type(PropertyType::Many(&["string", "object"]))
+    }
+}
+
+impl ConfigSchema for SortFeatures {
+    fn property() -> Property {
+        Property {
+            variants: SortFeatures::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect(),
+            ..Property::new("string")
+        }
+    }
+}
+
+impl ConfigSchema for FeatureFormat {
+    fn property() -> Property {
+        Property {
+            variants: FeatureFormat::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect(),
+            ..Property::new("string")
+        }
+    }
+}
+
+impl ConfigSchema for BadgeStyle {
+    fn property() -> Property {
+        Property {
+            variants: BadgeStyle::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect(),
+            ..Property::new("string")
+        }
+    }
+}
+
+impl ConfigSchema for LinkResolutionMode {
+    fn property() -> Property {
+        Property {
+            variants: LinkResolutionMode::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect(),
+            ..Property::new("string")
+        }
+    }
+}
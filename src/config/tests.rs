@@ -1,6 +1,11 @@
+use expect_test::expect_file;
 use serde::{Deserialize, Serialize};
 
-use crate::config::{BoolOrString, TargetSelection, serialize_target_selection};
+use crate::config::{
+    BoolOrString, PackageConfigPatch, ReadmeTarget, SortFeatures, TargetSelection, colorize_toml,
+    json_schema, package_patch_from_vars, profile, read_root_package_profiles,
+    read_workspace_config, serialize_target_selection,
+};
 
 #[test]
 fn test_target_selection() {
@@ -56,3 +61,172 @@ fn test_bool_or_string() {
         Ok(Table { foo: Some(BoolOrString::String(String::from("bar"))) })
     );
 }
+
+#[test]
+fn test_colorize_toml() {
+    let toml = indoc::indoc! {r#"
+        [resolved.foo]
+        feature-label = "say \"hi\""
+        shrink-headings = -1
+        check = false
+        features = ["foo", 'bar']
+    "#};
+
+    let colorized = colorize_toml(toml);
+
+    expect_test::expect![[r#"
+        [resolved.foo]
+        <cyan>feature-label<reset> = <green>"say \"hi\""<reset>
+        <cyan>shrink-headings<reset> = <magenta>-1<reset>
+        <cyan>check<reset> = <yellow>false<reset>
+        <cyan>features<reset> = [<green>"foo"<reset>, <green>'bar'<reset>]
+    "#]]
+    .assert_eq(
+        &colorized
+            .replace("\x1b[36m", "<cyan>")
+            .replace("\x1b[32m", "<green>")
+            .replace("\x1b[33m", "<yellow>")
+            .replace("\x1b[35m", "<magenta>")
+            .replace("\x1b[0m", "<reset>"),
+    );
+
+    assert_eq!(anstream::adapter::strip_str(&colorized).to_string(), toml);
+}
+
+/// Run with `UPDATE_EXPECT=1` to update the checked-in schema.
+#[test]
+fn test_json_schema() {
+    expect_file!["../../schemas/insert-docs-config.json"]
+        .assert_eq(&format!("{}\n", json_schema()));
+}
+
+#[test]
+fn test_profiles() {
+    let json = serde_json::json!({
+        "insert-docs": {
+            "default-profile": "ci",
+            "allow-dirty": true,
+            "profiles": {
+                "ci": { "check": true, "allow-dirty": false },
+                "dev": { "check": false },
+            },
+        },
+    });
+
+    let (wrk, pkg, profiles) = read_workspace_config(&json).unwrap();
+    assert_eq!(wrk.default_profile.as_deref(), Some("ci"));
+
+    let cfg = pkg.apply(profile(&profiles, "ci").unwrap()).finish();
+    assert!(cfg.check);
+    assert!(!cfg.allow_dirty);
+
+    assert_eq!(
+        profile(&profiles, "release").unwrap_err().to_string(),
+        "unknown profile \"release\", available profiles are \"ci\", \"dev\""
+    );
+    assert_eq!(
+        profile(&Default::default(), "ci").unwrap_err().to_string(),
+        "unknown profile \"ci\", there are no profiles in `[workspace.metadata.insert-docs.profiles]`"
+    );
+}
+
+#[test]
+fn test_root_package_profiles() {
+    let json = serde_json::json!({
+        "insert-docs": {
+            "default-profile": "dev",
+            "profiles": {
+                "dev": { "allow-dirty": true },
+            },
+        },
+    });
+
+    let (wrk, profiles) = read_root_package_profiles(&json).unwrap();
+    assert_eq!(wrk.default_profile.as_deref(), Some("dev"));
+    assert!(profile(&profiles, "dev").unwrap().clone().finish().allow_dirty);
+
+    let (wrk, profiles) = read_root_package_profiles(&serde_json::Value::Null).unwrap();
+    assert_eq!(wrk.default_profile, None);
+    assert!(profiles.is_empty());
+}
+
+#[test]
+fn test_readme_target() {
+    #[derive(Deserialize)]
+    struct Table {
+        readme_paths: Vec<ReadmeTarget>,
+    }
+
+    let table: Table = toml::from_str(
+        r#"readme_paths = ["README.md", { path = "docs/quickstart.md", section-name = "api" }]"#,
+    )
+    .unwrap();
+
+    assert_eq!(table.readme_paths[0], ReadmeTarget::Path("README.md".into()));
+    assert_eq!(table.readme_paths[0].section_name(), None);
+    assert_eq!(table.readme_paths[1].path(), std::path::Path::new("docs/quickstart.md"));
+    assert_eq!(table.readme_paths[1].section_name(), Some("api"));
+}
+
+#[test]
+fn test_readme_path_overwrites_readme_paths() {
+    let single = PackageConfigPatch { readme_path: Some("README.md".into()), ..Default::default() };
+    let multiple = PackageConfigPatch {
+        readme_paths: Some(vec![ReadmeTarget::Path("docs/README.md".into())]),
+        ..Default::default()
+    };
+
+    let patch = single.apply(&multiple);
+    assert_eq!(patch.readme_path, None);
+    assert_eq!(patch.readme_paths, Some(vec![ReadmeTarget::Path("docs/README.md".into())]));
+
+    let patch = multiple.apply(&single);
+    assert_eq!(patch.readme_path.as_deref(), Some(std::path::Path::new("README.md")));
+    assert_eq!(patch.readme_paths, None);
+}
+
+#[test]
+fn test_package_patch_from_vars() {
+    let vars = |name: &str| {
+        Some(
+            match name {
+                "CARGO_INSERT_DOCS_TOOLCHAIN" => "nightly-2025-01-01",
+                "CARGO_INSERT_DOCS_ALLOW_DIRTY" => "1",
+                "CARGO_INSERT_DOCS_CHECK" => "false",
+                "CARGO_INSERT_DOCS_SHRINK_HEADINGS" => "-1",
+                "CARGO_INSERT_DOCS_FEATURES" => "foo, bar",
+                "CARGO_INSERT_DOCS_BIN" => "my-bin",
+                "CARGO_INSERT_DOCS_SORT_FEATURES" => "alphabetical",
+                "CARGO_INSERT_DOCS_RUSTDOC_ARGS" => "--cfg docsrs",
+                _ => return None,
+            }
+            .to_string(),
+        )
+    };
+
+    let cfg = package_patch_from_vars(vars).unwrap();
+    assert_eq!(cfg.toolchain.as_deref(), Some("nightly-2025-01-01"));
+    assert_eq!(cfg.allow_dirty, Some(true));
+    assert_eq!(cfg.check, Some(false));
+    assert_eq!(cfg.shrink_headings, Some(-1));
+    assert_eq!(cfg.features, Some(vec!["foo".to_string(), "bar".to_string()]));
+    assert_eq!(cfg.bin, Some(BoolOrString::String("my-bin".to_string())));
+    assert_eq!(cfg.sort_features, Some(SortFeatures::Alphabetical));
+    assert_eq!(cfg.rustdoc_args, Some(vec!["--cfg".to_string(), "docsrs".to_string()]));
+    assert_eq!(cfg.readme_path, None);
+
+    let json = |name: &str| {
+        (name == "CARGO_INSERT_DOCS_RUSTDOC_ARGS")
+            .then(|| r#"["--crate-version", "1.0 beta"]"#.into())
+    };
+
+    let cfg = package_patch_from_vars(json).unwrap();
+    assert_eq!(cfg.rustdoc_args, Some(vec!["--crate-version".to_string(), "1.0 beta".to_string()]));
+
+    let invalid = |name: &str| (name == "CARGO_INSERT_DOCS_SHRINK_HEADINGS").then(|| "lots".into());
+
+    assert_eq!(
+        package_patch_from_vars(invalid).unwrap_err().to_string(),
+        "invalid value \"lots\" for environment variable CARGO_INSERT_DOCS_SHRINK_HEADINGS"
+    );
+}
@@ -0,0 +1,130 @@
+//! A line based unified diff for `--print-diff` and `--show-diff`.
+
+#[cfg(test)]
+mod tests;
+
+use std::{fmt::Write as _, ops::Range};
+
+use anstyle::{AnsiColor, Color, Effects, Style};
+use similar::{Algorithm, DiffTag};
+
+/// Lines of unchanged context around each change.
+const CONTEXT: usize = 3;
+
+const HEADER: Style = Style::new().effects(Effects::BOLD);
+const HUNK: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Cyan)));
+const DELETE: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Red)));
+const INSERT: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Green)));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Returns a unified diff with ansi colors, or an empty string if `old` and `new` are equal.
+///
+/// Lines are compared including their line ending, so a change from `\n` to `\r\n` shows up.
+///
+/// Print it through an [`anstream::AutoStream`] to remove the colors when they are not wanted.
+pub fn unified(path: &str, old: &str, new: &str) -> String {
+    let old = old.split_inclusive('\n').collect::<Vec<_>>();
+    let new = new.split_inclusive('\n').collect::<Vec<_>>();
+    let ops = diff_lines(&old, &new);
+
+    let mut out = String::new();
+
+    for (i, hunk) in hunks(&ops).into_iter().enumerate() {
+        if i == 0 {
+            writeln!(out, "{HEADER}--- a/{path}{HEADER:#}").unwrap();
+            writeln!(out, "{HEADER}+++ b/{path}{HEADER:#}").unwrap();
+        }
+
+        let before = &ops[..hunk.start];
+        let ops = &ops[hunk];
+
+        let old_start = before.iter().filter(|op| !matches!(op, Op::Insert(_))).count();
+        let new_start = before.iter().filter(|op| !matches!(op, Op::Delete(_))).count();
+        let old_len = ops.iter().filter(|op| !matches!(op, Op::Insert(_))).count();
+        let new_len = ops.iter().filter(|op| !matches!(op, Op::Delete(_))).count();
+
+        let old_range = hunk_range(old_start, old_len);
+        let new_range = hunk_range(new_start, new_len);
+        writeln!(out, "{HUNK}@@ -{old_range} +{new_range} @@{HUNK:#}").unwrap();
+
+        for op in ops {
+            let (prefix, style, line) = match *op {
+                Op::Equal(line) => (' ', Style::new(), line),
+                Op::Delete(line) => ('-', DELETE, line),
+                Op::Insert(line) => ('+', INSERT, line),
+            };
+
+            // the line ending is written as is, so `\r\n` lines keep their `\r`
+            let text = line.strip_suffix('\n').unwrap_or(line);
+            let text_without_cr = text.strip_suffix('\r').unwrap_or(text);
+            let cr = &text[text_without_cr.len()..];
+
+            writeln!(out, "{style}{prefix}{text_without_cr}{style:#}{cr}").unwrap();
+
+            if !line.ends_with('\n') {
+                out.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    out
+}
+
+/// Formats a hunk range like `diff -u` does.
+/// An empty range starts at the line before it.
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// Groups the changes with their context into hunks,
+/// merging hunks whose context would overlap.
+fn hunks(ops: &[Op]) -> Vec<Range<usize>> {
+    let mut hunks: Vec<Range<usize>> = vec![];
+
+    for (i, op) in ops.iter().enumerate() {
+        if matches!(op, Op::Equal(_)) {
+            continue;
+        }
+
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + 1 + CONTEXT).min(ops.len());
+
+        match hunks.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+
+    hunks
+}
+
+/// Computes the line operations to turn `old` into `new` with the Myers diff algorithm.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Op<'a>> {
+    let mut ops = vec![];
+
+    for op in similar::capture_diff_slices(Algorithm::Myers, old, new) {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+
+        match tag {
+            DiffTag::Equal => ops.extend(old[old_range].iter().map(|&line| Op::Equal(line))),
+            DiffTag::Delete => ops.extend(old[old_range].iter().map(|&line| Op::Delete(line))),
+            DiffTag::Insert => ops.extend(new[new_range].iter().map(|&line| Op::Insert(line))),
+            DiffTag::Replace => {
+                ops.extend(old[old_range].iter().map(|&line| Op::Delete(line)));
+                ops.extend(new[new_range].iter().map(|&line| Op::Insert(line)));
+            }
+        }
+    }
+
+    ops
+}
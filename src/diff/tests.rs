@@ -0,0 +1,109 @@
+use expect_test::expect;
+
+use super::unified;
+
+fn unified_plain(old: &str, new: &str) -> String {
+    anstream::adapter::strip_str(&unified("README.md", old, new)).to_string()
+}
+
+#[test]
+fn test_equal() {
+    assert_eq!(unified_plain("a\nb\nc\n", "a\nb\nc\n"), "");
+}
+
+#[test]
+fn test_change() {
+    expect![[r#"
+        --- a/README.md
+        +++ b/README.md
+        @@ -2,7 +2,7 @@
+         2
+         3
+         4
+        -5
+        +five
+         6
+         7
+         8
+    "#]]
+    .assert_eq(&unified_plain("1\n2\n3\n4\n5\n6\n7\n8\n9\n", "1\n2\n3\n4\nfive\n6\n7\n8\n9\n"));
+}
+
+#[test]
+fn test_separate_hunks() {
+    expect![[r#"
+        --- a/README.md
+        +++ b/README.md
+        @@ -1,3 +1,4 @@
+        +0
+         1
+         2
+         3
+        @@ -9,4 +10,3 @@
+         9
+         10
+         11
+        -12
+    "#]]
+    .assert_eq(&unified_plain(
+        "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n",
+        "0\n1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n",
+    ));
+}
+
+#[test]
+fn test_insert_into_empty() {
+    expect![[r#"
+        --- a/README.md
+        +++ b/README.md
+        @@ -0,0 +1,2 @@
+        +a
+        +b
+    "#]]
+    .assert_eq(&unified_plain("", "a\nb\n"));
+}
+
+#[test]
+fn test_stale_section() {
+    let old =
+        "# foo\n\n<!-- crate documentation start -->\nold docs\n<!-- crate documentation end -->\n";
+    let new =
+        "# foo\n\n<!-- crate documentation start -->\nnew docs\n<!-- crate documentation end -->\n";
+
+    expect![[r#"
+        --- a/README.md
+        +++ b/README.md
+        @@ -1,5 +1,5 @@
+         # foo
+         
+         <!-- crate documentation start -->
+        -old docs
+        +new docs
+         <!-- crate documentation end -->
+    "#]]
+    .assert_eq(&unified_plain(old, new));
+}
+
+#[test]
+fn test_line_ending_change() {
+    expect![[r#"
+        --- a/README.md
+        +++ b/README.md
+        @@ -1,2 +1,2 @@
+        -a
+        +a
+         b
+    "#]]
+    .assert_eq(&unified_plain("a\r\nb\n", "a\nb\n").replace('\r', ""));
+
+    expect![[r#"
+        --- a/README.md
+        +++ b/README.md
+        @@ -1,2 +1,2 @@
+         a
+        -b
+        \ No newline at end of file
+        +b
+    "#]]
+    .assert_eq(&unified_plain("a\nb", "a\nb\n"));
+}
@@ -1,45 +1,135 @@
 #[cfg(test)]
 mod tests;
 
-use std::ops::Range;
+use std::{
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
-use color_eyre::eyre::{Result, bail};
+use color_eyre::eyre::{Result, WrapErr as _, bail};
 use rangemap::RangeMap;
 use syn::spanned::Spanned as _;
+use tracing::error_span;
 
-use crate::{markdown, string_replacer::StringReplacer};
+use crate::{
+    line_ending::detect_line_ending,
+    markdown::{self, SectionDelimiters},
+    string_replacer::StringReplacer,
+};
 
 pub struct FeatureDocsSection<'a> {
     source: &'a str,
+    source_path: &'a Path,
     docs: Docs,
+    span: Range<usize>,
     content_span: Range<usize>,
 }
 
-impl<'a> FeatureDocsSection<'a> {
-    pub fn find(source: &'a str, section_name: &str) -> Result<Option<Self>> {
-        let docs = parse(source)?;
+/// The new content of the file that contains the section.
+///
+/// This is the source file unless the section is in a file included with `include_str!`.
+pub struct Replaced<'a> {
+    pub path: &'a Path,
+    pub old: &'a str,
+    pub new: String,
+}
 
-        let Some(section) = markdown::find_section(&docs.value, section_name) else {
+impl<'a> FeatureDocsSection<'a> {
+    /// `source_path` is used to resolve `include_str!` paths.
+    pub fn find(
+        source: &'a str,
+        source_path: &'a Path,
+        section_name: &str,
+        delimiters: &SectionDelimiters,
+    ) -> Result<Option<Self>> {
+        let docs = parse(source, source_path)?;
+
+        let Some(section) = markdown::find_section(&docs.value, section_name, delimiters)? else {
             return Ok(None);
         };
 
-        Ok(Some(FeatureDocsSection { source, docs, content_span: section.content_span }))
+        Ok(Some(FeatureDocsSection {
+            source,
+            source_path,
+            docs,
+            span: section.span,
+            content_span: section.content_span,
+        }))
+    }
+
+    /// Returns the current content of the section as it appears in the crate docs.
+    pub fn content(&self) -> &str {
+        &self.docs.value[self.content_span.clone()]
+    }
+
+    /// Returns the level of the last heading before the section or `0` if there is none.
+    pub fn heading_level(&self) -> u8 {
+        markdown::last_heading_level(&self.docs.value[..self.content_span.start]).unwrap_or(0)
     }
 
-    pub fn replace(&self, section_content: &str) -> Result<String> {
-        let Self { source, docs, content_span } = self;
+    pub fn replace(&self, section_content: &str) -> Result<Replaced<'_>> {
+        let Self { source, source_path, docs, span, content_span } = self;
 
         let start = content_span.start;
         let end = content_span.end;
 
-        let start_frag_i = *docs.source_map.get(&start).unwrap();
-        let end_frag_i = *docs.source_map.get(&end).unwrap();
+        let (start_frag_i, start_line_i) = *docs.source_map.get(&start).unwrap();
+        let (end_frag_i, end_line_i) = *docs.source_map.get(&end).unwrap();
 
         let start_frag = &docs.frags[start_frag_i];
         let end_frag = &docs.frags[end_frag_i];
 
         if start_frag_i == end_frag_i {
-            bail!("section start and end in the same doc attribute is not yet supported");
+            let markers = SectionMarkers {
+                start: &docs.value[span.start..content_span.start],
+                end: &docs.value[content_span.end..span.end],
+            };
+
+            if let Some(included) = &start_frag.included {
+                let new = if start_line_i == end_line_i {
+                    replace_between_markers(
+                        &included.content,
+                        0..included.content.len(),
+                        &markers,
+                        &markdown_lines(&included.content, section_content),
+                    )?
+                } else {
+                    replace_block_lines(
+                        &included.content,
+                        start_frag,
+                        start_line_i,
+                        end_line_i,
+                        section_content,
+                    )
+                };
+
+                return Ok(Replaced { path: &included.path, old: &included.content, new });
+            }
+
+            if start_frag.comment_kind != CommentKind::Block || start_line_i == end_line_i {
+                return Ok(Replaced {
+                    path: source_path,
+                    old: source,
+                    new: replace_within_frag(source, start_frag, &markers, section_content)?,
+                });
+            }
+
+            return Ok(Replaced {
+                path: source_path,
+                old: source,
+                new: replace_block_lines(
+                    source,
+                    start_frag,
+                    start_line_i,
+                    end_line_i,
+                    section_content,
+                ),
+            });
+        }
+
+        if start_frag.included.is_some() || end_frag.included.is_some() {
+            bail!("section start and end must both be in the same included file");
         }
 
         // Ideally we'd remove the text before the end marker within the same attribute
@@ -71,14 +161,19 @@ impl<'a> FeatureDocsSection<'a> {
                 out.push('\n');
             }
 
-            out
+            detect_line_ending(source).apply(&out).into_owned()
         };
 
         let mut out = StringReplacer::new(source);
 
-        let insert_start = start_frag.attr_span.end;
+        let mut insert_start = start_frag.attr_span.end;
         let mut insert_end = end_frag.attr_span.start;
 
+        // the span of a line doc comment may include the `\r` of a `\r\n`
+        if source[..insert_start].ends_with('\r') {
+            insert_start -= 1;
+        }
+
         // after the attribute end there was probably already a newline
         // so no need for a second one
         if source[insert_end..].starts_with('\n') {
@@ -87,25 +182,216 @@ impl<'a> FeatureDocsSection<'a> {
 
         out.replace(insert_start..insert_end, &replacement);
 
-        Ok(out.finish())
+        Ok(Replaced { path: source_path, old: source, new: out.finish() })
+    }
+}
+
+struct SectionMarkers<'a> {
+    start: &'a str,
+    end: &'a str,
+}
+
+/// Replaces the section content when both markers are in the same doc attribute and
+/// [`replace_block_lines`] does not apply, like `//! <!-- foo start --><!-- foo end -->`
+/// or `#![doc = "<!-- foo start -->\n<!-- foo end -->"]`.
+///
+/// Unlike the other cases, the markers are searched for in the source of the attribute.
+/// There is no source map for the inside of string literals.
+fn replace_within_frag(
+    source: &str,
+    frag: &DocFragment,
+    markers: &SectionMarkers,
+    section_content: &str,
+) -> Result<String> {
+    let line_start = source[..frag.attr_span.start].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &source[line_start..frag.attr_span.start];
+    let indent = if indent.chars().all(char::is_whitespace) { indent } else { "" };
+
+    let (span, replacement) = match frag.kind {
+        DocFragmentKind::SugaredDoc => {
+            let comment = match frag.comment_kind {
+                CommentKind::Line => "//!",
+                CommentKind::Block => "",
+            };
+
+            let mut out = String::new();
+            out.push('\n');
+
+            for mut line in section_content.lines() {
+                out.push_str(indent);
+                out.push_str(comment);
+
+                // rustfmt trims the end of comment lines, so we do too
+                line = line.trim_end();
+
+                if !line.chars().all(char::is_whitespace) {
+                    if !comment.is_empty() {
+                        out.push(' ');
+                    }
+
+                    out.push_str(line);
+                }
+
+                out.push('\n');
+            }
+
+            // the end marker goes on its own line
+            out.push_str(indent);
+
+            if !comment.is_empty() {
+                out.push_str(comment);
+                out.push(' ');
+            }
+
+            (frag.attr_span.clone(), detect_line_ending(source).apply(&out).into_owned())
+        }
+        DocFragmentKind::RawDoc => {
+            let literal = &source[frag.lit_span.clone()];
+
+            let replacement = match literal.strip_prefix('r') {
+                Some(hashes) => {
+                    let hashes = &hashes[..hashes.find('"').unwrap_or(0)];
+                    let replacement = markdown_lines(source, section_content);
+
+                    if replacement.contains(&format!("\"{hashes}")) {
+                        bail!("the section content would end the raw string literal early");
+                    }
+
+                    replacement
+                }
+                None => {
+                    let mut out = String::new();
+
+                    for char in markdown_lines("", section_content).chars() {
+                        match char {
+                            '\\' => out.push_str("\\\\"),
+                            '"' => out.push_str("\\\""),
+                            '\n' => out.push_str("\\n"),
+                            _ => out.push(char),
+                        }
+                    }
+
+                    out
+                }
+            };
+
+            (frag.lit_span.clone(), replacement)
+        }
+    };
+
+    replace_between_markers(source, span, markers, &replacement)
+}
+
+/// Returns the section content on its own lines, starting and ending with a newline.
+fn markdown_lines(source: &str, section_content: &str) -> String {
+    let mut out = String::new();
+    out.push('\n');
+
+    for line in section_content.lines() {
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    detect_line_ending(source).apply(&out).into_owned()
+}
+
+/// Replaces what is between the first start marker in `span` and the end marker after it.
+fn replace_between_markers(
+    source: &str,
+    span: Range<usize>,
+    markers: &SectionMarkers,
+    replacement: &str,
+) -> Result<String> {
+    let Some(start) = source[span.clone()].find(markers.start) else {
+        bail!("failed to find `{}` in the source of the doc attribute", markers.start);
+    };
+
+    let start = span.start + start + markers.start.len();
+
+    let Some(end) = source[start..span.end].find(markers.end) else {
+        bail!("failed to find `{}` in the source of the doc attribute", markers.end);
+    };
+
+    let end = start + end;
+
+    let mut out = StringReplacer::new(source);
+    out.replace(start..end, replacement.to_string());
+    Ok(out.finish())
+}
+
+/// Replaces the lines between the start and end line of a `/*! */` block comment
+/// or a file included with `include_str!`.
+///
+/// The new lines get the same prefix (like ` * `) as the end line.
+fn replace_block_lines(
+    source: &str,
+    frag: &DocFragment,
+    start_line_i: usize,
+    end_line_i: usize,
+    section_content: &str,
+) -> String {
+    let start_line = frag.line_spans[start_line_i].clone();
+    let end_line = frag.line_spans[end_line_i].clone();
+
+    let prefix = {
+        let end_line_str = &source[end_line.clone()];
+        let doc_line = frag.doc.lines().nth(end_line_i).unwrap_or("");
+        &end_line_str[..end_line_str.len() - doc_line.trim_start().len()]
+    };
+
+    let mut replacement = String::new();
+    replacement.push('\n');
+
+    for mut line in section_content.lines() {
+        // rustfmt trims the end of comment lines, so we do too
+        line = line.trim_end();
+
+        if line.chars().all(char::is_whitespace) {
+            replacement.push_str(prefix.trim_end());
+        } else {
+            replacement.push_str(prefix);
+            replacement.push_str(line);
+        }
+
+        replacement.push('\n');
     }
+
+    let replacement = detect_line_ending(source).apply(&replacement).into_owned();
+
+    let mut out = StringReplacer::new(source);
+    out.replace(start_line.end..end_line.start, replacement);
+    out.finish()
 }
 
-fn parse(lib_rs: &str) -> Result<Docs> {
-    let fragments = parse_doc_frags(lib_rs)?;
+/// Returns the crate docs as written in the source, like rustdoc would see them.
+pub fn crate_docs(lib_rs: &str, lib_rs_path: &Path) -> Result<String> {
+    let docs = parse(lib_rs, lib_rs_path)?;
+    Ok(docs.value.trim_end_matches('\n').to_string())
+}
+
+fn parse(lib_rs: &str, lib_rs_path: &Path) -> Result<Docs> {
+    let fragments = parse_doc_frags(lib_rs, lib_rs_path)?;
     Ok(combine_doc_frags(fragments))
 }
 
 #[derive(Clone, Debug)]
 pub struct DocFragment {
     attr_span: Range<usize>,
-    #[expect(dead_code)]
     lit_span: Range<usize>,
     doc: String,
     kind: DocFragmentKind,
-    #[expect(dead_code)]
     comment_kind: CommentKind,
     indent: usize,
+    /// For block comments and included files, the source span of each line of `doc`.
+    line_spans: Vec<Range<usize>>,
+    /// The file of a `#![doc = include_str!("...")]`.
+    included: Option<IncludedFile>,
+}
+
+#[derive(Clone, Debug)]
+struct IncludedFile {
+    path: PathBuf,
+    content: String,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -127,7 +413,7 @@ struct Docs {
     frags: Vec<DocFragment>,
 }
 
-fn parse_doc_frags(lib_rs: &str) -> Result<Vec<DocFragment>> {
+fn parse_doc_frags(lib_rs: &str, lib_rs_path: &Path) -> Result<Vec<DocFragment>> {
     let file = syn::parse_file(lib_rs)?;
 
     let mut doc_fragments = vec![];
@@ -146,10 +432,27 @@ fn parse_doc_frags(lib_rs: &str) -> Result<Vec<DocFragment>> {
             continue;
         }
 
-        let syn::Expr::Lit(lit) = value else { continue };
-
-        let syn::Lit::Str(lit_str) = &lit.lit else {
-            continue;
+        let lit_str = match value {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit_str), .. }) => lit_str,
+            syn::Expr::Macro(expr) if expr.mac.path.is_ident("include_str") => {
+                // forms like `include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))`
+                // are skipped, like any other doc attribute that isn't a string literal
+                let Some(included) = read_included_file(expr, lib_rs_path)? else {
+                    continue;
+                };
+                doc_fragments.push(DocFragment {
+                    attr_span: attr.span().byte_range(),
+                    lit_span: expr.span().byte_range(),
+                    doc: included.content.clone(),
+                    kind: DocFragmentKind::RawDoc,
+                    comment_kind: CommentKind::Line,
+                    indent: 0,
+                    line_spans: line_spans(&included.content, 0..included.content.len()),
+                    included: Some(included),
+                });
+                continue;
+            }
+            _ => continue,
         };
 
         let raw_attr = &lib_rs[attr.span().byte_range()];
@@ -179,13 +482,23 @@ fn parse_doc_frags(lib_rs: &str) -> Result<Vec<DocFragment>> {
             }
         }
 
+        let attr_span = attr.span().byte_range();
+        let doc = beautify_doc_string(lit_str.value(), comment_kind);
+
+        let line_spans = match comment_kind {
+            CommentKind::Block => block_comment_line_spans(lib_rs, attr_span.clone()),
+            CommentKind::Line => vec![],
+        };
+
         doc_fragments.push(DocFragment {
-            attr_span: attr.span().byte_range(),
+            attr_span,
             lit_span: lit_str.span().byte_range(),
-            doc: beautify_doc_string(lit_str.value(), comment_kind),
+            doc,
             kind: fragment_kind,
             comment_kind,
             indent: 0,
+            line_spans,
+            included: None,
         });
     }
 
@@ -194,7 +507,87 @@ fn parse_doc_frags(lib_rs: &str) -> Result<Vec<DocFragment>> {
     Ok(doc_fragments)
 }
 
-type SourceMap = RangeMap<usize, usize>;
+/// Returns the source spans of the lines of a `/*! */` comment that
+/// remain after [`beautify_doc_string`].
+fn block_comment_line_spans(lib_rs: &str, attr_span: Range<usize>) -> Vec<Range<usize>> {
+    let content_span = attr_span.start + "/*!".len()..attr_span.end - "*/".len();
+    let content = &lib_rs[content_span.clone()];
+    let mut spans = line_spans(lib_rs, content_span);
+
+    // `beautify_doc_string` removes a first line made of only stars
+    if content.contains('\n') && content.lines().next().is_some_and(|l| l.chars().all(|c| c == '*'))
+    {
+        spans.remove(0);
+    }
+
+    spans
+}
+
+/// Returns the spans of the lines in `span`, without the line endings.
+fn line_spans(source: &str, span: Range<usize>) -> Vec<Range<usize>> {
+    let mut spans = vec![];
+    let mut start = span.start;
+
+    for line in source[span].split_inclusive('\n') {
+        let line_len = line.trim_end_matches(['\n', '\r']).len();
+        spans.push(start..start + line_len);
+        start += line.len();
+    }
+
+    spans
+}
+
+/// Returns the paths of the files that the crate docs include with `include_str!("...")`.
+pub fn included_files(lib_rs: &str, lib_rs_path: &Path) -> Vec<PathBuf> {
+    let Ok(file) = syn::parse_file(lib_rs) else {
+        return vec![];
+    };
+
+    file.attrs
+        .iter()
+        .filter(|attr| matches!(attr.style, syn::AttrStyle::Inner { .. }))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(meta) if meta.path.is_ident("doc") => Some(&meta.value),
+            _ => None,
+        })
+        .filter_map(|value| match value {
+            syn::Expr::Macro(expr) if expr.mac.path.is_ident("include_str") => {
+                included_path(expr, lib_rs_path).map(|(path, _)| path)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the path of an `include_str!("...")` relative to the file that contains it,
+/// and the path as written.
+///
+/// Returns `None` if the argument is not a string literal.
+fn included_path(expr: &syn::ExprMacro, lib_rs_path: &Path) -> Option<(PathBuf, String)> {
+    let relative_path = expr.mac.parse_body::<syn::LitStr>().ok()?.value();
+    let path = lib_rs_path.parent().unwrap_or(Path::new("")).join(&relative_path);
+    Some((path, relative_path))
+}
+
+/// Reads the file of an `include_str!("...")` relative to the file that contains it.
+///
+/// Returns `None` if the argument is not a string literal.
+fn read_included_file(expr: &syn::ExprMacro, lib_rs_path: &Path) -> Result<Option<IncludedFile>> {
+    let Some((path, relative_path)) = included_path(expr, lib_rs_path) else {
+        return Ok(None);
+    };
+
+    let _span = error_span!("", path = %path.display()).entered();
+
+    let content = fs::read_to_string(&path)
+        .wrap_err_with(|| format!("failed to read included file {relative_path:?}"))?;
+
+    Ok(Some(IncludedFile { path, content }))
+}
+
+/// Maps a byte position in the combined docs to the fragment index
+/// and the line index within that fragment.
+type SourceMap = RangeMap<usize, (usize, usize)>;
 
 fn combine_doc_frags(frags: Vec<DocFragment>) -> Docs {
     #[derive(Default)]
@@ -204,7 +597,7 @@ fn combine_doc_frags(frags: Vec<DocFragment>) -> Docs {
     }
 
     impl DocsBuilder {
-        fn push(&mut self, i: usize, value: &str) {
+        fn push(&mut self, i: (usize, usize), value: &str) {
             let start = self.value.len();
             self.value.push_str(value);
             self.value.push('\n');
@@ -223,11 +616,11 @@ fn combine_doc_frags(frags: Vec<DocFragment>) -> Docs {
     let mut docs = DocsBuilder::default();
 
     for (i, frag) in frags.iter().enumerate() {
-        for line in frag.doc.lines() {
+        for (line_i, line) in frag.doc.lines().enumerate() {
             if !line.chars().all(char::is_whitespace) {
-                docs.push(i, &line[frag.indent..]);
+                docs.push((i, line_i), &line[frag.indent..]);
             } else {
-                docs.push(i, line);
+                docs.push((i, line_i), line);
             }
         }
     }
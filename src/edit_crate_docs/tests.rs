@@ -1,16 +1,25 @@
+use std::{fs, path::Path};
+
 use color_eyre::eyre::Result;
 use expect_test::expect;
 use indoc::indoc;
 
-use super::FeatureDocsSection;
+use crate::markdown::SectionDelimiters;
+
+use super::{CommentKind, FeatureDocsSection, crate_docs, included_files, parse_doc_frags};
 
 fn replace_section(
     source: &str,
     section_name: &str,
     section_content: &str,
 ) -> Result<Option<String>> {
-    if let Some(section) = FeatureDocsSection::find(source, section_name)? {
-        section.replace(section_content).map(Some)
+    if let Some(section) = FeatureDocsSection::find(
+        source,
+        Path::new("lib.rs"),
+        section_name,
+        &SectionDelimiters::default(),
+    )? {
+        section.replace(section_content).map(|replaced| Some(replaced.new))
     } else {
         Ok(None)
     }
@@ -71,9 +80,19 @@ fn line() {
 }
 
 #[test]
-#[ignore = "todo"]
 fn block() {
-    expect![[r#""#]].assert_eq(
+    expect![[r#"
+        /*! prefix
+         * keep <!-- section start --> remove
+         * multi
+         * line
+         *
+         * content
+         * remove <!-- section end --> keep
+         * suffix
+         */
+    "#]]
+    .assert_eq(
         &replace_section(
             indoc! {r#"
             /*! prefix
@@ -83,6 +102,38 @@ fn block() {
              */
             "#},
             "section",
+            "multi\nline\n\ncontent",
+        )
+        .unwrap()
+        .unwrap(),
+    );
+}
+
+#[test]
+fn block_without_stars() {
+    expect![[r#"
+        /*!
+        prefix
+        <!-- section start -->
+        multi
+        line
+        content
+        <!-- section end -->
+        suffix
+        */
+    "#]]
+    .assert_eq(
+        &replace_section(
+            indoc! {r#"
+            /*!
+            prefix
+            <!-- section start -->
+            remove
+            <!-- section end -->
+            suffix
+            */
+            "#},
+            "section",
             "multi\nline\ncontent",
         )
         .unwrap()
@@ -121,6 +172,77 @@ fn block_separate() {
     );
 }
 
+#[test]
+fn line_same_attr() {
+    expect![[r#"
+        //! prefix
+        //! keep <!-- section start -->
+        //! multi
+        //! line
+        //! content
+        //! <!-- section end --> keep
+        //! suffix
+    "#]]
+    .assert_eq(
+        &replace_section(
+            indoc! {r#"
+            //! prefix
+            //! keep <!-- section start --> remove <!-- section end --> keep
+            //! suffix
+            "#},
+            "section",
+            "multi\nline\ncontent",
+        )
+        .unwrap()
+        .unwrap(),
+    );
+}
+
+#[test]
+fn raw_same_attr() {
+    expect![[r#"
+        #![doc = "prefix"]
+        #![doc = "keep <!-- section start -->\nmulti \"quoted\"\nline\n<!-- section end --> keep"]
+        #![doc = "suffix"]
+    "#]]
+    .assert_eq(
+        &replace_section(
+            indoc! {r#"
+            #![doc = "prefix"]
+            #![doc = "keep <!-- section start -->\nremove\n<!-- section end --> keep"]
+            #![doc = "suffix"]
+            "#},
+            "section",
+            "multi \"quoted\"\nline",
+        )
+        .unwrap()
+        .unwrap(),
+    );
+}
+
+#[test]
+fn raw_string_same_attr() {
+    expect![[r##"
+        #![doc = r#"keep <!-- section start -->
+        multi "quoted"
+        line
+        <!-- section end --> keep"#]
+    "##]]
+    .assert_eq(
+        &replace_section(
+            indoc! {r##"
+            #![doc = r#"keep <!-- section start -->
+            remove
+            <!-- section end --> keep"#]
+            "##},
+            "section",
+            "multi \"quoted\"\nline",
+        )
+        .unwrap()
+        .unwrap(),
+    );
+}
+
 #[test]
 fn test_escaped_section() {
     let lib_rs = indoc! {r#"
@@ -135,6 +257,28 @@ fn test_escaped_section() {
     assert!(new_lib_rs.is_none());
 }
 
+#[test]
+fn test_content() {
+    let lib_rs = indoc! {r#"
+        //! prefix
+        //! <!-- feature documentation start -->
+        //! - **`std`** — Enables std
+        //!
+        //! <!-- feature documentation end -->
+        //! suffix
+    "#};
+
+    let section = FeatureDocsSection::find(
+        lib_rs,
+        Path::new("lib.rs"),
+        "feature documentation",
+        &SectionDelimiters::default(),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(section.content(), "\n- **`std`** — Enables std\n\n");
+}
+
 #[test]
 fn test_trim_end() {
     let lib_rs = indoc! {r#"
@@ -158,3 +302,189 @@ fn test_trim_end() {
         new_lib_rs
     );
 }
+
+#[test]
+fn include_str() {
+    let dir =
+        std::env::temp_dir().join(format!("cargo-insert-docs-include-{}", std::process::id()));
+    fs::create_dir_all(dir.join("src")).unwrap();
+
+    let readme_path = dir.join("README.md");
+    let lib_rs_path = dir.join("src/lib.rs");
+
+    fs::write(
+        &readme_path,
+        indoc! {"
+            # My crate
+            <!-- section start -->
+            old
+            <!-- section end -->
+            Bye.
+        "},
+    )
+    .unwrap();
+
+    let lib_rs = indoc! {r#"
+        //! prefix
+        #![doc = include_str!("../README.md")]
+        //! suffix
+    "#};
+
+    let section =
+        FeatureDocsSection::find(lib_rs, &lib_rs_path, "section", &SectionDelimiters::default())
+            .unwrap()
+            .unwrap();
+    assert_eq!(section.content(), "\nold\n");
+
+    let replaced = section.replace("multi\nline").unwrap();
+    assert_eq!(replaced.path, readme_path);
+
+    expect![[r#"
+        # My crate
+        <!-- section start -->
+        multi
+        line
+        <!-- section end -->
+        Bye.
+    "#]]
+    .assert_eq(&replaced.new);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn include_str_missing() {
+    let lib_rs = r#"#![doc = include_str!("../THIS_DOES_NOT_EXIST.md")]"#;
+    let lib_rs_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/lib.rs");
+
+    let Err(err) =
+        FeatureDocsSection::find(lib_rs, &lib_rs_path, "section", &SectionDelimiters::default())
+    else {
+        panic!("expected an error");
+    };
+
+    expect![[r#"failed to read included file "../THIS_DOES_NOT_EXIST.md""#]]
+        .assert_eq(&err.to_string());
+}
+
+#[test]
+fn include_str_non_literal() {
+    let lib_rs = indoc! {r#"
+        #![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
+        //! <!-- section start -->
+        //! <!-- section end -->
+    "#};
+
+    assert_eq!(
+        replace_section(lib_rs, "section", "new").unwrap().unwrap(),
+        indoc! {r#"
+        #![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
+        //! <!-- section start -->
+        //! new
+        //! <!-- section end -->
+    "#}
+    );
+}
+
+#[test]
+fn test_included_files() {
+    let lib_rs = indoc! {r#"
+        #![doc = include_str!("../README.md")]
+        #![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
+        #![doc = "literal"]
+    "#};
+
+    assert_eq!(
+        included_files(lib_rs, Path::new("crate/src/lib.rs")),
+        [Path::new("crate/src/../README.md")]
+    );
+}
+
+/// Returns whether `s` contains a `\n` that is not part of a `\r\n`.
+fn has_lf(s: &str) -> bool {
+    s.replace("\r\n", "").contains('\n')
+}
+
+#[test]
+fn test_crlf_line() {
+    let source =
+        "//! prefix\r\n//! <!-- section start -->\r\n//! <!-- section end -->\r\n//! suffix\r\n";
+    let replaced = replace_section(source, "section", "multi\nline").unwrap().unwrap();
+
+    assert_eq!(
+        replaced,
+        "//! prefix\r\n//! <!-- section start -->\r\n//! multi\r\n//! line\r\n//! <!-- section end -->\r\n//! suffix\r\n",
+    );
+    assert!(!has_lf(&replaced));
+}
+
+#[test]
+fn test_crlf_block() {
+    let source = "/*! prefix\r\n * <!-- section start -->\r\n * <!-- section end -->\r\n */\r\n";
+    let replaced = replace_section(source, "section", "multi\nline").unwrap().unwrap();
+
+    assert!(replaced.contains(" * multi\r\n * line\r\n"));
+    assert!(!has_lf(&replaced));
+}
+
+#[test]
+fn test_crate_docs() {
+    let source = indoc! {r#"
+        //! # Title
+        //!
+        //! Uses [`Foo`].
+
+        /// not crate docs
+        pub struct Foo;
+    "#};
+
+    expect![[r#"
+        # Title
+
+        Uses [`Foo`]."#]]
+    .assert_eq(&crate_docs(source, Path::new("lib.rs")).unwrap());
+}
+
+#[test]
+fn test_crate_docs_raw_string() {
+    let source = indoc! {r##"
+        #![doc = "escaped\nnewline"]
+        #![doc = r"Some docs\n\nwith backslash-n literal"]
+        #![doc = r#"hashed "raw" string"#]
+    "##};
+
+    let frags = parse_doc_frags(source, Path::new("lib.rs")).unwrap();
+    assert!(frags.iter().all(|frag| frag.comment_kind == CommentKind::Line));
+
+    expect![[r#"
+        escaped
+        newline
+        Some docs\n\nwith backslash-n literal
+        hashed "raw" string"#]]
+    .assert_eq(&crate_docs(source, Path::new("lib.rs")).unwrap());
+}
+
+#[test]
+fn test_replace_empty() {
+    expect![[r#"
+        //! prefix
+        //! <!-- section start -->
+        //! <!-- section end -->
+        //! suffix
+    "#]]
+    .assert_eq(
+        &replace_section(
+            indoc! {r#"
+            //! prefix
+            //! <!-- section start -->
+            //! old
+            //! <!-- section end -->
+            //! suffix
+            "#},
+            "section",
+            "",
+        )
+        .unwrap()
+        .unwrap(),
+    );
+}
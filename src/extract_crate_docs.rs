@@ -1,45 +1,215 @@
+mod cache;
 mod resolver;
-mod rewrite_markdown;
+pub(crate) mod rewrite_markdown;
 
 use std::path::PathBuf;
 
 use cargo_metadata::Metadata;
-use color_eyre::eyre::{OptionExt as _, Report, Result, bail};
-use rustdoc_types::Crate;
-use tracing::warn;
+use color_eyre::eyre::{OptionExt as _, Report, Result, bail, eyre};
+use rustdoc_types::{Crate, Item, ItemEnum, Module};
+use serde::Serialize;
+use tracing::{error_span, trace, warn};
 
 use crate::{
-    PackageContext,
+    PackageContext, badge,
+    config::{
+        DEFAULT_CFG_ANNOTATION_TEMPLATE, DEFAULT_DOCS_RS_URL, DEFAULT_SHRINK_HEADINGS,
+        DEFAULT_STD_DOCS_URL, LinkResolutionMode,
+    },
+    edit_crate_docs,
     extract_crate_docs::rewrite_markdown::{RewriteMarkdownOptions, rewrite_markdown},
+    pretty_log::Mutx as _,
     read_to_string,
     rustdoc_json::{self, CommandOutput},
 };
 
-use resolver::{Resolver, ResolverOptions};
+use resolver::{ItemSource, Resolver, ResolverOptions};
+
+/// An intra-doc link of the crate docs and what it resolved to, for `--print-links`.
+#[derive(Serialize)]
+pub struct DocLink {
+    pub link: String,
+    pub status: LinkStatus,
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// An item of the documented crate, for `--print-link-index`.
+#[derive(Serialize)]
+pub struct IndexItem {
+    pub path: String,
+    pub url: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LinkStatus {
+    /// Found in the index of the documented crate.
+    Index,
+    /// Found in the paths map, usually an item of a dependency.
+    Paths,
+    Failed,
+}
 
 pub fn extract(cx: &PackageContext) -> Result<String> {
-    let path = generate_rustdoc_json(cx)?;
-    let json = read_to_string(&path)?;
-    let krate = rustdoc_json::parse(&json, &cx.cfg.toolchain)?;
+    let docs = extract_with(cx, false)?.docs;
+
+    if cx.cfg.msrv_badge
+        && let Some(rust_version) = &cx.package.rust_version
+    {
+        let badge = badge::msrv(&rust_version.to_string(), cx.cfg.badge_style);
+        return Ok(format!("{badge}\n\n{docs}"));
+    }
+
+    Ok(docs)
+}
+
+/// Runs the whole pipeline like [`extract`] but returns the links instead of the docs.
+pub fn extract_links(cx: &PackageContext) -> Result<Vec<DocLink>> {
+    extract_with(cx, true).map(|extracted| extracted.links)
+}
+
+/// Returns the path and url of every item of the crate that a link can resolve to, sorted by path.
+pub fn extract_link_index(cx: &PackageContext) -> Result<Vec<IndexItem>> {
+    if cx.cfg.no_link_resolution {
+        bail!("`no-link-resolution` is set, the link index is only found in the rustdoc json");
+    }
+
+    let krate = read_crate(cx)?;
+
+    let resolver_options = ResolverOptions {
+        link_to_latest: cx.cfg.link_to_latest,
+        docs_rs_url: &cx.cfg.docs_rs_url,
+        std_docs_url: &cx.cfg.std_docs_url,
+        check_publish: !cx.cfg.no_check_publish,
+    };
+
+    let resolver = Resolver::new(&krate, &cx.metadata, &resolver_options)?;
+
+    let mut items = resolver
+        .all_items()
+        .map(|(path, url)| IndexItem { path: path.join("::"), url })
+        .collect::<Vec<_>>();
+
+    items.sort_by(|a, b| a.path.cmp(&b.path).then_with(|| a.url.cmp(&b.url)));
+    Ok(items)
+}
+
+fn extract_with(cx: &PackageContext, collect_links: bool) -> Result<ExtractedDocs> {
+    if cx.cfg.no_link_resolution {
+        return Ok(ExtractedDocs { docs: extract_from_source(cx)?, links: vec![], unresolved: 0 });
+    }
 
-    extract_docs(ExtractDocsOptions {
+    let krate = read_crate(cx)?;
+
+    let source_module = cx.cfg.source_module.as_deref();
+    let links = find_module(&krate, source_module).map_or(0, |module| module.links.len());
+
+    let extracted = extract_docs(ExtractDocsOptions {
         krate: &krate,
+        source_module,
         metadata: &cx.metadata,
-        on_not_found: &mut |link, cause| warn!(%cause, %link, "failed to resolve doc link"),
+        unresolved_links: cx.cfg.unresolved_links,
+        collect_links,
         link_to_latest: cx.cfg.link_to_latest,
+        docs_rs_url: &cx.cfg.docs_rs_url,
+        std_docs_url: &cx.cfg.std_docs_url,
+        check_publish: !cx.cfg.no_check_publish,
+        inline_links: cx.cfg.inline_links,
+        cfg_annotation_template: cx
+            .cfg
+            .show_cfg_annotations
+            .then_some(&*cx.cfg.cfg_annotation_template),
         shrink_headings: cx.cfg.shrink_headings,
-    })
+        strip_hidden_tests: cx.cfg.strip_hidden_tests,
+        warn_unknown_lang: cx.cfg.warn_unknown_lang,
+    })?;
+
+    let mut summary = cx.summary.lck();
+    summary.links_resolved = links.saturating_sub(extracted.unresolved);
+    summary.links_unresolved = extracted.unresolved;
+
+    Ok(extracted)
+}
+
+/// Parses the rustdoc json, generating it unless it was already generated in parallel.
+fn read_crate(cx: &PackageContext) -> Result<Crate> {
+    let pregenerated = cx.rustdoc_json.lck().take();
+
+    let path = match pregenerated {
+        Some(result) => result?,
+        None => generate_rustdoc_json(cx, false)?,
+    };
+
+    let json = read_to_string(&path)?;
+    rustdoc_json::parse(&json, &cx.cfg.toolchain, cx.cfg.strict_rustdoc_version)
+}
+
+/// Reads the crate docs from the source instead of the rustdoc json, so links are left as they are.
+fn extract_from_source(cx: &PackageContext) -> Result<String> {
+    let path = cx.target.src_path.as_std_path();
+    let source = read_to_string(path)?;
+    let docs = edit_crate_docs::crate_docs(&source, path)?;
+
+    Ok(rewrite_markdown(
+        &docs,
+        &RewriteMarkdownOptions {
+            shrink_headings: cx.cfg.shrink_headings,
+            strip_hidden_tests: cx.cfg.strip_hidden_tests,
+            warn_unknown_lang: cx.cfg.warn_unknown_lang,
+            inline_links: cx.cfg.inline_links,
+            links: vec![],
+            cfg_annotations: vec![],
+        },
+    ))
 }
 
-fn generate_rustdoc_json(cx: &PackageContext) -> Result<PathBuf> {
+/// With `in_parallel` cargo's output is collected so the output of parallel builds doesn't
+/// interleave. It is printed if the build fails.
+pub fn generate_rustdoc_json(cx: &PackageContext, in_parallel: bool) -> Result<PathBuf> {
+    let target_dir = match cx.cfg.target_dir.clone() {
+        Some(target_dir) => target_dir,
+        None => cx.metadata.target_directory.join("insert-docs").into_std_path_buf(),
+    };
+
     let command_output = if cx.cli.cfg.quiet {
         CommandOutput::Ignore
-    } else if cx.cli.cfg.quiet_cargo {
+    } else if cx.cli.cfg.quiet_cargo || in_parallel {
         CommandOutput::Collect
     } else {
         CommandOutput::Inherit
     };
 
+    let json_path = rustdoc_json::output_path(&target_dir, &cx.target.name);
+
+    // When cargo writes to the terminal we always invoke it so its progress is shown.
+    if !cx.cli.cfg.force_rebuild
+        && command_output != CommandOutput::Inherit
+        && cache::is_up_to_date(cx, &json_path)
+    {
+        trace!(path = %json_path.display(), "rustdoc json is up to date");
+        return Ok(json_path);
+    }
+
+    let cache_entry = if cx.cli.cfg.force_rebuild || !cx.cli.cfg.cache_rustdoc_json {
+        None
+    } else {
+        match cache::entry_path(cx, &target_dir) {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                trace!(%err, "failed to compute rustdoc json cache entry");
+                None
+            }
+        }
+    };
+
+    if let Some(entry) = &cache_entry
+        && cache::is_valid(cx, entry)
+    {
+        trace!(path = %entry.display(), "using cached rustdoc json");
+        return Ok(entry.clone());
+    }
+
     if matches!(command_output, CommandOutput::Inherit) {
         // the command invocation will write directly to the terminal
         // setting this flag here will make the log insert a newline
@@ -47,16 +217,12 @@ fn generate_rustdoc_json(cx: &PackageContext) -> Result<PathBuf> {
         cx.log.foreign_write_incoming();
     }
 
-    let target_dir = match cx.cfg.target_dir.clone() {
-        Some(target_dir) => target_dir,
-        None => cx.metadata.target_directory.join("insert-docs").into_std_path_buf(),
-    };
-
-    let (output, path) = rustdoc_json::generate(rustdoc_json::Options {
+    let options = rustdoc_json::Options {
         metadata: &cx.metadata,
         package: cx.package,
         package_target: cx.target,
         toolchain: Some(&cx.cfg.toolchain),
+        no_rustup: cx.cfg.no_rustup,
         all_features: cx.cfg.all_features,
         no_default_features: cx.cfg.no_default_features,
         features: &mut cx.enabled_features.iter().map(|s| &**s),
@@ -65,15 +231,23 @@ fn generate_rustdoc_json(cx: &PackageContext) -> Result<PathBuf> {
         target_dir: Some(&target_dir),
         quiet: cx.cli.cfg.quiet,
         document_private_items: cx.cfg.document_private_items,
+        rustdoc_args: &cx.cfg.rustdoc_args,
         output: command_output,
         no_deps: cx.cfg.no_deps,
-    })?;
+    };
+
+    let (output, path) = if command_output == CommandOutput::Inherit {
+        // cargo's own progress would be garbled by our progress bar
+        cx.log.suspend_progress(|| rustdoc_json::generate(options))?
+    } else {
+        rustdoc_json::generate(options)?
+    };
 
     if !output.status.success() {
         if command_output == CommandOutput::Collect {
             // write an empty line to separate our messages from the invoked command
             cx.log.foreign_write_incoming();
-            eprint!("{}", String::from_utf8_lossy(&output.stderr));
+            cx.log.suspend_progress(|| eprint!("{}", String::from_utf8_lossy(&output.stderr)));
         }
 
         let see = if command_output != CommandOutput::Ignore { " (see stderr above)" } else { "" };
@@ -81,37 +255,186 @@ fn generate_rustdoc_json(cx: &PackageContext) -> Result<PathBuf> {
         bail!("Failed to build rustdoc JSON{see}");
     }
 
+    if let Err(err) = cache::store_flags(cx, &path) {
+        trace!(%err, "failed to record the rustdoc json flags");
+    }
+
+    if let Some(entry) = cache_entry {
+        match cache::store(&path, &entry) {
+            Ok(()) => return Ok(entry),
+            Err(err) => trace!(%err, "failed to cache rustdoc json"),
+        }
+    }
+
     Ok(path)
 }
 
+/// Options for [`extract_from_crate`], which works on an already parsed rustdoc json.
+pub struct ExtractOptions<'a> {
+    pub krate: &'a Crate,
+    pub metadata: &'a Metadata,
+    /// Path of the module whose docs are extracted like `network::client`, the crate root if `None`.
+    pub source_module: Option<&'a str>,
+    pub link_to_latest: bool,
+    /// Url template with `{package}`, `{version}` and `{name}` placeholders.
+    pub docs_rs_url: &'a str,
+    /// Url template with a `{name}` placeholder.
+    pub std_docs_url: &'a str,
+    pub inline_links: bool,
+    /// Template with a `{cfg}` placeholder for annotations after links to items with `#[doc(cfg(...))]`.
+    pub cfg_annotation_template: Option<&'a str>,
+    pub shrink_headings: i8,
+    pub strip_hidden_tests: bool,
+    pub unresolved_links: LinkResolutionMode,
+}
+
+impl<'a> ExtractOptions<'a> {
+    /// Returns the options with the defaults of the configuration.
+    pub fn new(krate: &'a Crate, metadata: &'a Metadata) -> Self {
+        Self {
+            krate,
+            metadata,
+            source_module: None,
+            link_to_latest: false,
+            docs_rs_url: DEFAULT_DOCS_RS_URL,
+            std_docs_url: DEFAULT_STD_DOCS_URL,
+            inline_links: false,
+            cfg_annotation_template: Some(DEFAULT_CFG_ANNOTATION_TEMPLATE),
+            shrink_headings: DEFAULT_SHRINK_HEADINGS,
+            strip_hidden_tests: true,
+            unresolved_links: LinkResolutionMode::default(),
+        }
+    }
+}
+
+/// Extracts the crate docs from a parsed rustdoc json and resolves its links, without a [`PackageContext`].
+pub fn extract_from_crate(options: ExtractOptions) -> Result<String> {
+    let ExtractOptions {
+        krate,
+        metadata,
+        source_module,
+        link_to_latest,
+        docs_rs_url,
+        std_docs_url,
+        inline_links,
+        cfg_annotation_template,
+        shrink_headings,
+        strip_hidden_tests,
+        unresolved_links,
+    } = options;
+
+    let extracted = extract_docs(ExtractDocsOptions {
+        krate,
+        source_module,
+        metadata,
+        unresolved_links,
+        collect_links: false,
+        link_to_latest,
+        docs_rs_url,
+        std_docs_url,
+        check_publish: true,
+        inline_links,
+        cfg_annotation_template,
+        shrink_headings,
+        strip_hidden_tests,
+        warn_unknown_lang: false,
+    })?;
+
+    Ok(extracted.docs)
+}
+
 struct ExtractDocsOptions<'a> {
     krate: &'a Crate,
+    /// Path of the module whose docs are extracted, the crate root if `None`.
+    source_module: Option<&'a str>,
     metadata: &'a Metadata,
-    on_not_found: &'a mut dyn FnMut(&str, Report),
+    /// What to do about links that fail to resolve.
+    unresolved_links: LinkResolutionMode,
+    /// Return all links alongside the docs.
+    collect_links: bool,
     link_to_latest: bool,
+    docs_rs_url: &'a str,
+    std_docs_url: &'a str,
+    check_publish: bool,
+    inline_links: bool,
+    cfg_annotation_template: Option<&'a str>,
     shrink_headings: i8,
+    strip_hidden_tests: bool,
+    warn_unknown_lang: bool,
 }
 
 fn extract_docs(
-    ExtractDocsOptions { krate, metadata, on_not_found, link_to_latest, shrink_headings }: ExtractDocsOptions,
-) -> Result<String, Report> {
-    let root = krate.index.get(&krate.root).ok_or_eyre("crate index has no root")?;
-    let docs = root.docs.as_deref().unwrap_or("");
+    ExtractDocsOptions {
+        krate,
+        source_module,
+        metadata,
+        unresolved_links,
+        collect_links,
+        link_to_latest,
+        docs_rs_url,
+        std_docs_url,
+        check_publish,
+        inline_links,
+        cfg_annotation_template,
+        shrink_headings,
+        strip_hidden_tests,
+        warn_unknown_lang,
+    }: ExtractDocsOptions,
+) -> Result<ExtractedDocs, Report> {
+    let module = find_module(krate, source_module)?;
+    let docs = module.docs.as_deref().unwrap_or("");
 
-    let resolver_options = ResolverOptions { link_to_latest };
+    let resolver_options =
+        ResolverOptions { link_to_latest, docs_rs_url, std_docs_url, check_publish };
     let resolver = Resolver::new(krate, metadata, &resolver_options)?;
 
-    let mut links = root.links.iter().map(|(k, &v)| (k.clone(), v)).collect::<Vec<_>>();
+    let mut links = module.links.iter().map(|(k, &v)| (k.clone(), v)).collect::<Vec<_>>();
     links.sort_by(|(a, _), (b, _)| a.cmp(b));
 
+    let cfg_annotations = match cfg_annotation_template {
+        Some(template) => links
+            .iter()
+            .filter_map(|&(ref url, item_id)| {
+                let cfg = resolver.item_doc_cfg(item_id)?;
+                Some((url.clone(), template.replace("{cfg}", &cfg)))
+            })
+            .collect(),
+        None => vec![],
+    };
+
+    let mut doc_links = vec![];
+    let mut unresolved = 0;
+
     let links = links
         .into_iter()
         .map(|(url, item_id)| {
             let mut new_url = match resolver.item_url(item_id) {
                 Ok(ok) => ok,
                 Err(err) => {
-                    on_not_found(&url, err);
-                    return (url, None);
+                    unresolved += 1;
+
+                    // collected links already show the failure
+                    match unresolved_links {
+                        _ if collect_links => (),
+                        LinkResolutionMode::Warn => {
+                            warn!(cause = %err, link = %url, "failed to resolve doc link")
+                        }
+                        LinkResolutionMode::Ignore => (),
+                        LinkResolutionMode::Error => {
+                            return Err(err.wrap_err(format!("failed to resolve doc link {url}")));
+                        }
+                    }
+
+                    if collect_links {
+                        doc_links.push(DocLink {
+                            link: url.clone(),
+                            status: LinkStatus::Failed,
+                            url: None,
+                            error: Some(err.to_string()),
+                        });
+                    }
+
+                    return Ok((url, None));
                 }
             };
 
@@ -119,9 +442,69 @@ fn extract_docs(
                 new_url.push_str(&url[hash..]);
             }
 
-            (url, Some(new_url))
+            if collect_links {
+                doc_links.push(DocLink {
+                    link: url.clone(),
+                    status: match resolver.item_source(item_id) {
+                        Some(ItemSource::Index) => LinkStatus::Index,
+                        _ => LinkStatus::Paths,
+                    },
+                    url: Some(new_url.clone()),
+                    error: None,
+                });
+            }
+
+            Ok((url, Some(new_url)))
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>>>()?;
+
+    let docs = rewrite_markdown(
+        docs,
+        &RewriteMarkdownOptions {
+            shrink_headings,
+            strip_hidden_tests,
+            warn_unknown_lang,
+            inline_links,
+            links,
+            cfg_annotations,
+        },
+    );
+
+    Ok(ExtractedDocs { docs, links: doc_links, unresolved })
+}
+
+/// Walks the modules from the crate root along a path like `network::client`.
+fn find_module<'a>(krate: &'a Crate, path: Option<&str>) -> Result<&'a Item> {
+    let root = krate.index.get(&krate.root).ok_or_eyre("crate index has no root")?;
+
+    let Some(path) = path else {
+        return Ok(root);
+    };
+
+    let _span = error_span!("", module = path).entered();
+    let mut module = root;
+
+    for name in path.split("::") {
+        let ItemEnum::Module(Module { items, .. }) = &module.inner else {
+            unreachable!("only modules are walked");
+        };
+
+        module = items
+            .iter()
+            .filter_map(|id| krate.index.get(id))
+            .find(|item| {
+                item.name.as_deref() == Some(name) && matches!(item.inner, ItemEnum::Module(_))
+            })
+            .ok_or_else(|| eyre!("module `{name}` not found"))?;
+    }
+
+    Ok(module)
+}
 
-    Ok(rewrite_markdown(docs, &RewriteMarkdownOptions { shrink_headings, links }))
+struct ExtractedDocs {
+    docs: String,
+    /// Empty unless `collect_links` is set.
+    links: Vec<DocLink>,
+    /// The number of links that failed to resolve.
+    unresolved: usize,
 }
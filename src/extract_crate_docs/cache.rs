@@ -0,0 +1,299 @@
+//! Caches the generated rustdoc json so we don't invoke cargo when nothing changed.
+//!
+//! The cache is opt-in with `--cache-rustdoc-json`. A cache entry is named after a hash of
+//! the flags that affect the rustdoc json, the cargo configuration and the toolchain version,
+//! and a hash of the files of the package and its path dependencies. Files outside of
+//! these packages, like an `include_str!` of a file in a parent directory, are not tracked.
+//! Only the latest entry for each package and flags is kept.
+//!
+//! Before hashing the files, we check if the json rustdoc last wrote is newer than all
+//! of them and was generated with the same flags, recorded in a `.flags` file next to it.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::HashSet,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use cargo_metadata::PackageId;
+
+use crate::PackageContext;
+
+/// Returns the path of the cache entry for the current state of the package.
+pub fn entry_path(cx: &PackageContext, target_dir: &Path) -> io::Result<PathBuf> {
+    let mut hasher = DefaultHasher::new();
+
+    for path in source_files(cx)? {
+        path.hash(&mut hasher);
+        fs::read(&path)?.hash(&mut hasher);
+    }
+
+    let files_hash = hasher.finish();
+
+    Ok(target_dir
+        .join("json-cache")
+        .join(cx.package.name.as_str())
+        .join(format!("{}-{files_hash:016x}.json", flags_hash(cx))))
+}
+
+/// Returns whether the rustdoc json at `path` is newer than the files of the package and
+/// its path dependencies, according to `cargo metadata`, and the `Cargo.lock`,
+/// and was generated with the current flags.
+///
+/// Unlike [`entry_path`] this only looks at modification times, so it doesn't read the files.
+pub fn is_up_to_date(cx: &PackageContext, path: &Path) -> bool {
+    let Ok(files) = source_files(cx) else {
+        return false;
+    };
+
+    is_fresh(path, &files, &flags_hash(cx))
+}
+
+/// Returns whether the rustdoc json at `path` is not older than any of `files`
+/// and its recorded flags are `flags`.
+fn is_fresh(path: &Path, files: &[PathBuf], flags: &str) -> bool {
+    let Ok(json_modified) = fs::metadata(path).and_then(|m| m.modified()) else {
+        return false;
+    };
+
+    match fs::read_to_string(flags_path(path)) {
+        Ok(recorded) if recorded == flags => (),
+        _ => return false,
+    }
+
+    files.iter().all(|file| {
+        fs::metadata(file)
+            .and_then(|m| m.modified())
+            .is_ok_and(|file_modified| file_modified <= json_modified)
+    })
+}
+
+/// Records the flags the rustdoc json at `path` was generated with next to it.
+pub fn store_flags(cx: &PackageContext, path: &Path) -> io::Result<()> {
+    fs::write(flags_path(path), flags_hash(cx))
+}
+
+fn flags_path(path: &Path) -> PathBuf {
+    path.with_extension("flags")
+}
+
+fn flags_hash(cx: &PackageContext) -> String {
+    let mut hasher = DefaultHasher::new();
+    hash_flags(cx, &mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Hashes the features, flags, cargo configuration and toolchain version that affect the rustdoc json.
+fn hash_flags(cx: &PackageContext, hasher: &mut DefaultHasher) {
+    let cfg = &cx.cfg;
+    cx.target.name.hash(hasher);
+    cx.target.src_path.hash(hasher);
+    cx.enabled_features.hash(hasher);
+    cfg.all_features.hash(hasher);
+    cfg.no_default_features.hash(hasher);
+    cfg.toolchain.hash(hasher);
+    cfg.no_rustup.hash(hasher);
+    cfg.target.hash(hasher);
+    cfg.document_private_items.hash(hasher);
+    cfg.rustdoc_args.hash(hasher);
+    cfg.no_deps.hash(hasher);
+
+    for var in
+        ["RUSTFLAGS", "RUSTDOCFLAGS", "CARGO_ENCODED_RUSTFLAGS", "CARGO_ENCODED_RUSTDOCFLAGS"]
+    {
+        std::env::var_os(var).hash(hasher);
+    }
+
+    for path in cargo_config_files(cx) {
+        path.hash(hasher);
+        fs::read(&path).ok().hash(hasher);
+    }
+
+    toolchain_version(cx).hash(hasher);
+}
+
+/// A cache entry is valid if it exists and is not older than the `Cargo.lock`.
+pub fn is_valid(cx: &PackageContext, entry: &Path) -> bool {
+    let Ok(entry_modified) = fs::metadata(entry).and_then(|m| m.modified()) else {
+        return false;
+    };
+
+    let Some(lock) = cargo_lock_path(cx) else {
+        return true;
+    };
+
+    match fs::metadata(lock).and_then(|m| m.modified()) {
+        Ok(lock_modified) => entry_modified >= lock_modified,
+        Err(_) => true,
+    }
+}
+
+/// Stores the generated rustdoc json at `path` as the cache entry `entry`.
+///
+/// Removes the older entries of the package that were generated with the same flags.
+pub fn store(path: &Path, entry: &Path) -> io::Result<()> {
+    let Some(dir) = entry.parent() else {
+        return Ok(());
+    };
+
+    fs::create_dir_all(dir)?;
+
+    let file_name = entry.file_name().unwrap_or_default().to_string_lossy();
+    let flags_prefix = file_name.split_once('-').map_or("", |(flags, _)| flags);
+
+    for old in fs::read_dir(dir)? {
+        let old = old?;
+        let old_name = old.file_name();
+        let old_name = old_name.to_string_lossy();
+
+        if old_name != file_name && old_name.starts_with(&format!("{flags_prefix}-")) {
+            fs::remove_file(old.path())?;
+        }
+    }
+
+    fs::copy(path, entry)?;
+    Ok(())
+}
+
+fn package_dir<'a>(cx: &PackageContext<'a>) -> &'a Path {
+    let manifest_path = &cx.package.manifest_path;
+    manifest_path.parent().expect("manifest path has a parent").as_std_path()
+}
+
+/// Returns the files of the package and its path dependencies, according to `cargo metadata`,
+/// and the `Cargo.lock`.
+fn source_files(cx: &PackageContext) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    for dir in path_package_dirs(cx) {
+        files.extend(package_files(&dir)?);
+    }
+
+    files.extend(cargo_lock_path(cx));
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Returns the directories of the package and the path dependencies it transitively depends on.
+fn path_package_dirs(cx: &PackageContext) -> Vec<PathBuf> {
+    let metadata = &cx.metadata;
+    let package_dir = |id: &PackageId| {
+        let package = metadata.packages.iter().find(|p| p.id == *id)?;
+
+        // only path dependencies have no source
+        if package.source.is_some() {
+            return None;
+        }
+
+        Some(package.manifest_path.parent()?.as_std_path().to_path_buf())
+    };
+
+    let Some(resolve) = &metadata.resolve else {
+        return vec![package_dir(cx).to_path_buf()];
+    };
+
+    let mut seen = HashSet::new();
+    let mut stack = vec![&cx.package.id];
+    let mut dirs = vec![];
+
+    while let Some(id) = stack.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+
+        let Some(dir) = package_dir(id) else {
+            continue;
+        };
+
+        dirs.push(dir);
+
+        if let Some(node) = resolve.nodes.iter().find(|node| node.id == *id) {
+            stack.extend(&node.dependencies);
+        }
+    }
+
+    dirs
+}
+
+/// Returns the `.cargo/config.toml` files that cargo reads for the workspace.
+fn cargo_config_files(cx: &PackageContext) -> Vec<PathBuf> {
+    let workspace_root = cx.metadata.workspace_root.as_std_path();
+    let mut files = vec![];
+
+    for dir in package_dir(cx).ancestors().chain(workspace_root.ancestors()) {
+        for name in ["config.toml", "config"] {
+            let path = dir.join(".cargo").join(name);
+
+            if path.is_file() && !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    files
+}
+
+/// Returns the output of `rustc -vV`, so a toolchain like `nightly` that moves to
+/// a new version invalidates the cache.
+fn toolchain_version(cx: &PackageContext) -> Option<String> {
+    let mut command = if cx.cfg.no_rustup {
+        Command::new(std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()))
+    } else {
+        let mut command = Command::new("rustc");
+        command.arg(format!("+{}", cx.cfg.toolchain));
+        command
+    };
+
+    let output = command.arg("-vV").output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn cargo_lock_path(cx: &PackageContext) -> Option<PathBuf> {
+    let path = cx.metadata.workspace_root.join("Cargo.lock").into_std_path_buf();
+    path.exists().then_some(path)
+}
+
+/// Returns the files in the package directory sorted by path.
+///
+/// Build output, hidden directories like `.git` and the directories of nested packages,
+/// like the members below a workspace root package, are skipped.
+fn package_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    let root = dir;
+
+    while let Some(dir) = dirs.pop() {
+        if dir != root && dir.join("Cargo.toml").is_file() {
+            continue;
+        }
+
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            if entry.file_type()?.is_dir() {
+                if file_name != "target" {
+                    dirs.push(path);
+                }
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
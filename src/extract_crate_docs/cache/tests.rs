@@ -0,0 +1,93 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use super::{flags_path, is_fresh, package_files, store};
+
+#[test]
+fn test_package_files() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/test-crate");
+    let files = package_files(&dir).unwrap();
+
+    let names = files
+        .iter()
+        .map(|path| path.strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/"))
+        .collect::<Vec<_>>();
+
+    assert!(names.iter().any(|name| name == "Cargo.toml"));
+    assert!(names.iter().any(|name| name == "lib.rs"));
+    assert!(names.iter().all(|name| !name.starts_with("target/")));
+    assert!(names.is_sorted());
+}
+
+#[test]
+fn test_package_files_skips_nested_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("member/src")).unwrap();
+    fs::write(root.join("Cargo.toml"), "").unwrap();
+    fs::write(root.join("src/lib.rs"), "").unwrap();
+    fs::write(root.join("member/Cargo.toml"), "").unwrap();
+    fs::write(root.join("member/src/lib.rs"), "").unwrap();
+
+    let files = package_files(root).unwrap();
+    assert_eq!(files, [root.join("Cargo.toml"), root.join("src/lib.rs")]);
+}
+
+#[test]
+fn test_store_evicts_entries_with_same_flags() {
+    let dir = tempfile::tempdir().unwrap();
+    let json = dir.path().join("my_crate.json");
+    fs::write(&json, "{}").unwrap();
+
+    let cache = dir.path().join("json-cache/my-crate");
+    store(&json, &cache.join("aaaa-0001.json")).unwrap();
+    store(&json, &cache.join("bbbb-0001.json")).unwrap();
+    store(&json, &cache.join("aaaa-0002.json")).unwrap();
+
+    let mut names = fs::read_dir(&cache)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    names.sort();
+
+    assert_eq!(names, ["aaaa-0002.json", "bbbb-0001.json"]);
+}
+
+#[test]
+fn test_is_fresh() {
+    let dir = tempfile::tempdir().unwrap();
+    let json = dir.path().join("my_crate.json");
+    let source = dir.path().join("lib.rs");
+
+    let set_modified = |path: &Path, seconds: u64| {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        fs::File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    };
+
+    let files = [source.clone()];
+
+    // no json yet
+    fs::write(&source, "").unwrap();
+    assert!(!is_fresh(&json, &files, "flags"));
+
+    fs::write(&json, "{}").unwrap();
+    fs::write(flags_path(&json), "flags").unwrap();
+    set_modified(&source, 1000);
+    set_modified(&json, 2000);
+    assert!(is_fresh(&json, &files, "flags"));
+
+    // different flags
+    assert!(!is_fresh(&json, &files, "other flags"));
+
+    // stale
+    set_modified(&source, 3000);
+    assert!(!is_fresh(&json, &files, "flags"));
+
+    // a missing source file counts as stale
+    assert!(!is_fresh(&json, &[dir.path().join("missing.rs")], "flags"));
+}
@@ -1,31 +1,79 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
 
-use cargo_metadata::{Metadata, PackageId};
+use cargo_metadata::{Metadata, Package, PackageId};
 use color_eyre::eyre::{Result, bail};
-use rustdoc_types::{Crate, Id};
+use rustdoc_types::{Crate, Id, ItemEnum, ItemKind};
+use tracing::warn;
+
+#[cfg(test)]
+mod tests;
 
 mod index;
 mod paths;
 
+/// The primitives documented at `std/primitive.{name}.html`.
+const PRIMITIVES: &[&str] = &[
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "isize",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "usize",
+    "f16",
+    "f32",
+    "f64",
+    "f128",
+    "bool",
+    "char",
+    "str",
+    "never",
+    "unit",
+    "pointer",
+    "reference",
+    "fn",
+    "tuple",
+    "slice",
+    "array",
+];
+
 pub struct Resolver<'a> {
+    krate: &'a Crate,
     metadata: &'a Metadata,
     index: index::Tree<'a>,
     paths: paths::Tree<'a>,
     crate_to_package: HashMap<String, &'a PackageId>,
-    options: &'a ResolverOptions,
+    options: &'a ResolverOptions<'a>,
+    /// Packages we already warned about not being published, to warn only once.
+    warned_unpublished: RefCell<HashSet<&'a str>>,
 }
 
-pub struct ResolverOptions {
+pub struct ResolverOptions<'a> {
     pub link_to_latest: bool,
+    /// Url template with `{package}`, `{version}` and `{name}` placeholders.
+    pub docs_rs_url: &'a str,
+    /// Url template with a `{name}` placeholder.
+    pub std_docs_url: &'a str,
+    /// Warn when linking to docs.rs for a package that is not published to crates.io.
+    pub check_publish: bool,
 }
 
 impl<'a> Resolver<'a> {
     pub fn new(
         krate: &'a Crate,
         metadata: &'a Metadata,
-        options: &'a ResolverOptions,
+        options: &'a ResolverOptions<'a>,
     ) -> Result<Self> {
         Ok(Self {
+            krate,
             metadata,
             index: index::Tree::new(krate)?,
             paths: paths::Tree::new(krate),
@@ -35,10 +83,31 @@ impl<'a> Resolver<'a> {
                 .map(|p| (p.name.as_ref().replace('-', "_"), &p.id))
                 .collect(),
             options,
+            warned_unpublished: RefCell::default(),
         })
     }
 
     pub fn item_url(&self, id: Id) -> Result<String> {
+        // The path of a primitive can point to `core` or be missing entirely,
+        // but the primitives are always documented in `std`.
+        if let Some(name) = self.primitive_name(id)
+            && PRIMITIVES.contains(&name)
+        {
+            return Ok(format!("{}primitive.{name}.html", self.crate_doc_url("std")));
+        }
+
+        // `[crate]` links to the crate root, which is the documentation url of the crate itself.
+        if id == self.krate.root
+            && let Some(name) = self.krate.index.get(&id).and_then(|item| item.name.as_deref())
+        {
+            return Ok(self.crate_doc_url(name));
+        }
+
+        // rustdoc generates no page for a hidden item, so a link to it would be broken
+        if self.krate.index.get(&id).is_some_and(index::is_doc_hidden) {
+            bail!("link target is doc(hidden)");
+        }
+
         let path = self.item_path(id)?;
         let mut url = String::new();
 
@@ -58,6 +127,52 @@ impl<'a> Resolver<'a> {
         Ok(url)
     }
 
+    /// Returns where the path of an item is found, if anywhere.
+    pub fn item_source(&self, id: Id) -> Option<ItemSource> {
+        if self.index.path_to(id).is_some() {
+            Some(ItemSource::Index)
+        } else if self.paths.path_to(id).is_some() {
+            Some(ItemSource::Paths)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the `#[doc(cfg(...))]` of an item formatted for the documentation.
+    pub fn item_doc_cfg(&self, id: Id) -> Option<String> {
+        index::doc_cfg(self.krate.index.get(&id)?)
+    }
+
+    /// Iterates over all items of the documented crate that aren't `doc(hidden)`,
+    /// yielding their path and url.
+    pub fn all_items(&self) -> impl Iterator<Item = (Vec<String>, String)> {
+        self.index.ids().filter_map(|id| {
+            if self.krate.index.get(&id).is_some_and(index::is_doc_hidden) {
+                return None;
+            }
+
+            let path = self.index.path_to(id)?;
+            let url = self.item_url(id).ok()?;
+            Some((path.iter().rev().map(|item| item.name.to_string()).collect(), url))
+        })
+    }
+
+    fn primitive_name(&self, id: Id) -> Option<&'a str> {
+        if let Some(item) = self.krate.index.get(&id) {
+            return match &item.inner {
+                ItemEnum::Primitive(primitive) => Some(&primitive.name),
+                _ => None,
+            };
+        }
+
+        let summary = self.krate.paths.get(&id)?;
+
+        match summary.kind {
+            ItemKind::Primitive => summary.path.last().map(String::as_str),
+            _ => None,
+        }
+    }
+
     fn item_path(&self, id: Id) -> Result<Vec<PathItem<'a>>> {
         if let Some(path) = self.index.path_to(id) {
             return Ok(path);
@@ -74,12 +189,24 @@ impl<'a> Resolver<'a> {
 
     fn crate_doc_url(&self, name: &str) -> String {
         if matches!(name, "core" | "alloc" | "std") {
-            format!("https://doc.rust-lang.org/{name}/")
+            self.options.std_docs_url.replace("{name}", name)
         } else {
-            let metadata = &self.metadata;
+            let metadata: &'a Metadata = self.metadata;
             let package_id = self.crate_to_package.get(name);
             let package = package_id.map(|&p| &metadata[p]);
             let package_name = package.map(|p| p.name.as_str()).unwrap_or(name);
+
+            if self.options.check_publish
+                && self.options.docs_rs_url.starts_with("https://docs.rs/")
+                && let Some(package) = package
+                && !is_published(package)
+                && self.warned_unpublished.borrow_mut().insert(package.name.as_str())
+            {
+                warn!(
+                    "package {package_name} may not be published; generated docs.rs links may be broken, \
+                    set `docs-rs-url` to link to another documentation server"
+                );
+            }
             let from_workspace = package_id.map(|&p| metadata.workspace_members.contains(p));
             let link_to_latest = self.options.link_to_latest && from_workspace.unwrap_or(false);
 
@@ -91,11 +218,28 @@ impl<'a> Resolver<'a> {
                 "latest".to_string()
             };
 
-            format!("https://docs.rs/{package_name}/{version}/{name}/")
+            self.options
+                .docs_rs_url
+                .replace("{package}", package_name)
+                .replace("{version}", &version)
+                .replace("{name}", name)
         }
     }
 }
 
+/// Whether the package can be published to crates.io and would thus be on docs.rs.
+fn is_published(package: &Package) -> bool {
+    package.publish.as_ref().is_none_or(|registries| registries.iter().any(|r| r == "crates-io"))
+}
+
+/// Where an item's path was found, see [`Resolver::item_source`].
+pub enum ItemSource {
+    /// The index of the documented crate.
+    Index,
+    /// The paths map, which also has items of other crates.
+    Paths,
+}
+
 #[derive(Debug)]
 struct PathItem<'a> {
     name: &'a str,
@@ -13,6 +13,7 @@ use rustdoc_types::{Crate, Id};
 use super::{Kind, PathItem, index::simple::SimpleItemKind};
 
 use simple::SimpleItem;
+pub use simple::{doc_cfg, is_doc_hidden};
 
 pub struct Tree<'a> {
     inv_tree: HashMap<Id, Value<'a>>,
@@ -38,7 +39,7 @@ impl<'a> Tree<'a> {
             //
             // The `ExternCrate`... I have no idea if or where that would
             // come up and how it would be handled so for now we filter that out.
-            let Some(mut child_kind) = item_kind(&child_item.kind) else {
+            let Some(mut child_kind) = item_kind(child_item.kind) else {
                 continue;
             };
 
@@ -91,6 +92,10 @@ impl<'a> Tree<'a> {
         Ok(Self { inv_tree })
     }
 
+    pub fn ids(&self) -> impl Iterator<Item = Id> {
+        self.inv_tree.keys().copied()
+    }
+
     pub fn path_to(&self, mut id: Id) -> Option<Vec<PathItem<'a>>> {
         let mut path = vec![];
 
@@ -115,12 +120,14 @@ struct Value<'a> {
     name: &'a str,
 }
 
+/// Returns `None` for items that are not [documentable](SimpleItemKind::is_documentable).
 #[allow(clippy::unneeded_struct_pattern)]
-fn item_kind(item: &SimpleItemKind) -> Option<Kind> {
-    Some(match *item {
+fn item_kind(item: SimpleItemKind) -> Option<Kind> {
+    Some(match item {
+        SimpleItemKind::ExternCrate { .. } | SimpleItemKind::Use { .. } | SimpleItemKind::Impl => {
+            return None;
+        }
         SimpleItemKind::Module { .. } => Kind::Module,
-        SimpleItemKind::ExternCrate { .. } => return None,
-        SimpleItemKind::Use { .. } => return None,
         SimpleItemKind::Union { .. } => Kind::Union,
         SimpleItemKind::Struct { .. } => Kind::Struct,
         SimpleItemKind::StructField { .. } => Kind::StructField,
@@ -129,7 +136,6 @@ fn item_kind(item: &SimpleItemKind) -> Option<Kind> {
         SimpleItemKind::Function { .. } => Kind::Function,
         SimpleItemKind::Trait { .. } => Kind::Trait,
         SimpleItemKind::TraitAlias { .. } => Kind::TraitAlias,
-        SimpleItemKind::Impl { .. } => return None,
         SimpleItemKind::TypeAlias { .. } => Kind::TypeAlias,
         SimpleItemKind::Constant { .. } => Kind::Constant,
         SimpleItemKind::Static { .. } => Kind::Static,
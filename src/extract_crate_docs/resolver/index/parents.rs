@@ -3,12 +3,15 @@
 //! We need to choose a parent from multiple candidates:
 //! - prefer the shortest path for items, potentially through an `#[doc(inline)]`'ed `use`
 //! - don't choose non-`#[doc(inline)]`ed `use`s unless they're the only path
+//!
+//! Modules can (transitively) re-export themselves. We keep track of the items
+//! on the current path and don't descend into an item that is already on it.
 
-use std::collections::{HashMap, hash_map::Entry};
+use std::collections::{HashMap, HashSet, hash_map::Entry};
 
 use color_eyre::eyre::{Result, bail};
 use rustdoc_types::Id;
-use tracing::error_span;
+use tracing::{error_span, trace};
 
 use super::simple::{SimpleItem, SimpleItemKind};
 
@@ -16,27 +19,21 @@ const RECURSION_LIMIT: usize = 64;
 
 pub fn parents(index: &HashMap<Id, SimpleItem>, root: Id) -> Result<HashMap<Id, Id>> {
     let mut parents = HashMap::new();
-    parents_recurse(index, &mut parents, root, 0, PathList::EMPTY)?;
+    let mut on_path = HashSet::new();
+    parents_recurse(index, &mut parents, &mut on_path, root, 0, PathList::EMPTY)?;
     Ok(parents.into_iter().map(|(child_id, parent)| (child_id, parent.id)).collect())
 }
 
 fn parents_recurse<'a>(
     index: &HashMap<Id, SimpleItem<'a>>,
     parents: &mut HashMap<Id, Parent>,
+    on_path: &mut HashSet<Id>,
     parent_id: Id,
     depth: usize,
     path_for_error: PathList<'a>,
 ) -> Result<()> {
     if path_for_error.len > RECURSION_LIMIT {
-        let item_path = path_for_error
-            .iter()
-            .filter(|name| !name.is_empty())
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev()
-            .collect::<Vec<_>>()
-            .join("::");
-
+        let item_path = path_for_error.item_path();
         let _span = error_span!("", item_path).entered();
         bail!("recursed too deep while resolving item paths ({RECURSION_LIMIT})");
     }
@@ -45,6 +42,8 @@ fn parents_recurse<'a>(
         return Ok(());
     };
 
+    on_path.insert(parent_id);
+
     let parent_is_use = matches!(&parent_item.kind, SimpleItemKind::Use { .. });
 
     let parent = Parent {
@@ -62,6 +61,12 @@ fn parents_recurse<'a>(
             continue;
         };
 
+        if on_path.contains(&child_id) {
+            let item_path = path_for_error.append(parent_item.name).item_path();
+            trace!(item_path, child = child_item.name, "skipping cyclic re-export");
+            continue;
+        }
+
         match parents.entry(child_id) {
             Entry::Occupied(mut entry) => {
                 if parent.is_better_than(entry.get()) {
@@ -81,12 +86,15 @@ fn parents_recurse<'a>(
         parents_recurse(
             index,
             parents,
+            on_path,
             child_id,
             child_depth,
             path_for_error.append(parent_item.name),
         )?;
     }
 
+    on_path.remove(&parent_id);
+
     Ok(())
 }
 
@@ -102,6 +110,12 @@ impl<'a> PathList<'a> {
         PathList { node: Some(PathNode { prev: self.node.as_ref(), name }), len: self.len + 1 }
     }
 
+    fn item_path(&self) -> String {
+        let mut names = self.iter().filter(|name| !name.is_empty()).collect::<Vec<_>>();
+        names.reverse();
+        names.join("::")
+    }
+
     fn iter(&self) -> impl Iterator<Item = &'a str> {
         let mut next = self.node.as_ref();
 
@@ -1,6 +1,7 @@
 //! Parses `.index` into a simpler representation fitting our use case.
 
 use rustdoc_types::{Attribute, Crate, Function, Id, Item, ItemEnum, StructKind, VariantKind};
+use syn::punctuated::Punctuated;
 
 pub struct SimpleItem<'a> {
     pub name: &'a str,
@@ -39,6 +40,13 @@ pub enum SimpleItemKind {
     AssocType,
 }
 
+impl SimpleItemKind {
+    /// Whether this item has its own documentation (a page or an anchor) that we can link to.
+    pub fn is_documentable(self) -> bool {
+        !matches!(self, Self::ExternCrate | Self::Use { .. } | Self::Impl)
+    }
+}
+
 fn name(item: &Item) -> &str {
     item.name.as_deref().unwrap_or("")
 }
@@ -150,6 +158,108 @@ fn is_doc_inline(item: &Item) -> bool {
     false
 }
 
+/// Returns the `#[doc(cfg(...))]` predicate of an item formatted for the documentation,
+/// e.g. ``feature `foo` `` or `` `all(feature = "a", feature = "b")` ``.
+pub fn doc_cfg(item: &Item) -> Option<String> {
+    let cfgs = item
+        .attrs
+        .iter()
+        .flat_map(|attr| match attr {
+            Attribute::Other(attr_str) => doc_cfg_predicates(attr_str),
+            _ => vec![],
+        })
+        .collect::<Vec<_>>();
+
+    match cfgs.as_slice() {
+        [] => None,
+        [cfg] => Some(format_cfg(cfg)),
+        cfgs => {
+            Some(format!("`all({})`", cfgs.iter().map(render_cfg).collect::<Vec<_>>().join(", ")))
+        }
+    }
+}
+
+/// Returns the predicates of all `cfg(...)` in a `#[doc(...)]` attribute.
+pub(super) fn doc_cfg_predicates(attr_str: &str) -> Vec<syn::Meta> {
+    doc_attributes(attr_str)
+        .into_iter()
+        .filter_map(|meta| match meta {
+            syn::Meta::List(list) if list.path.is_ident("cfg") => list.parse_args().ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns whether an item has `#[doc(hidden)]`, rustdoc generates no page for it.
+pub fn is_doc_hidden(item: &Item) -> bool {
+    item.attrs.iter().any(|attr| match attr {
+        Attribute::Other(attr_str) => {
+            doc_attributes(attr_str).iter().any(|meta| meta.path().is_ident("hidden"))
+        }
+        _ => false,
+    })
+}
+
+/// Returns the arguments of a `#[doc(...)]` attribute, like `hidden` or `cfg(unix)`.
+pub(super) fn doc_attributes(attr_str: &str) -> Vec<syn::Meta> {
+    let Ok(attr) = parse_attr_str(attr_str) else {
+        return vec![];
+    };
+
+    if !attr.path().is_ident("doc") {
+        return vec![];
+    }
+
+    let Ok(metas) = attr.parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+    else {
+        return vec![];
+    };
+
+    metas.into_iter().collect()
+}
+
+pub(super) fn format_cfg(cfg: &syn::Meta) -> String {
+    if let syn::Meta::NameValue(name_value) = cfg
+        && name_value.path.is_ident("feature")
+        && let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(feature), .. }) = &name_value.value
+    {
+        return format!("feature `{}`", feature.value());
+    }
+
+    format!("`{}`", render_cfg(cfg))
+}
+
+/// Renders a cfg predicate with normalized whitespace.
+fn render_cfg(cfg: &syn::Meta) -> String {
+    fn path(path: &syn::Path) -> String {
+        path.segments.iter().map(|s| s.ident.to_string()).collect::<Vec<_>>().join("::")
+    }
+
+    match cfg {
+        syn::Meta::Path(p) => path(p),
+        syn::Meta::NameValue(name_value) => {
+            let value = match &name_value.value {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(str), .. }) => {
+                    str.token().to_string()
+                }
+                _ => "_".to_string(),
+            };
+
+            format!("{} = {value}", path(&name_value.path))
+        }
+        syn::Meta::List(list) => {
+            let args = match list
+                .parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+            {
+                Ok(args) => args.iter().map(render_cfg).collect::<Vec<_>>().join(", "),
+                Err(_) => list.tokens.to_string(),
+            };
+
+            format!("{}({args})", path(&list.path))
+        }
+    }
+}
+
 /// `Attribute` does not implement `Parse` (WHY NOT?) so we need to do it ourselves.
 fn parse_attr_str(str: &str) -> syn::Result<syn::Attribute> {
     struct Helper(syn::Attribute);
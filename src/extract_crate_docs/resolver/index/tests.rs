@@ -6,7 +6,11 @@ use rustdoc_types::{Crate, Id};
 
 use crate::{rustdoc_json, tests::TreeFormatter};
 
-use super::{Tree, Value};
+use super::{
+    super::Kind,
+    Tree, Value, item_kind,
+    simple::{SimpleItemKind, doc_attributes, doc_cfg_predicates, format_cfg},
+};
 
 #[test]
 fn test_tree() {
@@ -23,6 +27,7 @@ fn test_tree() {
         package,
         package_target,
         toolchain: Some("nightly-2026-06-24"),
+        no_rustup: false,
         all_features: false,
         no_default_features: false,
         features: &mut None.into_iter(),
@@ -31,6 +36,7 @@ fn test_tree() {
         target_dir: None,
         quiet: false,
         document_private_items: false,
+        rustdoc_args: &[],
         no_deps: false,
         output: rustdoc_json::CommandOutput::Inherit,
     })
@@ -145,3 +151,45 @@ struct Branch<'a> {
     value: &'a Value<'a>,
     children: Vec<Id>,
 }
+
+#[test]
+fn test_item_kind() {
+    // these don't have their own documentation, the tree skips them
+    assert!(item_kind(SimpleItemKind::ExternCrate).is_none());
+    assert!(item_kind(SimpleItemKind::Use { inline: false }).is_none());
+    assert!(item_kind(SimpleItemKind::Impl).is_none());
+
+    assert!(matches!(item_kind(SimpleItemKind::Struct), Some(Kind::Struct)));
+    assert!(matches!(item_kind(SimpleItemKind::Function { has_body: true }), Some(Kind::Function)));
+}
+
+#[test]
+fn test_doc_cfg() {
+    fn doc_cfg(attr: &str) -> Vec<String> {
+        doc_cfg_predicates(attr).iter().map(format_cfg).collect()
+    }
+
+    assert_eq!(doc_cfg(r#"#[doc(cfg(feature = "foo"))]"#), ["feature `foo`"]);
+    assert_eq!(
+        doc_cfg(r#"#[doc(cfg(all(feature = "a",feature="b")))]"#),
+        [r#"`all(feature = "a", feature = "b")`"#]
+    );
+    assert_eq!(doc_cfg(r#"#[doc(cfg(not(unix)))]"#), ["`not(unix)`"]);
+    assert_eq!(doc_cfg(r#"#[doc(inline)]"#), Vec::<String>::new());
+    assert_eq!(doc_cfg(r#"#[cfg(feature = "foo")]"#), Vec::<String>::new());
+}
+
+#[test]
+fn test_doc_attributes() {
+    fn paths(attr: &str) -> Vec<String> {
+        doc_attributes(attr)
+            .iter()
+            .map(|meta| meta.path().get_ident().unwrap().to_string())
+            .collect()
+    }
+
+    assert_eq!(paths("#[doc(hidden)]"), ["hidden"]);
+    assert_eq!(paths("#[doc(hidden, cfg(unix))]"), ["hidden", "cfg"]);
+    assert_eq!(paths(r#"#[doc = "hidden"]"#), Vec::<String>::new());
+    assert_eq!(paths("#[must_use]"), Vec::<String>::new());
+}
@@ -0,0 +1,146 @@
+use std::{collections::HashMap, fs};
+
+use cargo_metadata::{Metadata, MetadataCommand};
+use rustdoc_types::{Crate, Id, ItemEnum};
+
+use crate::{
+    config::{DEFAULT_DOCS_RS_URL, DEFAULT_STD_DOCS_URL},
+    rustdoc_json,
+};
+
+use super::{Resolver, ResolverOptions};
+
+const OPTIONS: ResolverOptions = ResolverOptions {
+    link_to_latest: false,
+    docs_rs_url: DEFAULT_DOCS_RS_URL,
+    std_docs_url: DEFAULT_STD_DOCS_URL,
+    check_publish: false,
+};
+
+/// Generates and parses the rustdoc json of `tests/test-crate` with the given `features`.
+fn test_crate(features: &[&str]) -> (Metadata, Crate) {
+    const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+    let metadata =
+        MetadataCommand::new().manifest_path(format!("{MANIFEST_DIR}/Cargo.toml")).exec().unwrap();
+
+    let package = metadata.packages.iter().find(|p| p.name.as_str() == "test-crate").unwrap();
+    let package_target = package.targets.iter().find(|t| t.is_lib()).unwrap();
+
+    // other features get their own target directory so tests don't overwrite each other's json
+    let target_dir = (!features.is_empty())
+        .then(|| metadata.target_directory.join(features.join("-")).into_std_path_buf());
+
+    let (_, path) = rustdoc_json::generate(rustdoc_json::Options {
+        metadata: &metadata,
+        package,
+        package_target,
+        toolchain: Some("nightly-2026-06-24"),
+        no_rustup: false,
+        all_features: false,
+        no_default_features: false,
+        features: &mut features.iter().copied(),
+        manifest_path: None,
+        target: None,
+        target_dir: target_dir.as_deref(),
+        quiet: false,
+        document_private_items: false,
+        rustdoc_args: &[],
+        no_deps: false,
+        output: rustdoc_json::CommandOutput::Inherit,
+    })
+    .unwrap();
+
+    let json = fs::read_to_string(path).expect("failed to read generated rustdoc json");
+    let krate: Crate = serde_json::from_str(&json).expect("failed to parse generated rustdoc json");
+
+    (metadata, krate)
+}
+
+#[test]
+fn test_crate_links() {
+    let (metadata, krate) = test_crate(&[]);
+    let resolver = Resolver::new(&krate, &metadata, &OPTIONS).unwrap();
+
+    // what `[crate]` resolves to
+    assert_eq!(
+        resolver.item_url(krate.root).unwrap(),
+        "https://docs.rs/test-crate/0.0.0/test_crate/"
+    );
+
+    // what `[crate::MyStruct]` resolves to
+    let my_struct = struct_id(&krate, "MyStruct");
+    assert_eq!(
+        resolver.item_url(my_struct).unwrap(),
+        "https://docs.rs/test-crate/0.0.0/test_crate/struct.MyStruct.html"
+    );
+}
+
+#[test]
+fn test_all_items() {
+    let (metadata, krate) = test_crate(&[]);
+    let resolver = Resolver::new(&krate, &metadata, &OPTIONS).unwrap();
+
+    let items = resolver
+        .all_items()
+        .map(|(path, url)| (path.join("::"), url))
+        .collect::<HashMap<String, String>>();
+
+    let url = |path: &str| {
+        items.get(path).map(|url| url.trim_start_matches("https://docs.rs/test-crate/0.0.0/"))
+    };
+
+    assert_eq!(url("test_crate"), Some("test_crate/"));
+    assert_eq!(url("test_crate::MyStruct"), Some("test_crate/struct.MyStruct.html"));
+    assert_eq!(
+        url("test_crate::MyStruct::my_field"),
+        Some("test_crate/struct.MyStruct.html#structfield.my_field")
+    );
+    assert_eq!(
+        url("test_crate::MyStruct::my_method"),
+        Some("test_crate/struct.MyStruct.html#method.my_method")
+    );
+    assert_eq!(url("test_crate::my_module"), Some("test_crate/my_module/index.html"));
+    assert_eq!(url("test_crate::my_function"), Some("test_crate/fn.my_function.html"));
+
+    // rustdoc generates no page for a hidden item
+    assert_eq!(url("test_crate::MyHiddenStruct"), None);
+}
+
+#[test]
+fn test_cyclic_reexports() {
+    let (metadata, krate) = test_crate(&["recurse"]);
+    let resolver = Resolver::new(&krate, &metadata, &OPTIONS).unwrap();
+
+    let items = resolver
+        .all_items()
+        .map(|(path, url)| (path.join("::"), url))
+        .collect::<HashMap<String, String>>();
+
+    let url = |path: &str| {
+        items.get(path).map(|url| url.trim_start_matches("https://docs.rs/test-crate/0.0.0/"))
+    };
+
+    // `a` and `b` re-export each other
+    assert_eq!(url("test_crate::cyclic::a"), Some("test_crate/cyclic/a/index.html"));
+    assert_eq!(url("test_crate::cyclic::b"), Some("test_crate/cyclic/b/index.html"));
+    assert_eq!(url("test_crate::cyclic::a::Robin"), Some("test_crate/cyclic/a/struct.Robin.html"));
+
+    // `a` and `n` re-export each other through `crate::`
+    assert_eq!(url("test_crate::a::Batman"), Some("test_crate/a/struct.Batman.html"));
+}
+
+fn struct_id(krate: &Crate, name: &str) -> Id {
+    let root = &krate.index[&krate.root];
+    let ItemEnum::Module(module) = &root.inner else { panic!("crate root is not a module") };
+
+    module
+        .items
+        .iter()
+        .copied()
+        .find(|id| {
+            let item = &krate.index[id];
+            item.name.as_deref() == Some(name) && matches!(item.inner, ItemEnum::Struct(_))
+        })
+        .unwrap()
+}
@@ -2,22 +2,52 @@
 mod tests;
 
 use core::{fmt::Write, ops::Range};
-use std::collections::{HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 
 use crate::{
     markdown::{self, Tree, format_link_destination},
-    markdown_rs::event::Name,
+    markdown_rs::{event::Name, util::normalize_identifier::normalize_identifier},
     string_replacer::StringReplacer,
 };
 
-#[derive(Default)]
 pub struct RewriteMarkdownOptions {
     pub shrink_headings: i8,
+    pub strip_hidden_tests: bool,
+    /// Warn about code blocks whose language is unknown, see [`markdown::lang_string::unknown_lang`].
+    pub warn_unknown_lang: bool,
+    /// Turn references into inline links and remove all definitions,
+    /// except those that images still refer to.
+    /// Otherwise the definitions stay and their destinations are resolved in place.
+    pub inline_links: bool,
     pub links: Vec<(String, Option<String>)>,
+    /// Text to put after links, keyed by their destination like `links`.
+    pub cfg_annotations: Vec<(String, String)>,
+}
+
+impl Default for RewriteMarkdownOptions {
+    fn default() -> Self {
+        Self {
+            shrink_headings: 0,
+            strip_hidden_tests: true,
+            warn_unknown_lang: false,
+            inline_links: false,
+            links: vec![],
+            cfg_annotations: vec![],
+        }
+    }
 }
 
 pub fn rewrite_markdown(markdown: &str, options: &RewriteMarkdownOptions) -> String {
-    let markdown = add_definitions(markdown, options);
+    let mut markdown = add_definitions(markdown, options);
+
+    // Setext headings are only rewritten when the heading levels change.
+    if options.shrink_headings != 0 {
+        markdown = setext_headings_to_atx(&markdown);
+    }
+
     rewrite(&markdown, options)
 }
 
@@ -44,6 +74,43 @@ fn add_definitions(markdown: &str, options: &RewriteMarkdownOptions) -> String {
     markdown
 }
 
+/// Turns setext headings (underlined with `===` or `---`) into atx headings (prefixed with `#`),
+/// so [`rewrite`] can shrink them like any other heading.
+fn setext_headings_to_atx(markdown: &str) -> String {
+    let tree = Tree::new(markdown);
+    let mut out = StringReplacer::new(markdown);
+
+    for node in tree.depth_first() {
+        if node.name() != Name::HeadingSetext {
+            continue;
+        }
+
+        let Some(text) = node.child(Name::HeadingSetextText) else {
+            continue;
+        };
+
+        let Some(underline) = node.child(Name::HeadingSetextUnderline) else {
+            continue;
+        };
+
+        let hashes = if underline.str().trim_start().starts_with('=') { "#" } else { "##" };
+        out.insert(text.byte_range().start, format!("{hashes} "));
+
+        // An atx heading can't span multiple lines.
+        // The joined lines lose their container prefix like `> ` or the indentation of a list item.
+        for line_ending in text.descendants_with_name(Name::LineEnding) {
+            let Range { start, end } = line_ending.byte_range();
+            let rest = &markdown[end..];
+            let prefix_len = rest.len() - rest.trim_start_matches(['>', ' ', '\t']).len();
+            out.replace(start..end + prefix_len, " ");
+        }
+
+        out.remove(text.byte_range().end..underline.byte_range().end);
+    }
+
+    out.finish()
+}
+
 fn rewrite(markdown: &str, options: &RewriteMarkdownOptions) -> String {
     let links: HashMap<&str, Option<&str>> =
         options.links.iter().map(|(k, v)| (k.as_str(), v.as_deref())).collect();
@@ -56,9 +123,25 @@ fn rewrite(markdown: &str, options: &RewriteMarkdownOptions) -> String {
 
     let mut out = StringReplacer::new(markdown);
     let unused_definitions = unused_definitions(&tree, options);
+    let inline_destinations =
+        if options.inline_links { inline_destinations(&tree, &links) } else { HashMap::new() };
+    let image_references =
+        if options.inline_links { image_references(&tree) } else { HashSet::new() };
+    let cfg_annotations: HashMap<&str, &str> =
+        options.cfg_annotations.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let reference_cfg_annotations = reference_cfg_annotations(&tree, &cfg_annotations);
+
+    // The first line of an alert blockquote like `> [!NOTE]`.
+    let mut alert_marker: Option<Range<usize>> = None;
 
     for node in tree.depth_first() {
         match node.name() {
+            Name::BlockQuote => {
+                if is_gfm_alert_blockquote(node.str()) {
+                    let start = node.byte_range().start;
+                    alert_marker = Some(start..end_of_line(markdown, start));
+                }
+            }
             Name::HeadingAtx => {
                 let Some(hashes) = node.child(Name::HeadingAtxSequence) else {
                     continue;
@@ -68,22 +151,34 @@ fn rewrite(markdown: &str, options: &RewriteMarkdownOptions) -> String {
                 let level = hashes.len() as i8;
                 let new_level = level.saturating_add(options.shrink_headings).clamp(1, 6);
                 let new_hashes = &"######"[..new_level as usize];
-                out.replace(hashes, new_hashes);
+
+                // Normalize the space before the text to a single one.
+                // A heading without text is left alone, it may be an intentional spacer.
+                match node.child(Name::HeadingAtxText) {
+                    Some(text) => {
+                        out.replace(hashes.start..text.byte_range().start, format!("{new_hashes} "))
+                    }
+                    None => out.replace(hashes, new_hashes),
+                }
             }
             Name::CodeFenced => {
                 if let Some(fence_info) = node.descendant(Name::CodeFencedFenceInfo) {
                     if !code_block_fence_is_rust(fence_info.str()) {
+                        if options.warn_unknown_lang {
+                            warn_unknown_lang(fence_info.str(), node.position().start.line);
+                        }
+
                         continue;
                     }
 
                     out.replace(fence_info.byte_range(), "rust");
-
-                    for child in node.children_with_name(Name::CodeFlowChunk) {
-                        clean_code_chunk(&mut out, markdown, child.byte_range());
-                    }
                 } else if let Some(fence) = node.descendant(Name::CodeFencedFenceSequence) {
                     out.insert(fence.byte_range().end, "rust");
+                } else {
+                    continue;
+                }
 
+                if options.strip_hidden_tests {
                     for child in node.children_with_name(Name::CodeFlowChunk) {
                         clean_code_chunk(&mut out, markdown, child.byte_range());
                     }
@@ -91,22 +186,52 @@ fn rewrite(markdown: &str, options: &RewriteMarkdownOptions) -> String {
             }
             Name::CodeIndented => {
                 let range = node.byte_range();
-                let mut last_space = None;
+
+                // The indentation of the current line, removed with the line's content.
+                let mut space = None;
+                // The line ending before the current line, removed with a hidden line.
+                let mut line_ending = None;
+                // A hidden first line takes the line ending after it instead.
+                let mut remove_next_line_ending = false;
 
                 out.insert(range.start, "```rust\n");
 
                 for child in node.children() {
                     match child.name() {
                         Name::SpaceOrTab => {
-                            last_space = Some(child);
+                            space = Some(child);
+                        }
+                        Name::LineEnding => {
+                            // a line of only whitespace
+                            if let Some(space) = space.take() {
+                                out.remove(space.byte_range());
+                            }
+
+                            if remove_next_line_ending {
+                                out.remove(child.byte_range());
+                                remove_next_line_ending = false;
+                            } else {
+                                line_ending = Some(child);
+                            }
                         }
                         Name::CodeFlowChunk => {
                             let space =
-                                last_space.expect("an indented codeblock must be indented (duh)");
+                                space.take().expect("an indented codeblock must be indented (duh)");
+
+                            let action = if options.strip_hidden_tests {
+                                clean_code_line(child.str())
+                            } else {
+                                None
+                            };
 
-                            match clean_code_line(child.str()) {
+                            match action {
                                 Some(CleanAction::RemoveLine) => {
-                                    out.remove(expand_to_line(markdown, child.byte_range()));
+                                    out.remove(space.byte_range().start..child.byte_range().end);
+
+                                    match line_ending.take() {
+                                        Some(line_ending) => out.remove(line_ending.byte_range()),
+                                        None => remove_next_line_ending = true,
+                                    }
                                 }
                                 Some(CleanAction::RemoveHash(idx)) => {
                                     let hash = idx + child.byte_range().start;
@@ -122,9 +247,18 @@ fn rewrite(markdown: &str, options: &RewriteMarkdownOptions) -> String {
                     }
                 }
 
+                if let Some(space) = space {
+                    out.remove(space.byte_range());
+                }
+
                 out.insert(range.end, "\n```");
             }
             Name::Link => {
+                // The `[!NOTE]` of an alert is not a link, we leave it as is.
+                if alert_marker.as_ref().is_some_and(|m| m.contains(&node.byte_range().start)) {
+                    continue;
+                }
+
                 // The links we care about have a label text.
                 let Some(label_text) = node.descendant(Name::LabelText) else {
                     continue;
@@ -155,39 +289,74 @@ fn rewrite(markdown: &str, options: &RewriteMarkdownOptions) -> String {
                     // We replace the link destination
                     // e.g. `[Vec](Vec)` -> `[Vec](https://doc.rust-lang.org/std/vec/struct.Vec.html)`
                     out.replace(dest.byte_range(), format_link_destination(new_url));
+
+                    if let Some(annotation) = cfg_annotations.get(dest_string.str()) {
+                        out.insert(node.byte_range().end, format!(" {annotation}"));
+                    }
+
                     continue;
                 }
 
                 // Is this a reference like `[a][b]` or `[a][]`?
                 if let Some(reference) = node.child(Name::Reference) {
-                    let identifier = match reference.child(Name::ReferenceString) {
-                        Some(string) => string.str(),
-                        None => label_text.str(),
+                    let (identifier, label) = match reference.child(Name::ReferenceString) {
+                        Some(string) => (string.str(), Cow::Borrowed(label_text.str())),
+                        // A collapsed reference like `[a][]` is displayed like a shortcut.
+                        None => (label_text.str(), strip_disambiguator(label_text.str())),
                     };
 
-                    let Some(&resolved) = links.get(identifier) else {
-                        continue;
-                    };
+                    let unresolved = links.get(identifier) == Some(&None);
 
-                    if resolved.is_none() {
+                    if unresolved {
                         // This is a reference we failed to resolve with rustdoc.
                         // We replace the reference with its label e.g. `[a][b]` -> `a`
-                        out.replace(node.byte_range(), label_text.str());
-                    };
+                        out.replace(node.byte_range(), label.into_owned());
+                    } else if let Some(destination) =
+                        inline_destinations.get(&normalize_identifier(identifier))
+                    {
+                        // We replace the reference with an inline link e.g. `[a][b]` -> `[a](c)`
+                        out.replace(node.byte_range(), format!("[{label}]({destination})"));
+                    } else if label != label_text.str() {
+                        // We remove the disambiguator from the label e.g. `[fn@a][]` -> `[a][fn@a]`
+                        out.replace(node.byte_range(), format!("[{label}][{identifier}]"));
+                    }
+
+                    if !unresolved
+                        && let Some(annotation) =
+                            reference_cfg_annotations.get(&normalize_identifier(identifier))
+                    {
+                        out.insert(node.byte_range().end, format!(" {annotation}"));
+                    }
 
                     continue;
                 }
 
                 // This can now only be a shortcut like `[a]`.
 
-                let Some(&resolved) = links.get(label_text.str()) else {
-                    continue;
-                };
+                let identifier = label_text.str();
+                let label = strip_disambiguator(identifier);
+                let unresolved = links.get(identifier) == Some(&None);
 
-                if resolved.is_none() {
+                if unresolved {
                     // This points to an reference we failed to resolve with rustdoc.
                     // We replace the shorcut with its label e.g. `[a]` -> `a`
-                    out.replace(node.byte_range(), label_text.str());
+                    // A code span label stays a code span e.g. ``[`a`]`` -> `` `a` ``
+                    out.replace(node.byte_range(), label.into_owned());
+                } else if let Some(destination) =
+                    inline_destinations.get(&normalize_identifier(identifier))
+                {
+                    // We replace the shortcut with an inline link e.g. `[a]` -> `[a](c)`
+                    out.replace(node.byte_range(), format!("[{label}]({destination})"));
+                } else if label != identifier {
+                    // We remove the disambiguator from the label e.g. `[fn@a]` -> `[a][fn@a]`
+                    out.replace(node.byte_range(), format!("[{label}][{identifier}]"));
+                }
+
+                if !unresolved
+                    && let Some(annotation) =
+                        reference_cfg_annotations.get(&normalize_identifier(identifier))
+                {
+                    out.insert(node.byte_range().end, format!(" {annotation}"));
                 }
             }
             Name::Definition => {
@@ -203,6 +372,17 @@ fn rewrite(markdown: &str, options: &RewriteMarkdownOptions) -> String {
                     continue;
                 };
 
+                if options.inline_links
+                    && !image_references.contains(&normalize_identifier(label.str()))
+                {
+                    // The blank lines after it go too, so no run of blank lines is left
+                    // where the definitions were.
+                    let end =
+                        end_of_blank_lines(markdown, end_of_line(markdown, node.byte_range().end));
+                    out.remove(node.byte_range().start..end);
+                    continue;
+                }
+
                 if dest_string.str() == PLACEHOLDER_DESTINATION
                     || unused_definitions.contains(label.str())
                 {
@@ -264,6 +444,174 @@ fn unused_definitions<'a>(
     all_definitions.difference(&used_definitions).copied().collect()
 }
 
+/// Maps the normalized labels of the link definitions to the destination (and title)
+/// that an inline link needs to point to the same place.
+fn inline_destinations(
+    tree: &Tree,
+    links: &HashMap<&str, Option<&str>>,
+) -> HashMap<String, String> {
+    let mut destinations = HashMap::new();
+
+    for node in tree.depth_first() {
+        if node.name() != Name::Definition {
+            continue;
+        }
+
+        let Some(dest) = node.child(Name::DefinitionDestination) else {
+            continue;
+        };
+
+        let Some(dest_string) = dest.descendant(Name::DefinitionDestinationString) else {
+            continue;
+        };
+
+        let Some(label) = node.descendant(Name::DefinitionLabelString) else {
+            continue;
+        };
+
+        if dest_string.str() == PLACEHOLDER_DESTINATION {
+            continue;
+        }
+
+        let mut destination = match links.get(dest_string.str()) {
+            Some(Some(new_url)) => format_link_destination(new_url),
+            Some(None) => continue,
+            None => dest.str().to_string(),
+        };
+
+        if let Some(title) = node.child(Name::DefinitionTitle) {
+            destination.push(' ');
+            destination.push_str(title.str());
+        }
+
+        // When there are multiple definitions with the same label, the first one wins.
+        destinations.entry(normalize_identifier(label.str())).or_insert(destination);
+    }
+
+    destinations
+}
+
+/// Returns the normalized labels that images like `![a][b]` or `![a]` refer to.
+///
+/// Images are not turned into inline images, so their definitions must stay.
+fn image_references(tree: &Tree) -> HashSet<String> {
+    let mut references = HashSet::new();
+
+    for node in tree.depth_first() {
+        if node.name() != Name::Image || node.child(Name::Resource).is_some() {
+            continue;
+        }
+
+        let identifier = match node.descendant(Name::ReferenceString) {
+            Some(some) => some,
+            None => match node.descendant(Name::LabelText) {
+                Some(some) => some,
+                None => continue,
+            },
+        };
+
+        references.insert(normalize_identifier(identifier.str()));
+    }
+
+    references
+}
+
+/// Maps the normalized labels of the link definitions to the cfg annotation of their item.
+///
+/// The definitions we added for intra-doc links have the link as their label,
+/// the others have it as their destination.
+fn reference_cfg_annotations<'a>(
+    tree: &Tree,
+    cfg_annotations: &HashMap<&str, &'a str>,
+) -> HashMap<String, &'a str> {
+    let mut annotations = HashMap::new();
+
+    if cfg_annotations.is_empty() {
+        return annotations;
+    }
+
+    for node in tree.depth_first() {
+        if node.name() != Name::Definition {
+            continue;
+        }
+
+        let Some(label) = node.descendant(Name::DefinitionLabelString) else {
+            continue;
+        };
+
+        let destination = node.descendant(Name::DefinitionDestinationString);
+
+        let Some(&annotation) = destination
+            .and_then(|d| cfg_annotations.get(d.str()))
+            .or_else(|| cfg_annotations.get(label.str()))
+        else {
+            continue;
+        };
+
+        // When there are multiple definitions with the same label, the first one wins.
+        annotations.entry(normalize_identifier(label.str())).or_insert(annotation);
+    }
+
+    annotations
+}
+
+/// Removes a rustdoc disambiguator prefix like `fn@` from a link label,
+/// because rustdoc doesn't display it either.
+///
+/// Suffixes like `()` or `!` are displayed by rustdoc, so they are kept.
+///
+/// See <https://doc.rust-lang.org/rustdoc/write-documentation/linking-to-items-by-name.html#namespaces-and-disambiguators>.
+fn strip_disambiguator(label: &str) -> Cow<'_, str> {
+    const PREFIXES: &[&str] = &[
+        "struct",
+        "enum",
+        "trait",
+        "union",
+        "module",
+        "mod",
+        "const",
+        "constant",
+        "static",
+        "function",
+        "fn",
+        "method",
+        "derive",
+        "type",
+        "value",
+        "macro",
+        "prim",
+        "primitive",
+        "tyalias",
+        "typealias",
+        "field",
+        "variant",
+        "attr",
+    ];
+
+    fn strip(path: &str) -> &str {
+        match path.split_once('@') {
+            Some((prefix, rest)) if PREFIXES.contains(&prefix) => rest,
+            _ => path,
+        }
+    }
+
+    if let Some(code) = label.strip_prefix('`').and_then(|l| l.strip_suffix('`')) {
+        let stripped = strip(code);
+
+        if stripped.len() != code.len() {
+            return Cow::Owned(format!("`{stripped}`"));
+        }
+    } else {
+        let stripped = strip(label);
+
+        if stripped.len() != label.len() {
+            return Cow::Borrowed(stripped);
+        }
+    }
+
+    Cow::Borrowed(label)
+}
+
 fn start_of_line(markdown: &str, index: usize) -> usize {
     match markdown[..index].bytes().rposition(|b| b == b'\n') {
         Some(i) => i + 1,
@@ -278,6 +626,21 @@ fn end_of_line(markdown: &str, index: usize) -> usize {
     }
 }
 
+/// Returns the index after the blank lines that start at `index`.
+fn end_of_blank_lines(markdown: &str, mut index: usize) -> usize {
+    while index < markdown.len() {
+        let end = end_of_line(markdown, index);
+
+        if !markdown[index..end].trim().is_empty() {
+            break;
+        }
+
+        index = end;
+    }
+
+    index
+}
+
 fn expand_to_line(markdown: &str, range: Range<usize>) -> Range<usize> {
     start_of_line(markdown, range.start)..end_of_line(markdown, range.end)
 }
@@ -324,6 +687,31 @@ fn substr_range(str: &str, substr: &str) -> Range<usize> {
     start..end
 }
 
+/// Returns whether this blockquote is a GitHub alert like `> [!NOTE]`.
+///
+/// See <https://docs.github.com/en/get-started/writing-on-github/getting-started-with-writing-and-formatting-on-github/basic-writing-and-formatting-syntax#alerts>.
+fn is_gfm_alert_blockquote(blockquote: &str) -> bool {
+    const KINDS: &[&str] = &["NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION"];
+
+    let first_line = blockquote.lines().next().unwrap_or("");
+
+    let Some(marker) = first_line.trim().strip_prefix('>') else {
+        return false;
+    };
+
+    let Some(kind) = marker.trim().strip_prefix("[!").and_then(|m| m.strip_suffix(']')) else {
+        return false;
+    };
+
+    KINDS.iter().any(|k| k.eq_ignore_ascii_case(kind))
+}
+
+fn warn_unknown_lang(lang: &str, line: usize) {
+    if let Some(unknown) = markdown::lang_string::unknown_lang(lang) {
+        tracing::warn!(lang = unknown, line, "unknown code block language");
+    }
+}
+
 fn code_block_fence_is_rust(lang: &str) -> bool {
     match markdown::lang_string::is_rust(lang) {
         Ok(is_rust) => is_rust,
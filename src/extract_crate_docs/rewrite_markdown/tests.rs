@@ -2,9 +2,9 @@ use expect_test::expect;
 
 use crate::{
     extract_crate_docs::rewrite_markdown::{
-        RewriteMarkdownOptions, code_block_fence_is_rust, rewrite_markdown,
+        RewriteMarkdownOptions, code_block_fence_is_rust, is_gfm_alert_blockquote, rewrite_markdown,
     },
-    markdown::Tree,
+    markdown::{Tree, lang_string::unknown_lang},
     pretty_log,
 };
 
@@ -162,6 +162,347 @@ fn test_reference_unresolved() {
     assert_eq!(result, "Vec\n\n");
 }
 
+#[test]
+fn test_unresolved_keeps_code_span() {
+    let unresolved = |markdown: &str, identifier: &str| {
+        rewrite_markdown(
+            markdown,
+            &RewriteMarkdownOptions {
+                links: [(String::from(identifier), None)].into_iter().collect(),
+                ..Default::default()
+            },
+        )
+    };
+
+    assert_eq!(unresolved("[`Vec`]", "`Vec`"), "`Vec`\n\n");
+    assert_eq!(unresolved("[`Vec`][]", "`Vec`"), "`Vec`\n\n");
+    assert_eq!(unresolved("[`Vec`](Vec)", "Vec"), "`Vec`\n\n");
+    assert_eq!(unresolved("[`fn@foo`]", "`fn@foo`"), "`foo`\n\n");
+    assert_eq!(unresolved("[foo]", "foo"), "foo\n\n");
+}
+
+#[test]
+fn test_inline_links() {
+    let markdown = "\
+[Vec], [vector][vec-ref], [`Vec`][] and [rust].
+
+[vec-ref]: Vec
+[rust]: https://www.rust-lang.org \"Rust\"
+";
+
+    let result = rewrite_markdown(
+        markdown,
+        &RewriteMarkdownOptions {
+            links: [
+                (
+                    String::from("Vec"),
+                    Some(String::from("https://doc.rust-lang.org/alloc/vec/struct.Vec.html")),
+                ),
+                (
+                    String::from("`Vec`"),
+                    Some(String::from("https://doc.rust-lang.org/alloc/vec/struct.Vec.html")),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            inline_links: true,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        result,
+        "\
+[Vec](https://doc.rust-lang.org/alloc/vec/struct.Vec.html), \
+[vector](https://doc.rust-lang.org/alloc/vec/struct.Vec.html), \
+[`Vec`](https://doc.rust-lang.org/alloc/vec/struct.Vec.html) and \
+[rust](https://www.rust-lang.org \"Rust\").\n\n"
+    );
+}
+
+const SHARED_DEFINITION: &str = "\
+[first][vec-ref] and [second][vec-ref].
+
+[vec-ref]: Vec
+";
+
+#[test]
+fn test_shared_definition() {
+    let result = rewrite_markdown(
+        SHARED_DEFINITION,
+        &RewriteMarkdownOptions {
+            links: [(
+                String::from("Vec"),
+                Some(String::from("https://doc.rust-lang.org/alloc/vec/struct.Vec.html")),
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        result,
+        "\
+[first][vec-ref] and [second][vec-ref].
+
+[vec-ref]: https://doc.rust-lang.org/alloc/vec/struct.Vec.html\n\n\n"
+    );
+}
+
+#[test]
+fn test_shared_definition_inline_links() {
+    let result = rewrite_markdown(
+        SHARED_DEFINITION,
+        &RewriteMarkdownOptions {
+            links: [(
+                String::from("Vec"),
+                Some(String::from("https://doc.rust-lang.org/alloc/vec/struct.Vec.html")),
+            )]
+            .into_iter()
+            .collect(),
+            inline_links: true,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        result,
+        "\
+[first](https://doc.rust-lang.org/alloc/vec/struct.Vec.html) and \
+[second](https://doc.rust-lang.org/alloc/vec/struct.Vec.html).\n\n"
+    );
+}
+
+#[test]
+fn test_inline_links_keeps_image_definitions() {
+    let markdown = "\
+![logo][logo-ref] and [rust].
+
+[rust]: https://www.rust-lang.org
+[logo-ref]: logo.svg
+
+Done.
+";
+
+    let result = rewrite_markdown(
+        markdown,
+        &RewriteMarkdownOptions { inline_links: true, ..Default::default() },
+    );
+
+    assert_eq!(
+        result,
+        "\
+![logo][logo-ref] and [rust](https://www.rust-lang.org).
+
+[logo-ref]: logo.svg
+
+Done.
+"
+    );
+}
+
+#[test]
+fn test_inline_links_unresolved() {
+    let markdown = "[Vec] and [String]";
+
+    let result = rewrite_markdown(
+        markdown,
+        &RewriteMarkdownOptions {
+            links: [
+                (String::from("Vec"), None),
+                (
+                    String::from("String"),
+                    Some(String::from("https://doc.rust-lang.org/alloc/string/struct.String.html")),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            inline_links: true,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        result,
+        "Vec and [String](https://doc.rust-lang.org/alloc/string/struct.String.html)\n\n"
+    );
+}
+
+#[test]
+fn test_links_in_table() {
+    let markdown = "\
+| Item | Kind |
+|---|---|
+| [`MyStruct`] | struct |
+| [fn@my_function] | function |
+| [`Missing`] | unresolved |
+";
+
+    let result = rewrite_markdown(
+        markdown,
+        &RewriteMarkdownOptions {
+            links: [
+                (String::from("`MyStruct`"), Some(String::from("struct.MyStruct.html"))),
+                (String::from("fn@my_function"), Some(String::from("fn.my_function.html"))),
+                (String::from("`Missing`"), None),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        },
+    );
+
+    expect![[r#"
+        | Item | Kind |
+        |---|---|
+        | [`MyStruct`] | struct |
+        | [my_function][fn@my_function] | function |
+        | `Missing` | unresolved |
+
+
+        [`MyStruct`]: struct.MyStruct.html
+        [fn@my_function]: fn.my_function.html
+    "#]]
+    .assert_eq(&result);
+}
+
+#[test]
+fn test_disambiguated_links() {
+    let markdown = "[type@MyStruct], [`fn@my_function`], [macro@my_macro!] and [my_macro!()][]";
+
+    let result = rewrite_markdown(
+        markdown,
+        &RewriteMarkdownOptions {
+            links: [
+                (String::from("type@MyStruct"), Some(String::from("struct.MyStruct.html"))),
+                (String::from("`fn@my_function`"), Some(String::from("fn.my_function.html"))),
+                (String::from("macro@my_macro!"), None),
+                (String::from("my_macro!()"), Some(String::from("macro.my_macro.html"))),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        result,
+        "\
+[MyStruct][type@MyStruct], [`my_function`][`fn@my_function`], my_macro! and [my_macro!()][]\n\n\
+[type@MyStruct]: struct.MyStruct.html\n\
+[`fn@my_function`]: fn.my_function.html\n\
+[my_macro!()]: macro.my_macro.html\n"
+    );
+}
+
+#[test]
+fn test_suffix_disambiguators_are_kept() {
+    let markdown = "[foo()], [bar!], [`baz()`] and [fn@qux()]";
+
+    let result = rewrite_markdown(
+        markdown,
+        &RewriteMarkdownOptions {
+            links: [
+                (String::from("foo()"), Some(String::from("fn.foo.html"))),
+                (String::from("bar!"), None),
+                (String::from("`baz()`"), None),
+                (String::from("fn@qux()"), Some(String::from("fn.qux.html"))),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        result,
+        "\
+[foo()], bar!, `baz()` and [qux()][fn@qux()]\n\n\
+[foo()]: fn.foo.html\n\
+[fn@qux()]: fn.qux.html\n"
+    );
+}
+
+#[test]
+fn test_macro_links() {
+    let markdown = "[my_macro!], [`my_macro!`], [macro@my_macro!] and [`other_macro!`]";
+
+    let result = rewrite_markdown(
+        markdown,
+        &RewriteMarkdownOptions {
+            links: [
+                (String::from("my_macro!"), Some(String::from("macro.my_macro.html"))),
+                (String::from("`my_macro!`"), Some(String::from("macro.my_macro.html"))),
+                (String::from("macro@my_macro!"), Some(String::from("macro.my_macro.html"))),
+                (String::from("`other_macro!`"), None),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        result,
+        "\
+[my_macro!], [`my_macro!`], [my_macro!][macro@my_macro!] and `other_macro!`\n\n\
+[my_macro!]: macro.my_macro.html\n\
+[`my_macro!`]: macro.my_macro.html\n\
+[macro@my_macro!]: macro.my_macro.html\n"
+    );
+}
+
+#[test]
+fn test_disambiguated_links_inline() {
+    let markdown = "[fn@my_function]\n\n[fn@my_function]: fn.my_function.html";
+
+    let result = rewrite_markdown(
+        markdown,
+        &RewriteMarkdownOptions { inline_links: true, ..Default::default() },
+    );
+
+    assert_eq!(result, "[my_function](fn.my_function.html)\n\n");
+}
+
+#[test]
+fn test_cfg_annotations() {
+    let markdown = "[Foo], [the bar](Bar), [baz][baz-ref] and [Qux].\n\n[baz-ref]: Baz";
+
+    let result = rewrite_markdown(
+        markdown,
+        &RewriteMarkdownOptions {
+            links: [
+                (String::from("Foo"), Some(String::from("struct.Foo.html"))),
+                (String::from("Bar"), Some(String::from("struct.Bar.html"))),
+                (String::from("Baz"), Some(String::from("struct.Baz.html"))),
+                (String::from("Qux"), None),
+            ]
+            .into_iter()
+            .collect(),
+            cfg_annotations: [
+                (String::from("Foo"), String::from("*Available on feature `foo` only.*")),
+                (String::from("Bar"), String::from("*Available on `unix` only.*")),
+                (String::from("Baz"), String::from("*Available on feature `baz` only.*")),
+                (String::from("Qux"), String::from("*Available on feature `qux` only.*")),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        result,
+        "\
+[Foo] *Available on feature `foo` only.*, \
+[the bar](struct.Bar.html) *Available on `unix` only.*, \
+[baz][baz-ref] *Available on feature `baz` only.* and Qux.\n\n\
+[baz-ref]: struct.Baz.html\n\n\
+[Foo]: struct.Foo.html\n"
+    );
+}
+
 #[test]
 fn test_unused_definition() {
     let markdown = "[Vector](Vec)";
@@ -194,6 +535,19 @@ fn test_hidden_code_line() {
     assert_eq!(out, "```rust\n// this stays\n```")
 }
 
+#[test]
+fn test_hidden_code_line_no_strip() {
+    let options = RewriteMarkdownOptions { strip_hidden_tests: false, ..Default::default() };
+
+    let markdown = "```\n// this stays\n# // this stays too\n## so does this\n```";
+    let out = rewrite_markdown(markdown, &options);
+    assert_eq!(out, "```rust\n// this stays\n# // this stays too\n## so does this\n```");
+
+    let markdown = "    // this stays\n    # // this stays too\n";
+    let out = rewrite_markdown(markdown, &options);
+    assert_eq!(out, "```rust\n// this stays\n# // this stays too\n```\n");
+}
+
 #[test]
 fn test_code_block_ignore_line_fenced() {
     let markdown = r#"\
@@ -284,6 +638,27 @@ fn test_code_block_ignore_line_indented() {
     .assert_eq(&rewrite_markdown(markdown, &RewriteMarkdownOptions::default()));
 }
 
+#[test]
+fn test_code_block_hidden_line_indented() {
+    let markdown = r#"
+    fn main() {
+    # let hidden = 1;
+        let x = 2;
+            # let nested_hidden = 3;
+    }
+"#;
+
+    expect![[r#"
+
+        ```rust
+        fn main() {
+            let x = 2;
+        }
+        ```
+    "#]]
+    .assert_eq(&rewrite_markdown(markdown, &RewriteMarkdownOptions::default()));
+}
+
 #[test]
 fn test_clean_code_blocks() {
     expect![[r#"
@@ -347,6 +722,33 @@ def square(n):
     ));
 }
 
+#[test]
+fn test_gfm_alert() {
+    let markdown = "> [!WARNING]\n> This is dangerous.";
+
+    let result = rewrite_markdown(
+        markdown,
+        &RewriteMarkdownOptions {
+            links: [(String::from("!WARNING"), None)].into_iter().collect(),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(result, "> [!WARNING]\n> This is dangerous.\n\n");
+}
+
+#[test]
+fn test_is_gfm_alert_blockquote() {
+    assert!(is_gfm_alert_blockquote("> [!NOTE]\n> text"));
+    assert!(is_gfm_alert_blockquote(">[!tip]"));
+    assert!(is_gfm_alert_blockquote("> [!CAUTION]  "));
+
+    assert!(!is_gfm_alert_blockquote("> [NOTE]"));
+    assert!(!is_gfm_alert_blockquote("> [!FOO]"));
+    assert!(!is_gfm_alert_blockquote("> [!NOTE] text"));
+    assert!(!is_gfm_alert_blockquote("> text\n> [!NOTE]"));
+}
+
 #[test]
 fn test_code_block_fence_is_rust() {
     assert!(code_block_fence_is_rust(""));
@@ -370,6 +772,61 @@ fn test_code_block_fence_is_rust() {
     assert!(!code_block_fence_is_rust("c"));
 }
 
+#[test]
+fn test_code_block_fence_rust_aliases() {
+    for alias in ["rust", "Rust", "RUST", "rs", "Rs", "RS"] {
+        assert!(code_block_fence_is_rust(alias), "{alias}");
+
+        for flag in ["no_run", "compile_fail", "ignore", "should_panic", "edition2021"] {
+            assert!(code_block_fence_is_rust(&format!("{alias},{flag}")), "{alias},{flag}");
+            assert!(code_block_fence_is_rust(&format!("{flag},{alias}")), "{flag},{alias}");
+        }
+
+        assert_eq!(unknown_lang(alias), None);
+    }
+
+    assert!(!code_block_fence_is_rust("rsx"));
+    assert!(!code_block_fence_is_rust("rs,custom"));
+}
+
+#[test]
+fn test_rewrite_rust_alias() {
+    let markdown = "```rs,no_run\n# use foo;\nfoo();\n```\n";
+    let result = rewrite_markdown(markdown, &RewriteMarkdownOptions::default());
+    assert_eq!(result, "```rust\nfoo();\n```\n");
+}
+
+#[test]
+fn test_unknown_lang() {
+    assert_eq!(unknown_lang("ruxt"), Some("ruxt".to_string()));
+    assert_eq!(unknown_lang("ruxt,ignore"), Some("ruxt".to_string()));
+    assert_eq!(unknown_lang("rust"), None);
+    assert_eq!(unknown_lang("no_run"), None);
+    assert_eq!(unknown_lang("python"), None);
+    assert_eq!(unknown_lang("TOML"), None);
+    assert_eq!(unknown_lang("text,ignore"), None);
+}
+
+#[test]
+#[ignore = "needs to be run separately because of hooks"]
+fn test_warn_unknown_lang() {
+    let markdown = "# Title\n\n```ruxt\nfn main() {}\n```\n\n```python\nprint()\n```\n";
+
+    let out = pretty_log::tests::simple_log(|_| {
+        rewrite_markdown(
+            markdown,
+            &RewriteMarkdownOptions { warn_unknown_lang: true, ..Default::default() },
+        );
+    });
+
+    expect![[r#"
+        warning: unknown code block language
+          lang: ruxt
+          line: 3
+    "#]]
+    .assert_eq(&pretty_log::tests::prepare_for_compare(&out));
+}
+
 #[test]
 #[ignore = "needs to be run separately because of hooks"]
 fn test_code_block_fence_error_unexpected_end() {
@@ -418,7 +875,28 @@ fn test_shrink_headings() {
     assert_eq!(shrink_headings("## foo", 5), "###### foo");
     assert_eq!(shrink_headings("## foo", 6), "###### foo");
 
-    assert_eq!(shrink_headings("  ####   foo", -2), "  ##   foo");
+    assert_eq!(shrink_headings("  ####   foo", -2), "  ## foo");
+
+    assert_eq!(shrink_headings("##  foo", 1), "### foo");
+    assert_eq!(shrink_headings("##\tfoo", 0), "## foo");
+    assert_eq!(shrink_headings("##  foo  ##", 1), "### foo  ##");
+    assert_eq!(shrink_headings("## ", 1), "### ");
+
+    assert_eq!(shrink_headings("foo\n===\n", -1), "# foo\n");
+    assert_eq!(shrink_headings("foo\n===\n", 0), "foo\n===\n");
+    assert_eq!(shrink_headings("foo\n===\n", 1), "## foo\n");
+    assert_eq!(shrink_headings("foo\n===\n", 2), "### foo\n");
+    assert_eq!(shrink_headings("foo\n---\n", -1), "# foo\n");
+    assert_eq!(shrink_headings("foo\n---\n", 0), "foo\n---\n");
+    assert_eq!(shrink_headings("foo\n---\n", 1), "### foo\n");
+    assert_eq!(shrink_headings("foo\n---\n", 2), "#### foo\n");
+    assert_eq!(shrink_headings("foo\nbar\n===", 1), "## foo bar");
+    assert_eq!(shrink_headings("foo [bar](baz)\n---", 1), "### foo [bar](baz)");
+
+    // multi-line setext headings in containers
+    assert_eq!(shrink_headings("> foo\n> bar\n> ===\n", 1), "> ## foo bar\n");
+    assert_eq!(shrink_headings("- foo\n  bar\n  ---\n", 1), "- ### foo bar\n");
+    assert_eq!(shrink_headings("> - foo\n>   bar\n>   ===\n", 1), "> - ## foo bar\n");
 }
 
 #[test]
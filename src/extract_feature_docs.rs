@@ -1,43 +1,153 @@
 #[cfg(test)]
 mod tests;
 
-use std::{collections::HashSet, fmt::Write};
-
-use color_eyre::eyre::{Result, bail};
-
-pub fn extract(toml: &str, feature_label: &str, hidden_features: &HashSet<&str>) -> Result<String> {
-    let mut docs = parse(toml)?;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    mem,
+};
+
+use color_eyre::eyre::{Result, WrapErr as _, bail};
+use tracing::warn;
+
+use crate::config::{
+    self, DEFAULT_DEFAULT_MARKER, DEFAULT_FEATURE_COMMENT_PREFIX, DEFAULT_FEATURE_LABEL,
+    DEFAULT_IN_BETWEEN_COMMENT_PREFIX, FeatureConfig, FeatureFormat, SortFeatures,
+};
+
+pub struct ExtractFeatureDocsOptions<'a> {
+    pub feature_label: &'a str,
+    pub default_marker: &'a str,
+    /// The level of the heading the feature documentation is inserted under or `0` if there is
+    /// none. Group headings like `#! ## Async support` are nested under it.
+    pub heading_level: u8,
+    pub sort_features: SortFeatures,
+    pub feature_format: FeatureFormat,
+    /// The prefix of comments that document a feature, like `##`.
+    pub feature_comment_prefix: &'a str,
+    /// The prefix of comments between features, like `#!`.
+    pub in_between_comment_prefix: &'a str,
+    pub include_optional_deps: bool,
+    /// Add a line like ``*Enables: `bar`, `baz`.*`` to the docs of a feature.
+    pub show_feature_deps: bool,
+    /// Add a line like ``*Required by: `full`, `async`.*`` to the docs of a feature.
+    pub show_feature_rdeps: bool,
+    pub hidden_features: &'a [String],
+    /// The contents of a features file, which is read instead of the `Cargo.toml` comments.
+    pub features_file: Option<&'a str>,
+}
 
-    docs.retain(|entry| match entry {
-        FeatureDocEntry::InBetween { .. } => true,
-        FeatureDocEntry::Feature { name, .. } => !hidden_features.contains(name.as_str()),
-    });
+impl Default for ExtractFeatureDocsOptions<'_> {
+    fn default() -> Self {
+        Self {
+            feature_label: DEFAULT_FEATURE_LABEL,
+            default_marker: DEFAULT_DEFAULT_MARKER,
+            heading_level: 0,
+            sort_features: SortFeatures::default(),
+            feature_format: FeatureFormat::default(),
+            feature_comment_prefix: DEFAULT_FEATURE_COMMENT_PREFIX,
+            in_between_comment_prefix: DEFAULT_IN_BETWEEN_COMMENT_PREFIX,
+            include_optional_deps: false,
+            show_feature_deps: false,
+            show_feature_rdeps: false,
+            hidden_features: &[],
+            features_file: None,
+        }
+    }
+}
 
-    Ok(format(&docs, feature_label))
+/// Extracts the feature documentation of a package manifest's contents (`Cargo.toml`).
+///
+/// Features without documentation fall back to the documentation in
+/// `[workspace.metadata.insert-docs.feature-docs]` of the workspace manifest.
+pub fn extract(
+    toml: &str,
+    workspace_toml: Option<&str>,
+    options: &ExtractFeatureDocsOptions,
+) -> Result<String> {
+    let docs = parse(toml, workspace_toml, options)?;
+
+    let hidden_by_config = docs
+        .iter()
+        .filter_map(|entry| match entry {
+            FeatureDocEntry::Feature { name, hidden: true, .. } => Some(name.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let hidden_features =
+        options.hidden_features.iter().chain(&hidden_by_config).map(String::as_str).collect();
+
+    let docs = hide(docs, &hidden_features);
+    let docs = sort(docs, options.sort_features);
+
+    Ok(format(&docs, options))
 }
 
 type FeatureDocs = Vec<FeatureDocEntry>;
 
 #[derive(Debug)]
 enum FeatureDocEntry {
-    InBetween { docs: String },
-    Feature { name: String, docs: String, is_default: bool },
+    /// Documentation between features (`#!`).
+    ///
+    /// Docs that start with a heading like `## Async support` are a group heading.
+    /// The features that follow it belong to that group.
+    InBetween { docs: String, is_group_heading: bool },
+    Feature {
+        name: String,
+        docs: String,
+        is_default: bool,
+        /// The version of a `## since: 1.2.0` comment.
+        since: Option<String>,
+        /// Set by `hidden` in the feature config.
+        hidden: bool,
+        /// Overwrites the feature label, from the feature config.
+        label: Option<String>,
+        /// Like `experimental`, from the feature config.
+        status: Option<String>,
+        /// The features this feature enables.
+        enables: Vec<String>,
+        /// The features that enable this feature.
+        required_by: Vec<String>,
+    },
 }
 
-fn parse(toml: &str) -> Result<FeatureDocs> {
+fn parse(
+    toml: &str,
+    workspace_toml: Option<&str>,
+    options: &ExtractFeatureDocsOptions,
+) -> Result<FeatureDocs> {
     let doc = toml_edit::Document::parse(toml)?;
+    let defaults = default_features(&doc);
 
-    let Some(features) = doc.get("features") else {
-        return Ok(vec![]);
+    let mut vec = match options.features_file {
+        Some(features_file) => parse_from_features_file(&doc, &defaults, features_file)?,
+        None => parse_from_toml_comments(&doc, &defaults, options)?,
     };
 
-    let Some(features) = features.as_table_like() else {
-        return Ok(vec![]);
-    };
+    if options.include_optional_deps {
+        add_optional_deps(&doc, &mut vec, &defaults, options.feature_comment_prefix)?;
+    }
 
+    add_required_by(&mut vec);
+    apply_feature_config(&mut vec, &config::read_feature_config(toml)?);
+
+    if let Some(workspace_toml) = workspace_toml {
+        apply_workspace_feature_docs(
+            &mut vec,
+            &config::read_workspace_feature_docs(workspace_toml)?,
+        );
+    }
+
+    Ok(vec)
+}
+
+/// Returns the features enabled by the `default` feature.
+fn default_features<'a>(doc: &'a toml_edit::Document<&str>) -> HashSet<&'a str> {
     let mut defaults = HashSet::new();
 
-    if let Some(item) = features.get("default")
+    if let Some(features) = doc.get("features").and_then(|f| f.as_table_like())
+        && let Some(item) = features.get("default")
         && let Some(array) = item.as_array()
     {
         for value in array.iter() {
@@ -47,9 +157,19 @@ fn parse(toml: &str) -> Result<FeatureDocs> {
         }
     }
 
+    defaults
+}
+
+/// Reads the feature documentation from the `##` and `#!` comments of the `Cargo.toml`.
+fn parse_from_toml_comments(
+    doc: &toml_edit::Document<&str>,
+    defaults: &HashSet<&str>,
+    options: &ExtractFeatureDocsOptions,
+) -> Result<FeatureDocs> {
+    let features = doc.get("features").and_then(|f| f.as_table_like());
     let mut vec = vec![];
 
-    for (key, _) in features.get_values() {
+    for (key, value) in features.into_iter().flat_map(|f| f.get_values()) {
         let key = key[0];
         let name = key.get();
 
@@ -57,46 +177,434 @@ fn parse(toml: &str) -> Result<FeatureDocs> {
             continue;
         }
 
-        let decor = key.leaf_decor();
-
-        let prefix = match decor.prefix() {
-            Some(raw_string) => match (raw_string.as_str(), raw_string.span()) {
-                (Some(string), _) => string,
-                (None, Some(span)) => &doc.raw()[span],
-                (None, None) => "",
-            },
-            None => "",
-        };
+        let prefix = decor_prefix(doc, key.leaf_decor());
 
         let mut in_between_docs = String::new();
         let mut feature_docs = String::new();
+        let mut since = None;
 
         for line in prefix.lines() {
-            if let Some(in_between_comment) = comment_line(line, "#!")? {
+            if let Some(in_between_comment) = comment_line(line, options.in_between_comment_prefix)?
+            {
+                // a group heading starts its own entry
+                if atx_heading_level(in_between_comment).is_some() {
+                    push_in_between(&mut vec, mem::take(&mut in_between_docs));
+                }
+
                 in_between_docs.push_str(in_between_comment);
                 in_between_docs.push('\n');
-            }
+            } else if let Some(feature_comment) =
+                comment_line(line, options.feature_comment_prefix)?
+            {
+                if let Some(version) = since_comment(feature_comment) {
+                    since = Some(version.to_string());
+                    continue;
+                }
 
-            if let Some(feature_comment) = comment_line(line, "##")? {
                 feature_docs.push_str(feature_comment);
                 feature_docs.push('\n');
             }
         }
 
-        if !in_between_docs.is_empty() {
-            vec.push(FeatureDocEntry::InBetween { docs: in_between_docs });
+        push_in_between(&mut vec, in_between_docs);
+        let enables = enabled_features(value);
+        vec.push(feature_entry(name, feature_docs, since, defaults, enables));
+    }
+
+    Ok(vec)
+}
+
+/// Reads the feature documentation from a features file like
+///
+/// ```toml
+/// [features.my-feature]
+/// docs = "Enable async support."
+/// ```
+///
+/// The features are in the order of the features file.
+/// Features of the `Cargo.toml` that the file doesn't list are added undocumented at the end.
+fn parse_from_features_file(
+    doc: &toml_edit::Document<&str>,
+    defaults: &HashSet<&str>,
+    features_file: &str,
+) -> Result<FeatureDocs> {
+    let file =
+        toml_edit::Document::parse(features_file).wrap_err("failed to parse features file")?;
+
+    let features = doc.get("features").and_then(|f| f.as_table_like());
+    let file_features = file.get("features").and_then(|f| f.as_table_like());
+
+    let mut vec = vec![];
+    let mut unknown = vec![];
+
+    for (name, item) in file_features.into_iter().flat_map(|f| f.iter()) {
+        let Some(value) = features.and_then(|f| f.get(name)).filter(|_| name != "default") else {
+            unknown.push(name);
+            continue;
+        };
+
+        let docs = item.get("docs").and_then(|d| d.as_str()).map(docs_lines).unwrap_or_default();
+        let enables = value.as_value().map(enabled_features).unwrap_or_default();
+        vec.push(feature_entry(name, docs, None, defaults, enables));
+    }
+
+    if !unknown.is_empty() {
+        warn!("the features file documents features that don't exist: {}", unknown.join(", "));
+    }
+
+    let mut missing = vec![];
+
+    for (name, value) in features.into_iter().flat_map(|f| f.iter()) {
+        if name == "default" || file_features.is_some_and(|f| f.contains_key(name)) {
+            continue;
         }
 
-        vec.push(FeatureDocEntry::Feature {
-            name: name.to_string(),
-            docs: feature_docs,
-            is_default: defaults.contains(name),
-        });
+        missing.push(name);
+        let enables = value.as_value().map(enabled_features).unwrap_or_default();
+        vec.push(feature_entry(name, String::new(), None, defaults, enables));
+    }
+
+    if !missing.is_empty() {
+        warn!("features missing from the features file: {}", missing.join(", "));
     }
 
     Ok(vec)
 }
 
+fn feature_entry(
+    name: &str,
+    docs: String,
+    since: Option<String>,
+    defaults: &HashSet<&str>,
+    enables: Vec<String>,
+) -> FeatureDocEntry {
+    FeatureDocEntry::Feature {
+        name: name.to_string(),
+        docs,
+        is_default: defaults.contains(name),
+        since,
+        hidden: false,
+        label: None,
+        status: None,
+        enables,
+        required_by: vec![],
+    }
+}
+
+/// Returns the lines of `docs`, each ending with a newline like the docs from comments.
+fn docs_lines(docs: &str) -> String {
+    docs.lines().flat_map(|line| [line, "\n"]).collect()
+}
+
+/// Documents the features without `##` comments with the docs of
+/// `[workspace.metadata.insert-docs.feature-docs]`.
+fn apply_workspace_feature_docs(vec: &mut FeatureDocs, workspace_docs: &BTreeMap<String, String>) {
+    for entry in vec.iter_mut() {
+        if let FeatureDocEntry::Feature { name, docs, .. } = entry
+            && docs.trim().is_empty()
+            && let Some(workspace_docs) = workspace_docs.get(name.as_str())
+        {
+            *docs = docs_lines(workspace_docs);
+        }
+    }
+}
+
+/// Applies the settings of `[package.metadata.insert-docs.feature-config.<feature>]`.
+fn apply_feature_config(vec: &mut FeatureDocs, feature_config: &BTreeMap<String, FeatureConfig>) {
+    for entry in vec.iter_mut() {
+        if let FeatureDocEntry::Feature { name, since, hidden, label, status, .. } = entry
+            && let Some(config) = feature_config.get(name.as_str())
+        {
+            *hidden = config.hidden;
+
+            if config.since.is_some() {
+                since.clone_from(&config.since);
+            }
+
+            label.clone_from(&config.label);
+            status.clone_from(&config.status);
+        }
+    }
+}
+
+/// Returns the features enabled by a feature's value like `["bar", "dep:baz", "serde/std"]`.
+///
+/// Optional dependencies (`dep:baz`) and features of dependencies (`serde/std`)
+/// are not features of this package, so they are left out.
+fn enabled_features(value: &toml_edit::Value) -> Vec<String> {
+    let Some(array) = value.as_array() else {
+        return vec![];
+    };
+
+    array
+        .iter()
+        .filter_map(|v| v.as_str())
+        .filter(|f| !f.starts_with("dep:") && !f.contains('/'))
+        .map(|f| f.to_string())
+        .collect()
+}
+
+/// Fills in `required_by` from the `enables` of the other features.
+fn add_required_by(vec: &mut FeatureDocs) {
+    let mut required_by = HashMap::<String, Vec<String>>::new();
+
+    for entry in vec.iter() {
+        if let FeatureDocEntry::Feature { name, enables, .. } = entry {
+            for enabled in enables {
+                required_by.entry(enabled.clone()).or_default().push(name.clone());
+            }
+        }
+    }
+
+    for entry in vec.iter_mut() {
+        if let FeatureDocEntry::Feature { name, required_by: entry_required_by, .. } = entry
+            && let Some(features) = required_by.remove(name.as_str())
+        {
+            *entry_required_by = features;
+        }
+    }
+}
+
+fn push_in_between(vec: &mut FeatureDocs, docs: String) {
+    if !docs.is_empty() {
+        let is_group_heading = atx_heading_level(&docs).is_some();
+        vec.push(FeatureDocEntry::InBetween { docs, is_group_heading });
+    }
+}
+
+/// Returns the level of an atx heading like `## Async support` at the start of `docs`.
+fn atx_heading_level(docs: &str) -> Option<usize> {
+    let level = docs.bytes().take_while(|&b| b == b'#').count();
+    let rest = &docs[level..];
+
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with([' ', '\t'])))
+        .then_some(level)
+}
+
+/// Adds the features of optional dependencies.
+///
+/// An optional dependency that is not referenced with `dep:` in the features table
+/// is an implicit feature, so we add it with the `##` docs in front of the dependency.
+/// A feature that only enables `dep:that-dependency` and is not documented itself
+/// gets the docs of the dependency.
+fn add_optional_deps(
+    doc: &toml_edit::Document<&str>,
+    vec: &mut FeatureDocs,
+    defaults: &HashSet<&str>,
+    feature_comment_prefix: &str,
+) -> Result<()> {
+    let Some(deps) = doc.get("dependencies").and_then(|d| d.as_table_like()) else {
+        return Ok(());
+    };
+
+    let features = doc.get("features").and_then(|f| f.as_table_like());
+    let mut dep_features = HashMap::<&str, Vec<&str>>::new();
+
+    for (feature, item) in features.iter().flat_map(|f| f.iter()) {
+        let Some(array) = item.as_array() else {
+            continue;
+        };
+
+        for dep in array.iter().filter_map(|v| v.as_str()?.strip_prefix("dep:")) {
+            let aliases = dep_features.entry(dep).or_default();
+
+            if array.len() == 1 {
+                aliases.push(feature);
+            }
+        }
+    }
+
+    for (name, item) in deps.iter() {
+        if item.get("optional").and_then(|o| o.as_bool()) != Some(true) {
+            continue;
+        }
+
+        let decor = match item.as_table() {
+            Some(table) => table.decor(),
+            None => match deps.get_key_value(name) {
+                Some((key, _)) => key.leaf_decor(),
+                None => continue,
+            },
+        };
+
+        let mut dep_docs = String::new();
+        let mut dep_since = None;
+
+        for line in decor_prefix(doc, decor).lines() {
+            if let Some(comment) = comment_line(line, feature_comment_prefix)? {
+                if let Some(version) = since_comment(comment) {
+                    dep_since = Some(version.to_string());
+                    continue;
+                }
+
+                dep_docs.push_str(comment);
+                dep_docs.push('\n');
+            }
+        }
+
+        match dep_features.get(name) {
+            Some(aliases) => {
+                for entry in vec.iter_mut() {
+                    if let FeatureDocEntry::Feature { name: feature, docs, since, .. } = entry
+                        && docs.is_empty()
+                        && aliases.contains(&feature.as_str())
+                    {
+                        docs.clone_from(&dep_docs);
+
+                        if since.is_none() {
+                            since.clone_from(&dep_since);
+                        }
+                    }
+                }
+            }
+            None => {
+                let is_declared = vec.iter().any(|entry| {
+                    matches!(entry, FeatureDocEntry::Feature { name: feature, .. } if feature == name)
+                });
+
+                if !is_declared {
+                    vec.push(FeatureDocEntry::Feature {
+                        name: name.to_string(),
+                        docs: dep_docs,
+                        is_default: defaults.contains(name),
+                        since: dep_since,
+                        hidden: false,
+                        label: None,
+                        status: None,
+                        enables: vec![],
+                        required_by: vec![],
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn decor_prefix<'a>(doc: &'a toml_edit::Document<&str>, decor: &'a toml_edit::Decor) -> &'a str {
+    match decor.prefix() {
+        Some(raw_string) => match (raw_string.as_str(), raw_string.span()) {
+            (Some(string), _) => string,
+            (None, Some(span)) => &doc.raw()[span],
+            (None, None) => "",
+        },
+        None => "",
+    }
+}
+
+/// Removes the hidden features.
+///
+/// `InBetween` docs are removed too if all the features up to the next `InBetween` are hidden.
+fn hide(docs: FeatureDocs, hidden_features: &HashSet<&str>) -> FeatureDocs {
+    let is_hidden = |entry: &FeatureDocEntry| match entry {
+        FeatureDocEntry::InBetween { .. } => false,
+        FeatureDocEntry::Feature { name, .. } => hidden_features.contains(name.as_str()),
+    };
+
+    let keep = (0..docs.len())
+        .map(|i| match &docs[i] {
+            FeatureDocEntry::InBetween { .. } => {
+                let mut features = docs[i + 1..]
+                    .iter()
+                    .take_while(|entry| matches!(entry, FeatureDocEntry::Feature { .. }))
+                    .peekable();
+
+                features.peek().is_none() || !features.all(is_hidden)
+            }
+            feature => !is_hidden(feature),
+        })
+        .collect::<Vec<_>>();
+
+    docs.into_iter()
+        .zip(keep)
+        .filter_map(|(entry, keep)| keep.then_some(entry))
+        .map(|mut entry| {
+            // hidden features are not listed as dependencies either
+            if let FeatureDocEntry::Feature { enables, required_by, .. } = &mut entry {
+                enables.retain(|f| !hidden_features.contains(f.as_str()));
+                required_by.retain(|f| !hidden_features.contains(f.as_str()));
+            }
+
+            entry
+        })
+        .collect()
+}
+
+/// Sorts the features by `order`.
+///
+/// Features are only sorted within their group, group headings stay in place.
+fn sort(docs: FeatureDocs, order: SortFeatures) -> FeatureDocs {
+    if order == SortFeatures::Declaration {
+        return docs;
+    }
+
+    let mut out = vec![];
+    let mut segment = vec![];
+
+    for entry in docs {
+        if let FeatureDocEntry::InBetween { is_group_heading: true, .. } = entry {
+            out.extend(sort_segment(mem::take(&mut segment), order));
+            out.push(entry);
+        } else {
+            segment.push(entry);
+        }
+    }
+
+    out.extend(sort_segment(segment, order));
+    out
+}
+
+/// Sorts the features of a single group.
+///
+/// `InBetween` docs move with the feature that follows them.
+/// Trailing `InBetween` docs stay at the end.
+fn sort_segment(docs: FeatureDocs, order: SortFeatures) -> FeatureDocs {
+    // Each group consists of `InBetween` entries followed by a single `Feature`.
+    let mut groups: Vec<Vec<FeatureDocEntry>> = vec![];
+    let mut trailing = vec![];
+
+    for entry in docs {
+        let is_feature = matches!(entry, FeatureDocEntry::Feature { .. });
+        trailing.push(entry);
+
+        if is_feature {
+            groups.push(mem::take(&mut trailing));
+        }
+    }
+
+    fn feature(group: &[FeatureDocEntry]) -> (&str, bool) {
+        match group.last() {
+            Some(FeatureDocEntry::Feature { name, is_default, .. }) => (name, *is_default),
+            _ => unreachable!("every group ends in a feature"),
+        }
+    }
+
+    // this is a stable sort, so features that compare equal stay in declaration order
+    groups.sort_by(|a, b| {
+        let (a_name, a_default) = feature(a);
+        let (b_name, b_default) = feature(b);
+
+        match order {
+            SortFeatures::Declaration => unreachable!(),
+            SortFeatures::Alphabetical => a_name.cmp(b_name),
+            SortFeatures::DefaultFirst => b_default.cmp(&a_default),
+            SortFeatures::NonDefaultFirst => a_default.cmp(&b_default),
+            SortFeatures::AlphabeticalDefaultFirst => {
+                b_default.cmp(&a_default).then_with(|| a_name.cmp(b_name))
+            }
+        }
+    });
+
+    groups.into_iter().flatten().chain(trailing).collect()
+}
+
+/// Returns the version of a `since: 1.2.0` feature docs line.
+fn since_comment(line: &str) -> Option<&str> {
+    let version = line.strip_prefix("since:")?.trim();
+    (!version.is_empty()).then_some(version)
+}
+
 fn comment_line<'a>(line: &'a str, prefix: &str) -> Result<Option<&'a str>> {
     let Some(comment) = line.strip_prefix(prefix) else {
         return Ok(None);
@@ -125,26 +633,106 @@ fn comment_line_unprefixed(mut line: &str) -> Result<&str> {
     Ok(line)
 }
 
-fn format(docs: &FeatureDocs, feature_label: &str) -> String {
+fn format(docs: &FeatureDocs, options: &ExtractFeatureDocsOptions) -> String {
+    let ExtractFeatureDocsOptions { feature_label, default_marker, heading_level, .. } = *options;
     let mut out = String::new();
 
+    // Documentation between features ends a table, the next feature starts a new one.
+    let mut in_table = false;
+
+    // Group headings are shifted so that the highest one is nested under the section's heading,
+    // `#! ## Foo` under a `### Features` heading becomes `#### Foo`.
+    // Group headings that are already nested are left alone.
+    let highest_group_heading = docs
+        .iter()
+        .filter_map(|doc| match doc {
+            FeatureDocEntry::InBetween { docs, is_group_heading: true } => atx_heading_level(docs),
+            _ => None,
+        })
+        .min();
+    let group_heading_shift = match highest_group_heading {
+        Some(highest) if highest <= usize::from(heading_level) => {
+            usize::from(heading_level) + 1 - highest
+        }
+        _ => 0,
+    };
+
     for doc in docs {
         match doc {
-            FeatureDocEntry::InBetween { docs } => {
+            FeatureDocEntry::InBetween { docs, is_group_heading } => {
                 let start_pad = if out.is_empty() { "" } else { "\n" };
-                writeln!(out, "{start_pad}{docs}").unwrap();
+                in_table = false;
+
+                if *is_group_heading {
+                    let level =
+                        atx_heading_level(docs).expect("group headings start with a heading");
+
+                    let new_level = (level + group_heading_shift).min(6);
+
+                    writeln!(out, "{start_pad}{}{}", &"######"[..new_level], &docs[level..])
+                        .unwrap();
+                } else {
+                    writeln!(out, "{start_pad}{docs}").unwrap();
+                }
             }
-            FeatureDocEntry::Feature { name, docs, is_default } => {
-                let label = feature_label.replace("{feature}", name);
-                let default = if *is_default { " *(enabled by default)*" } else { "" };
+            FeatureDocEntry::Feature {
+                name,
+                docs,
+                is_default,
+                since,
+                label,
+                status,
+                enables,
+                required_by,
+                ..
+            } => {
+                let mut label = label
+                    .as_deref()
+                    .unwrap_or(feature_label)
+                    .replace("{feature}", name)
+                    .replace("{default_marker}", if *is_default { "*" } else { "" })
+                    .replace("{since}", since.as_deref().unwrap_or(""));
+
+                if *is_default && !default_marker.is_empty() {
+                    write!(label, " {default_marker}").unwrap();
+                }
+
+                let mut lines = docs.lines().map(String::from).collect::<Vec<_>>();
+
+                if let Some(status) = status {
+                    lines.push(format!("*Status: {status}.*"));
+                }
+
+                if options.show_feature_deps && !enables.is_empty() {
+                    lines.push(format!("*Enables: {}.*", code_list(enables)));
+                }
+
+                if options.show_feature_rdeps && !required_by.is_empty() {
+                    lines.push(format!("*Required by: {}.*", code_list(required_by)));
+                }
+
+                if options.feature_format == FeatureFormat::Table {
+                    if !in_table {
+                        // a table needs a blank line before it
+                        let start_pad =
+                            if out.is_empty() || out.ends_with("\n\n") { "" } else { "\n" };
+                        writeln!(out, "{start_pad}| Feature | Description |").unwrap();
+                        writeln!(out, "|---------|-------------|").unwrap();
+                        in_table = true;
+                    }
+
+                    writeln!(out, "| {} | {} |", table_cell(&label), table_cell(&lines.join("\n")))
+                        .unwrap();
+
+                    continue;
+                }
 
-                write!(out, "- {label}{default}").unwrap();
+                write!(out, "- {label}").unwrap();
 
-                if docs.is_empty() {
+                if lines.is_empty() {
                     out.push('\n');
                 } else {
-                    // non-empty docs always end in a newline
-                    for (i, line) in docs.lines().enumerate() {
+                    for (i, line) in lines.iter().enumerate() {
                         // either add the em dash or indentation
                         out.push_str(if i == 0 { " — " } else { "  " });
                         out.push_str(line);
@@ -157,3 +745,33 @@ fn format(docs: &FeatureDocs, feature_label: &str) -> String {
 
     out
 }
+
+/// Puts markdown into a single table cell.
+///
+/// Lines of a paragraph are joined by spaces and paragraphs are separated by `<br><br>`.
+fn table_cell(markdown: &str) -> String {
+    let mut paragraphs = vec![];
+    let mut paragraph = Vec::<&str>::new();
+
+    for line in markdown.lines().map(str::trim) {
+        if line.is_empty() {
+            if !paragraph.is_empty() {
+                paragraphs.push(paragraph.join(" "));
+                paragraph.clear();
+            }
+        } else {
+            paragraph.push(line);
+        }
+    }
+
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph.join(" "));
+    }
+
+    paragraphs.join("<br><br>").replace('|', "\\|")
+}
+
+/// Formats features like `` `bar`, `baz` ``.
+fn code_list(features: &[String]) -> String {
+    features.iter().map(|f| format!("`{f}`")).collect::<Vec<_>>().join(", ")
+}
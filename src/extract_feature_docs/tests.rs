@@ -1,13 +1,26 @@
-use std::collections::HashSet;
-
 use color_eyre::eyre::Result;
 use expect_test::expect;
 use indoc::indoc;
 
-use super::{comment_line_unprefixed, extract, parse};
+use crate::config::{FeatureFormat, SortFeatures};
+
+use super::{ExtractFeatureDocsOptions, comment_line_unprefixed, extract, parse};
 
 fn extract_simple(toml: &str) -> String {
-    extract(toml, "{feature}", &HashSet::new()).unwrap()
+    extract_sorted(toml, SortFeatures::Declaration)
+}
+
+fn extract_sorted(toml: &str, sort_features: SortFeatures) -> String {
+    extract(
+        toml,
+        None,
+        &ExtractFeatureDocsOptions {
+            feature_label: "{feature}",
+            sort_features,
+            ..Default::default()
+        },
+    )
+    .unwrap()
 }
 
 #[test]
@@ -56,17 +69,221 @@ fn test_extract_hidden() {
         hidden-documented = []
         hidden-undocumented = []
     "#},
-            "{feature}",
-            &["hidden-documented", "hidden-undocumented"].into_iter().collect(),
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "{feature}",
+                hidden_features: &["hidden-documented".into(), "hidden-undocumented".into()],
+                ..Default::default()
+            },
         )
         .unwrap(),
     );
 }
 
+#[test]
+fn test_extract_hidden_in_between() {
+    expect![[r#"
+        - documented — bla bla
+
+        ## Shown
+        Some shown features.
+
+        - shown
+    "#]]
+    .assert_eq(
+        &extract(
+            indoc! {r#"
+        [features]
+        ## bla bla
+        documented = []
+        #! ## Internal
+        #! Some internal features.
+        internal-a = []
+        internal-b = []
+        #! ## Shown
+        #! Some shown features.
+        internal-c = []
+        shown = []
+    "#},
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "{feature}",
+                hidden_features: &["internal-a".into(), "internal-b".into(), "internal-c".into()],
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+}
+
+const SORT_TOML: &str = indoc! {r#"
+    [features]
+    default = ["b", "d"]
+    ## Docs about a
+    a = []
+    #! Section about b
+    b = []
+    c = []
+    ## Docs about d
+    d = []
+"#};
+
+#[test]
+fn test_sort_default_first() {
+    assert_eq!(
+        extract_sorted(SORT_TOML, SortFeatures::DefaultFirst),
+        "\
+Section about b
+
+- b *(enabled by default)*
+- d *(enabled by default)* — Docs about d
+- a — Docs about a
+- c
+"
+    );
+}
+
+#[test]
+fn test_sort_non_default_first() {
+    assert_eq!(
+        extract_sorted(SORT_TOML, SortFeatures::NonDefaultFirst),
+        "\
+- a — Docs about a
+- c
+
+Section about b
+
+- b *(enabled by default)*
+- d *(enabled by default)* — Docs about d
+"
+    );
+}
+
+#[test]
+fn test_sort_alphabetical_default_first() {
+    assert_eq!(
+        extract_sorted(
+            indoc! {r#"
+            [features]
+            default = ["zeta", "beta"]
+            zeta = []
+            #! Section about mid
+            mid = []
+            ## Docs about alpha
+            alpha = []
+            beta = []
+            "#},
+            SortFeatures::AlphabeticalDefaultFirst
+        ),
+        "\
+- beta *(enabled by default)*
+- zeta *(enabled by default)*
+- alpha — Docs about alpha
+
+Section about mid
+
+- mid
+"
+    );
+}
+
+#[test]
+fn test_sort_alphabetical() {
+    assert_eq!(
+        extract_sorted(
+            "[features]\nzeta = []\n## Docs about alpha\nalpha = []\nmid = []",
+            SortFeatures::Alphabetical
+        ),
+        "- alpha — Docs about alpha\n- mid\n- zeta\n"
+    );
+}
+
+fn extract_with_optional_deps(toml: &str) -> String {
+    extract(
+        toml,
+        None,
+        &ExtractFeatureDocsOptions {
+            feature_label: "{feature}",
+            include_optional_deps: true,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_optional_deps_implicit() {
+    expect![[r#"
+        - std *(enabled by default)* — Some docs about std
+        - serde *(enabled by default)* — Some docs about serde
+        - rayon
+        - log — Some docs about log
+    "#]]
+    .assert_eq(&extract_with_optional_deps(indoc! {r#"
+        [features]
+        default = ["std", "serde"]
+        ## Some docs about std
+        std = []
+
+        [dependencies]
+        ## Some docs about serde
+        serde = { version = "1", optional = true }
+        rayon = { version = "1", optional = true }
+        ## Not optional
+        regex = "1"
+
+        ## Some docs about log
+        [dependencies.log]
+        version = "0.4"
+        optional = true
+    "#}));
+}
+
+#[test]
+fn test_optional_deps_dep_syntax() {
+    expect![[r#"
+        - serde — Some docs about serde
+        - rayon — Feature docs win
+        - json
+    "#]]
+    .assert_eq(&extract_with_optional_deps(indoc! {r#"
+        [features]
+        serde = ["dep:serde"]
+        ## Feature docs win
+        rayon = ["dep:rayon"]
+        json = ["dep:serde_json", "serde"]
+
+        [dependencies]
+        ## Some docs about serde
+        serde = { version = "1", optional = true }
+        ## Some docs about rayon
+        rayon = { version = "1", optional = true }
+        ## Some docs about serde_json
+        serde_json = { version = "1", optional = true }
+    "#}));
+}
+
+#[test]
+fn test_optional_deps_disabled() {
+    expect![[r#"
+        - std
+    "#]]
+    .assert_eq(&extract_simple(indoc! {r#"
+        [features]
+        std = []
+
+        [dependencies]
+        serde = { version = "1", optional = true }
+    "#}));
+}
+
 #[test]
 fn test_feature_syntax_no_space() {
-    expect!["a non-empty feature docs comment line must start with a space"]
-        .assert_eq(&parse("[features]\n##Evil docs.\nmy_feature = []").unwrap_err().to_string());
+    expect!["a non-empty feature docs comment line must start with a space"].assert_eq(
+        &parse("[features]\n##Evil docs.\nmy_feature = []", None, &Default::default())
+            .unwrap_err()
+            .to_string(),
+    );
 }
 
 #[test]
@@ -108,3 +325,453 @@ fn test_comment_line() {
     assert_eq!(strip(" Hello\t"), "Hello");
     assert_eq!(strip(" Hello\u{A0}"), "Hello");
 }
+
+const GROUPS_TOML: &str = indoc! {r#"
+    [features]
+    default = ["std"]
+    ## Use the standard library
+    std = []
+    #! ## Async support
+    ## Tokio runtime
+    tokio = []
+    ## Async std runtime
+    async-std = []
+    #! ## Serialization
+    #! Formats for serde.
+    ## Json format
+    json = []
+    ## Bincode format
+    bincode = []
+"#};
+
+#[test]
+fn test_groups() {
+    expect![[r#"
+        - std *(enabled by default)* — Use the standard library
+
+        ## Async support
+
+        - tokio — Tokio runtime
+        - async-std — Async std runtime
+
+        ## Serialization
+        Formats for serde.
+
+        - json — Json format
+        - bincode — Bincode format
+    "#]]
+    .assert_eq(&extract_simple(GROUPS_TOML));
+}
+
+#[test]
+fn test_groups_nested() {
+    expect![[r#"
+        - std *(enabled by default)* — Use the standard library
+
+        #### Async support
+
+        - tokio — Tokio runtime
+        - async-std — Async std runtime
+
+        #### Serialization
+        Formats for serde.
+
+        - json — Json format
+        - bincode — Bincode format
+    "#]]
+    .assert_eq(
+        &extract(
+            GROUPS_TOML,
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "{feature}",
+                heading_level: 3,
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_groups_already_nested() {
+    // `###` groups are already below a `## Features` heading
+    let toml = GROUPS_TOML.replace("#! ## ", "#! ### ");
+
+    expect![[r#"
+        - std *(enabled by default)* — Use the standard library
+
+        ### Async support
+
+        - tokio — Tokio runtime
+        - async-std — Async std runtime
+
+        ### Serialization
+        Formats for serde.
+
+        - json — Json format
+        - bincode — Bincode format
+    "#]]
+    .assert_eq(
+        &extract(
+            &toml,
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "{feature}",
+                heading_level: 2,
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_groups_sorted() {
+    expect![[r#"
+        - std *(enabled by default)* — Use the standard library
+
+        ## Async support
+
+        - async-std — Async std runtime
+        - tokio — Tokio runtime
+
+        ## Serialization
+        Formats for serde.
+
+        - bincode — Bincode format
+        - json — Json format
+    "#]]
+    .assert_eq(&extract_sorted(GROUPS_TOML, SortFeatures::Alphabetical));
+}
+
+const LABEL_TOML: &str = indoc! {r#"
+    [features]
+    default = ["std"]
+    ## Use the standard library
+    ## since: 1.2.0
+    std = []
+    ## since: 1.3.0
+    ## Serde support
+    serde = []
+"#};
+
+#[test]
+fn test_label_default_marker() {
+    expect![[r#"
+        - `std`* — Use the standard library
+        - `serde` — Serde support
+    "#]]
+    .assert_eq(
+        &extract(
+            LABEL_TOML,
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "`{feature}`{default_marker}",
+                default_marker: "",
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_label_since() {
+    expect![[r#"
+        - std *since 1.2.0* (default) — Use the standard library
+        - serde *since 1.3.0* — Serde support
+    "#]]
+    .assert_eq(
+        &extract(
+            LABEL_TOML,
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "{feature} *since {since}*",
+                default_marker: "(default)",
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+}
+
+const DEPS_TOML: &str = indoc! {r#"
+    [features]
+    default = ["std"]
+    ## Use the standard library
+    std = []
+    ## Everything
+    full = ["std", "serde", "dep:tokio", "serde/derive"]
+    serde = []
+    internal = ["std"]
+"#};
+
+#[test]
+fn test_feature_deps() {
+    expect![[r#"
+        - std *(enabled by default)* — Use the standard library
+        - full — Everything
+          *Enables: `std`, `serde`.*
+        - serde
+    "#]]
+    .assert_eq(
+        &extract(
+            DEPS_TOML,
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "{feature}",
+                show_feature_deps: true,
+                hidden_features: &["internal".into()],
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_feature_rdeps() {
+    expect![[r#"
+        - std *(enabled by default)* — Use the standard library
+          *Required by: `full`, `internal`.*
+        - full — Everything
+          *Enables: `std`, `serde`.*
+        - serde — *Required by: `full`.*
+        - internal — *Enables: `std`.*
+    "#]]
+    .assert_eq(
+        &extract(
+            DEPS_TOML,
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "{feature}",
+                show_feature_deps: true,
+                show_feature_rdeps: true,
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_feature_rdeps_hidden() {
+    expect![[r#"
+        - std *(enabled by default)* — Use the standard library
+          *Required by: `full`.*
+        - full — Everything
+        - serde — *Required by: `full`.*
+    "#]]
+    .assert_eq(
+        &extract(
+            DEPS_TOML,
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "{feature}",
+                show_feature_rdeps: true,
+                hidden_features: &["internal".into()],
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_feature_format_table() {
+    let toml = indoc! {r#"
+        [features]
+        default = ["std"]
+        ## Some docs about std
+        std = ["alloc"]
+        ## Docs about alloc
+        ##
+        ## With a second paragraph and a | pipe.
+        alloc = []
+        #! Docs about the following features
+        serde = []
+    "#};
+
+    let out = extract(
+        toml,
+        None,
+        &ExtractFeatureDocsOptions {
+            feature_format: FeatureFormat::Table,
+            show_feature_deps: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    expect![[r#"
+        | Feature | Description |
+        |---------|-------------|
+        | **`std`** *(enabled by default)* | Some docs about std *Enables: `alloc`.* |
+        | **`alloc`** | Docs about alloc<br><br>With a second paragraph and a \| pipe. |
+
+        Docs about the following features
+
+        | Feature | Description |
+        |---------|-------------|
+        | **`serde`** |  |
+    "#]]
+    .assert_eq(&out);
+}
+
+#[test]
+fn test_comment_prefix() {
+    let custom = extract(
+        indoc! {r#"
+            [features]
+            default = ["std"]
+            # prose: Docs about std
+            # doc: Some docs about std
+            # doc:
+            # doc: More docs.
+            std = []
+            ## Not docs with custom prefixes
+            serde = []
+        "#},
+        None,
+        &ExtractFeatureDocsOptions {
+            feature_comment_prefix: "# doc:",
+            in_between_comment_prefix: "# prose:",
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let default = extract(
+        indoc! {r#"
+            [features]
+            default = ["std"]
+            #! Docs about std
+            ## Some docs about std
+            ##
+            ## More docs.
+            std = []
+            serde = []
+        "#},
+        None,
+        &ExtractFeatureDocsOptions::default(),
+    )
+    .unwrap();
+
+    assert_eq!(custom, default);
+}
+
+#[test]
+fn test_feature_config() {
+    expect![[r#"
+        - std *since 1.0.0* — Use the standard library
+        - **serde** *since 1.3.0* — Serde support
+          *Status: experimental.*
+    "#]]
+    .assert_eq(
+        &extract(
+            indoc! {r#"
+                [features]
+                ## Use the standard library
+                std = []
+                ## Serde support
+                ## since: 1.2.0
+                serde = []
+                ## Internal stuff
+                internal = []
+
+                [package.metadata.insert-docs.feature-config.std]
+                since = "1.0.0"
+
+                [package.metadata.insert-docs.feature-config.serde]
+                since = "1.3.0"
+                status = "experimental"
+                label = "**{feature}** *since {since}*"
+
+                [package.metadata.insert-docs.feature-config.internal]
+                hidden = true
+            "#},
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "{feature} *since {since}*",
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_workspace_feature_docs() {
+    expect![[r#"
+        - `std` — Use the standard library
+        - `serde` — Serde support
+          for all packages
+        - `internal`
+    "#]]
+    .assert_eq(
+        &extract(
+            indoc! {r#"
+                [features]
+                ## Use the standard library
+                std = []
+                serde = []
+                internal = []
+            "#},
+            Some(indoc! {r#"
+                [workspace]
+                members = ["foo"]
+
+                [workspace.metadata.insert-docs.feature-docs]
+                std = "Overwritten by the package docs"
+                serde = """
+                Serde support
+                for all packages
+                """
+            "#}),
+            &ExtractFeatureDocsOptions { feature_label: "`{feature}`", ..Default::default() },
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_features_file() {
+    expect![[r#"
+        - `serde` — Serde support
+          for all types
+        - `std` *(enabled by default)* — Use the standard library
+        - `internal`
+    "#]]
+    .assert_eq(
+        &extract(
+            indoc! {r#"
+                [features]
+                default = ["std"]
+                ## Ignored because of the features file
+                std = []
+                internal = []
+                serde = []
+            "#},
+            None,
+            &ExtractFeatureDocsOptions {
+                feature_label: "`{feature}`",
+                features_file: Some(indoc! {r#"
+                    [features.serde]
+                    docs = """
+                    Serde support
+                    for all types
+                    """
+
+                    [features.std]
+                    docs = "Use the standard library"
+
+                    [features.unknown]
+                    docs = "Doesn't exist"
+                "#}),
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+}
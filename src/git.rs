@@ -5,6 +5,7 @@ use core::fmt;
 use std::{
     collections::{HashMap, HashSet, hash_map},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use arcstr::ArcStr;
@@ -26,6 +27,8 @@ pub fn file_status(paths: impl IntoIterator<Item: AsRef<Path>>) -> Vec<Status> {
 #[derive(Debug, Default)]
 struct StatusChecker {
     repos: HashMap<PathBuf, RepoAndPaths>,
+    // Maps directories to the path of the repository they were discovered in.
+    discovered: HashMap<PathBuf, Option<PathBuf>>,
     statuses: IndexMap<PathBuf, Option<Status>>,
     results: Vec<ResultKind>,
 }
@@ -70,7 +73,7 @@ impl StatusChecker {
         let repo = match self.repo_at(path) {
             Ok(Some(repo)) => repo,
             Ok(None) => return TryAdd::Orphan,
-            Err(err) => return TryAdd::Err(Error::new(err)),
+            Err(err) => return TryAdd::Err(err),
         };
 
         let workdir = match repo.repo.workdir() {
@@ -93,29 +96,32 @@ impl StatusChecker {
             None => return Err(error!("path has no parent")),
         };
 
-        let repo_path = match gix::discover::upwards(path) {
-            Ok(ok) => ok.0.into_repository_and_work_tree_directories().0,
-            Err(err) => {
-                return match err {
-                    gix::discover::upwards::Error::NoGitRepository { .. }
-                    | gix::discover::upwards::Error::NoGitRepositoryWithinCeiling { .. }
-                    | gix::discover::upwards::Error::NoGitRepositoryWithinFs { .. } => Ok(None),
-                    _ => Err(Error::new(err)),
-                };
+        let repo_path = match self.discovered.get(path) {
+            Some(Some(repo_path)) => repo_path.clone(),
+            Some(None) => return Ok(None),
+            None => {
+                let repo_path = discover(path)?;
+                self.discovered.insert(path.to_path_buf(), repo_path.clone());
+
+                match repo_path {
+                    Some(some) => some,
+                    None => return Ok(None),
+                }
             }
         };
 
         Ok(Some(match self.repos.entry(repo_path) {
             hash_map::Entry::Occupied(entry) => entry.into_mut(),
             hash_map::Entry::Vacant(entry) => {
-                let repo = gix::open(entry.key()).unwrap();
+                let repo =
+                    gix::open(entry.key()).map_err(|err| Error::open_repo(entry.key(), err))?;
                 entry.insert(RepoAndPaths { repo, paths: Default::default() })
             }
         }))
     }
 
     fn finish(self) -> Vec<Status> {
-        let Self { repos, results, mut statuses } = self;
+        let Self { repos, discovered: _, results, mut statuses } = self;
 
         for RepoAndPaths { repo, paths } in repos.into_values() {
             if paths.is_empty() {
@@ -175,6 +181,18 @@ impl StatusChecker {
     }
 }
 
+fn discover(directory: &Path) -> Result<Option<PathBuf>> {
+    match gix::discover::upwards(directory) {
+        Ok(ok) => Ok(Some(ok.0.into_repository_and_work_tree_directories().0)),
+        Err(err) => match err {
+            gix::discover::upwards::Error::NoGitRepository { .. }
+            | gix::discover::upwards::Error::NoGitRepositoryWithinCeiling { .. }
+            | gix::discover::upwards::Error::NoGitRepositoryWithinFs { .. } => Ok(None),
+            _ => Err(Error::new(err)),
+        },
+    }
+}
+
 fn repo_status(
     repo: &gix::Repository,
     paths: impl IntoIterator<Item = BString>,
@@ -234,11 +252,13 @@ impl Status {
 #[derive(Clone, PartialEq, Eq)]
 pub struct Error {
     inner: ArcStr,
+    // The repository we failed to open, if that's what this error is about.
+    repo_path: Option<Arc<Path>>,
 }
 
 macro_rules! error {
     ($lit:literal) => {
-        Error { inner: arcstr::literal!($lit) }
+        Error { inner: arcstr::literal!($lit), repo_path: None }
     };
 }
 
@@ -246,18 +266,27 @@ use error;
 
 impl Error {
     fn new(str: impl ToString) -> Self {
-        Self { inner: str.to_string().into() }
+        Self { inner: str.to_string().into(), repo_path: None }
+    }
+
+    fn open_repo(path: &Path, str: impl ToString) -> Self {
+        Self { inner: str.to_string().into(), repo_path: Some(path.into()) }
     }
 }
 
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(&self.inner.as_str(), f)
+        fmt::Debug::fmt(&self.to_string(), f)
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(repo_path) = &self.repo_path {
+            let repo_path = repo_path.display();
+            f.write_fmt(format_args!("failed to open git repository at {repo_path}: "))?;
+        }
+
         fmt::Display::fmt(&self.inner.as_str(), f)
     }
 }
@@ -1,6 +1,6 @@
 use std::path::Path;
 
-use crate::git::{Status, file_status};
+use crate::git::{Error, Status, StatusChecker, file_status};
 
 #[test]
 fn test_example() {
@@ -51,3 +51,44 @@ fn test_outside_subdir() {
 fn test_in_subdir() {
     check_test_crate(true);
 }
+
+#[test]
+fn test_open_repo_error() {
+    let error = Error::open_repo(Path::new("path/to/repo"), "not a git repository");
+
+    assert_eq!(
+        error.to_string(),
+        "failed to open git repository at path/to/repo: not a git repository"
+    );
+}
+
+#[test]
+fn test_discovery_is_cached() {
+    let workspace_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut checker = StatusChecker::default();
+
+    // both paths share the discovery of `src`
+    checker.add(&workspace_dir.join("src").join("main.rs"));
+    checker.add(&workspace_dir.join("src").join("lib.rs"));
+    assert_eq!(checker.discovered.len(), 1);
+    assert_eq!(checker.repos.len(), 1);
+
+    // another directory is discovered on its own, but it's the same repository
+    checker.add(&workspace_dir.join("Cargo.toml"));
+    assert_eq!(checker.discovered.len(), 2);
+    assert_eq!(checker.repos.len(), 1);
+
+    // a directory outside of any repository is cached as a miss
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("file.txt");
+    std::fs::write(&path, "").unwrap();
+    checker.add(&path);
+    checker.add(&path);
+    assert_eq!(checker.discovered.get(dir.path()), Some(&None));
+    assert_eq!(checker.discovered.len(), 3);
+    assert_eq!(checker.repos.len(), 1);
+
+    let status = checker.finish();
+    assert!(matches!(status[3], Status::Orphan));
+    assert!(matches!(status[4], Status::Orphan));
+}
@@ -0,0 +1,126 @@
+//! Installs a git pre-commit hook for `cargo insert-docs install-hook`.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Result, WrapErr as _, bail};
+use tracing::{error_span, info, warn};
+
+/// Marks a hook as installed by us, so we don't remove someone else's hook.
+const MARKER: &str = "# installed by `cargo insert-docs install-hook`";
+
+#[derive(Debug, Clone, Default)]
+pub struct HookOptions {
+    /// Overwrite or remove a hook that was not installed by us.
+    pub force: bool,
+    /// Remove the hook instead of installing it.
+    pub uninstall: bool,
+    /// Extra arguments for `cargo insert-docs --check`, inserted into the script as is.
+    pub args: Option<String>,
+}
+
+/// Returns the pre-commit hook script.
+pub fn script(args: Option<&str>) -> String {
+    let mut command = String::from("cargo insert-docs --check --quiet");
+
+    if let Some(args) = args.map(str::trim).filter(|args| !args.is_empty()) {
+        command.push(' ');
+        command.push_str(args);
+    }
+
+    format!("#!/bin/sh\n{MARKER}\nexec {command}\n")
+}
+
+/// Returns whether `script` is a hook we installed.
+pub fn is_ours(script: &str) -> bool {
+    script.lines().nth(1) == Some(MARKER)
+}
+
+/// Installs or uninstalls the pre-commit hook of the repository containing the current directory.
+pub fn run(options: &HookOptions) -> Result<()> {
+    let path = pre_commit_path()?;
+    let _span = error_span!("", path = %path.display()).entered();
+
+    let existing = match fs::read_to_string(&path) {
+        Ok(existing) => Some(existing),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err).wrap_err("failed to read the existing hook"),
+    };
+
+    if options.uninstall {
+        match existing {
+            None => warn!("no pre-commit hook installed"),
+            Some(existing) if !is_ours(&existing) && !options.force => {
+                bail!(
+                    "pre-commit hook was not installed by cargo-insert-docs, use `--force` to remove it anyway"
+                )
+            }
+            Some(_) => {
+                fs::remove_file(&path).wrap_err("failed to remove the hook")?;
+                info!("removed pre-commit hook");
+            }
+        }
+
+        return Ok(());
+    }
+
+    if let Some(existing) = &existing
+        && !is_ours(existing)
+        && !options.force
+    {
+        bail!("a pre-commit hook already exists, use `--force` to overwrite it");
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).wrap_err("failed to create the hooks directory")?;
+    }
+
+    fs::write(&path, script(options.args.as_deref())).wrap_err("failed to write the hook")?;
+    make_executable(&path)?;
+
+    info!("installed pre-commit hook");
+    Ok(())
+}
+
+/// Returns the path of the pre-commit hook, respecting `GIT_DIR` and `core.hooksPath`.
+fn pre_commit_path() -> Result<PathBuf> {
+    let current_dir = std::env::current_dir().wrap_err("failed to get the current directory")?;
+
+    let repo = gix::ThreadSafeRepository::discover_with_environment_overrides(&current_dir)
+        .wrap_err("the current directory is not inside a git repository")?
+        .to_thread_local();
+
+    let hooks_dir = match repo.config_snapshot().string("core.hooksPath") {
+        Some(hooks_path) => {
+            let hooks_path = gix::path::from_bstr(hooks_path.as_ref()).into_owned();
+
+            // like git, a relative path is relative to the work tree
+            let base = repo.workdir().unwrap_or(repo.git_dir());
+            base.join(hooks_path)
+        }
+        // worktrees share the hooks of the main repository
+        None => repo.common_dir().join("hooks"),
+    };
+
+    Ok(hooks_dir.join("pre-commit"))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let mut permissions =
+        fs::metadata(path).wrap_err("failed to read the hook metadata")?.permissions();
+    permissions.set_mode(permissions.mode() | 0o111);
+    fs::set_permissions(path, permissions).wrap_err("failed to make the hook executable")
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use expect_test::expect;
+
+use super::{is_ours, script};
+
+#[test]
+fn test_script() {
+    expect![[r#"
+        #!/bin/sh
+        # installed by `cargo insert-docs install-hook`
+        exec cargo insert-docs --check --quiet
+    "#]]
+    .assert_eq(&script(None));
+
+    expect![[r#"
+        #!/bin/sh
+        # installed by `cargo insert-docs install-hook`
+        exec cargo insert-docs --check --quiet --workspace --allow-dirty
+    "#]]
+    .assert_eq(&script(Some(" --workspace --allow-dirty ")));
+}
+
+#[test]
+fn test_is_ours() {
+    assert!(is_ours(&script(Some("--workspace"))));
+    assert!(!is_ours("#!/bin/sh\ncargo fmt --check\n"));
+    assert!(!is_ours(""));
+}
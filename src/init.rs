@@ -0,0 +1,105 @@
+//! Adds empty sections for `cargo insert-docs init`.
+
+#[cfg(test)]
+mod tests;
+
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+
+use crate::{
+    edit_crate_docs::FeatureDocsSection,
+    line_ending::detect_line_ending,
+    markdown::{self, SectionDelimiters},
+    string_replacer::StringReplacer,
+};
+
+/// The content of a newly added section.
+pub const PLACEHOLDER: &str = "<!-- auto-generated by cargo insert-docs -->";
+
+/// Adds the section after the first paragraph of the readme or at the end if there is none.
+///
+/// Returns `None` if the readme already has the section.
+pub fn readme(
+    readme: &str,
+    section_name: &str,
+    delimiters: &SectionDelimiters,
+) -> Result<Option<String>> {
+    if markdown::find_section(readme, section_name, delimiters)?.is_some() {
+        return Ok(None);
+    }
+
+    let start = delimiters.start(section_name);
+    let end = delimiters.end(section_name);
+    let line_ending = detect_line_ending(readme);
+    let section = line_ending.apply(&format!("{start}\n{PLACEHOLDER}\n{end}\n")).into_owned();
+
+    let index = markdown::end_of_first_paragraph(readme).unwrap_or(readme.len());
+
+    // an empty line separates the section from the text before it
+    let newlines = match &readme[..index] {
+        "" => 0,
+        before if before.ends_with('\n') => 1,
+        _ => 2,
+    };
+    let separator = line_ending.as_str().repeat(newlines);
+
+    let mut out = StringReplacer::new(readme);
+    out.insert(index, format!("{separator}{section}"));
+    Ok(Some(out.finish()))
+}
+
+/// Adds the section after the last `//!` line at the start of the crate source,
+/// or as the first lines if there are none.
+///
+/// Returns `None` if the crate docs already have the section.
+pub fn crate_docs(
+    source: &str,
+    source_path: &Path,
+    section_name: &str,
+    delimiters: &SectionDelimiters,
+) -> Result<Option<String>> {
+    if FeatureDocsSection::find(source, source_path, section_name, delimiters)?.is_some() {
+        return Ok(None);
+    }
+
+    let mut insert_at = None;
+    let mut index = 0;
+
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("//!") {
+            insert_at = Some(index + line.len());
+        } else if !(trimmed.trim_end().is_empty()
+            || trimmed.starts_with("//")
+            || trimmed.starts_with("#!["))
+        {
+            // the crate docs are only at the start of the file
+            break;
+        }
+
+        index += line.len();
+    }
+
+    let start = delimiters.start(section_name);
+    let end = delimiters.end(section_name);
+
+    // an empty line separates the section from the existing crate docs
+    let separator = if insert_at.is_some() { "//!\n" } else { "" };
+    let section = format!("{separator}//! {start}\n//! {PLACEHOLDER}\n//! {end}\n");
+    let line_ending = detect_line_ending(source);
+
+    let mut out = StringReplacer::new(source);
+
+    match insert_at {
+        // the last `//!` line is also the last line of the file
+        Some(index) if !source[..index].ends_with('\n') => {
+            out.insert(index, line_ending.apply(&format!("\n{section}")).into_owned());
+        }
+        Some(index) => out.insert(index, line_ending.apply(&section).into_owned()),
+        None => out.insert(0, line_ending.apply(&section).into_owned()),
+    }
+
+    Ok(Some(out.finish()))
+}
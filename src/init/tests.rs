@@ -0,0 +1,126 @@
+use std::path::Path;
+
+use expect_test::expect;
+use indoc::indoc;
+
+use crate::markdown::SectionDelimiters;
+
+fn readme(readme: &str) -> Option<String> {
+    super::readme(readme, "crate documentation", &SectionDelimiters::default()).unwrap()
+}
+
+fn crate_docs(source: &str) -> Option<String> {
+    super::crate_docs(
+        source,
+        Path::new("lib.rs"),
+        "feature documentation",
+        &SectionDelimiters::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_readme() {
+    expect![[r#"
+        # My crate
+
+        Does things.
+
+        <!-- crate documentation start -->
+        <!-- auto-generated by cargo insert-docs -->
+        <!-- crate documentation end -->
+
+        ## License
+    "#]]
+    .assert_eq(
+        &readme(indoc! {"
+            # My crate
+
+            Does things.
+
+            ## License
+        "})
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_readme_without_paragraph() {
+    expect![[r#"
+        # My crate
+
+        <!-- crate documentation start -->
+        <!-- auto-generated by cargo insert-docs -->
+        <!-- crate documentation end -->
+    "#]]
+    .assert_eq(&readme("# My crate").unwrap());
+
+    expect![[r#"
+        <!-- crate documentation start -->
+        <!-- auto-generated by cargo insert-docs -->
+        <!-- crate documentation end -->
+    "#]]
+    .assert_eq(&readme("").unwrap());
+}
+
+#[test]
+fn test_readme_existing_section() {
+    assert_eq!(
+        readme(indoc! {"
+            <!-- crate documentation start -->
+            <!-- crate documentation end -->
+        "}),
+        None
+    );
+}
+
+#[test]
+fn test_crate_docs() {
+    expect![[r#"
+        //! # My crate
+        //!
+        //! Does things.
+        //!
+        //! <!-- feature documentation start -->
+        //! <!-- auto-generated by cargo insert-docs -->
+        //! <!-- feature documentation end -->
+        #![no_std]
+
+        /// Not crate docs.
+        pub fn foo() {}
+    "#]]
+    .assert_eq(
+        &crate_docs(indoc! {"
+            //! # My crate
+            //!
+            //! Does things.
+            #![no_std]
+
+            /// Not crate docs.
+            pub fn foo() {}
+        "})
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_crate_docs_without_docs() {
+    expect![[r#"
+        //! <!-- feature documentation start -->
+        //! <!-- auto-generated by cargo insert-docs -->
+        //! <!-- feature documentation end -->
+        pub fn foo() {}
+    "#]]
+    .assert_eq(&crate_docs("pub fn foo() {}\n").unwrap());
+}
+
+#[test]
+fn test_crate_docs_existing_section() {
+    assert_eq!(
+        crate_docs(indoc! {"
+            //! <!-- feature documentation start -->
+            //! <!-- feature documentation end -->
+        "}),
+        None
+    );
+}
@@ -0,0 +1,2091 @@
+#![doc = include_str!("../README.md")]
+#![allow(
+    // ifs are intentionally uncollapsed to make the logic clearer
+    clippy::collapsible_if,
+    clippy::collapsible_else_if,
+)]
+
+pub mod api;
+mod atomic_write;
+mod badge;
+mod check_links;
+mod cli;
+mod config;
+mod diff;
+mod edit_crate_docs;
+mod extract_crate_docs;
+mod extract_feature_docs;
+mod git;
+mod hook;
+mod init;
+mod line_ending;
+mod markdown;
+mod markdown_rs;
+mod pretty_log;
+mod readme_template;
+mod release_filter;
+mod run_summary;
+mod rustdoc_json;
+mod string_replacer;
+#[cfg(test)]
+mod tests;
+mod toolchain_file;
+
+extern crate alloc;
+
+use core::fmt::Write;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, IsTerminal as _},
+    mem,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Instant,
+};
+
+use cargo_metadata::{Metadata, MetadataCommand, Package, Target};
+use color_eyre::eyre::{OptionExt, Report, Result, WrapErr as _, bail, eyre};
+use encoding_rs::Encoding;
+use indexmap::IndexSet;
+use relative_path::PathExt;
+use serde::Serialize;
+use tracing::{Level, error_span, info, info_span, span::EnteredSpan, trace, warn};
+
+use pretty_log::{
+    LogFormat, Mutx as _, PrettyLog, WithErrorSeverity as _, WithResultSeverity as _,
+};
+
+use crate::{
+    cli::Cli,
+    config::{
+        CliConfig, PackageConfig, PackageConfigPatch, PrintConfigFormat, WorkspaceConfig,
+        WorkspaceConfigPatch, is_lib_like,
+    },
+    pretty_log::AnyWrite,
+    run_summary::{PackageSummary, RunSummary},
+    string_replacer::StringReplacer,
+};
+
+/// Runs `cargo insert-docs` with the arguments of the current process.
+#[doc(hidden)]
+pub fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    if cli.cfg.print_supported_toolchain {
+        println!("{}", config::DEFAULT_TOOLCHAIN);
+
+        // stdout only has the toolchain so scripts can use it directly
+        eprintln!(
+            "rustdoc json version {} (rustdoc-types {})",
+            rustdoc_types::FORMAT_VERSION,
+            rustdoc_json::RUSTDOC_TYPES_VERSION,
+        );
+
+        return ExitCode::SUCCESS;
+    }
+
+    if let Some(shell) = cli.cfg.completions {
+        cli::print_completions(shell);
+        return ExitCode::SUCCESS;
+    }
+
+    if cli.cfg.config_schema {
+        println!("{}", config::json_schema());
+        return ExitCode::SUCCESS;
+    }
+
+    let stream: Box<dyn AnyWrite> = if cli.cfg.quiet {
+        Box::new(io::empty())
+    } else {
+        Box::new(anstream::AutoStream::new(std::io::stderr(), cli.cfg.color))
+    };
+
+    let log = PrettyLog::new(stream, cli.cfg.log_format);
+    log.source_info(cli.cfg.verbose >= 2);
+    log.progress(
+        !cli.cfg.quiet
+            && cli.cfg.color != anstream::ColorChoice::Never
+            && cli.cfg.log_format == LogFormat::Pretty
+            && io::stderr().is_terminal(),
+    );
+
+    let log_level = if cli.cfg.verbose >= 1 { "trace" } else { "info" };
+    log.install(&format!("cargo_insert_docs={log_level}"));
+
+    let result = match &cli.cfg.install_hook {
+        Some(options) => hook::run(options),
+        None => try_main(&cli, &log),
+    };
+
+    if let Err(err) = result {
+        log.print_report(&err);
+    }
+
+    log.print_tally();
+
+    if log.tally().errors == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
+
+fn try_main(cli: &Cli, log: &PrettyLog) -> Result<()> {
+    let mut cmd = MetadataCommand::new();
+
+    if let Some(manifest_path) = cli.cfg.manifest_path.as_deref() {
+        cmd.manifest_path(manifest_path);
+    }
+
+    let metadata = cmd.exec()?;
+    let (workspace_workspace_config_patch, workspace_package_config_patch, mut profiles) =
+        config::read_workspace_config(&metadata.workspace_metadata)?;
+
+    // the package at the workspace root can have profiles too,
+    // the ones of the workspace take precedence
+    let root_package_workspace_config_patch = match metadata.root_package() {
+        Some(root) => {
+            let (patch, root_profiles) = config::read_root_package_profiles(&root.metadata)?;
+
+            for (name, profile) in root_profiles {
+                profiles.entry(name).or_insert(profile);
+            }
+
+            patch
+        }
+        None => WorkspaceConfigPatch::default(),
+    };
+
+    let workspace = root_package_workspace_config_patch
+        .apply(&workspace_workspace_config_patch)
+        .apply(&cli.workspace_patch)
+        .finish();
+
+    // the environment variables overwrite the manifests but not the cli arguments
+    let env_patch = CliConfig::from_env()?;
+
+    // the profile overwrites the manifests but not the environment variables or cli arguments
+    let package_patch = match &workspace.default_profile {
+        Some(name) => {
+            let _span = error_span!("", profile = name.as_str()).entered();
+            config::profile(&profiles, name)?.apply(&env_patch).apply(&cli.package_patch)
+        }
+        None => env_patch.apply(&cli.package_patch),
+    };
+
+    let mut packages: Vec<&Package> = if workspace.workspace {
+        metadata.workspace_members.iter().map(|p| &metadata[p]).collect()
+    } else if workspace.package.is_empty() {
+        assert!(
+            metadata.workspace_default_members.is_available(),
+            "to infer the current package, cargo of rust version 1.71 or higher is required"
+        );
+
+        if metadata.workspace_default_members.is_available() {
+            (*metadata.workspace_default_members).iter().map(|p| &metadata[p]).collect()
+        } else {
+            bail!("`cargo-insert-docs` requires a cargo version >= 1.71");
+        }
+    } else {
+        find_packages_by_name(&metadata, &workspace.package)?
+    };
+
+    let excluded_packages = workspace
+        .exclude
+        .iter()
+        .map(|name| find_package_by_name(&metadata, name))
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    packages.retain(|id| !excluded_packages.contains(id));
+
+    // directories in the workspace manifest are relative to the workspace root,
+    // directories from the cli are relative to the current directory
+    let exclude_dir_root = match cli.workspace_patch.exclude_dir {
+        Some(_) => Path::new(""),
+        None => metadata.workspace_root.as_std_path(),
+    };
+
+    retain_outside_dirs(&mut packages, exclude_dir_root, &workspace.exclude_dir)?;
+
+    let baseline = match (&cli.cfg.since_version, &cli.cfg.since_git_tag) {
+        (Some(version), _) => Some(release_filter::Baseline::Version(version)),
+        (None, Some(tag)) => Some(release_filter::Baseline::GitTag(tag)),
+        (None, None) => None,
+    };
+
+    if baseline.is_some() || cli.cfg.exclude_prereleases {
+        release_filter::retain_released(
+            &mut packages,
+            metadata.workspace_root.as_std_path(),
+            baseline,
+            cli.cfg.exclude_prereleases,
+        )?;
+    }
+
+    if packages.is_empty() {
+        bail!("no packages selected");
+    }
+
+    // error if a feature is not available in any selected package
+    if !cli.cfg.print_config {
+        let pkg = workspace_package_config_patch.clone().apply(&package_patch).finish();
+
+        let all_available_features = packages
+            .iter()
+            .flat_map(|p| p.features.keys())
+            .map(|s| s.as_str())
+            .collect::<HashSet<&str>>();
+
+        let unavailable_features = pkg
+            .features
+            .iter()
+            .map(|s| s.as_str())
+            .filter(|f| !all_available_features.contains(f))
+            .collect::<Vec<&str>>();
+
+        if !unavailable_features.is_empty() {
+            let contain_these_features = if unavailable_features.len() == 1 {
+                "contains this feature"
+            } else {
+                "contain these features"
+            };
+
+            let unavailable_features = unavailable_features.join(", ");
+
+            bail!("none of the selected packages {contain_these_features}: {unavailable_features}");
+        }
+    }
+
+    // We first prepare all the contexts for each package.
+    // This way we error early if there are any severe errors.
+    let write_lock = WriteLock::default();
+    let mut cxs = vec![];
+    let uses_default_packages = !workspace.workspace && workspace.package.is_empty();
+
+    // a nightly `rust-toolchain.toml` in the workspace root replaces the default toolchain
+    let workspace_toolchain = toolchain_file::find_nightly(metadata.workspace_root.as_std_path());
+
+    // features without docs fall back to `[workspace.metadata.insert-docs.feature-docs]`
+    let workspace_toml_path = metadata.workspace_root.as_std_path().join("Cargo.toml");
+    let workspace_toml = fs::read_to_string(&workspace_toml_path).wrap_err_with(|| {
+        format!("failed to read workspace manifest {}", workspace_toml_path.display())
+    })?;
+
+    // With `--keep-going` the errors are collected and printed after all other packages ran.
+    let keep_going = workspace_package_config_patch.apply(&package_patch).finish().keep_going;
+    let mut errors = vec![];
+    let mut summary = RunSummary::default();
+
+    for package in packages {
+        let _span = error_span!("", package = package.name.as_str()).entered();
+
+        match prepare_package(
+            cli,
+            log,
+            package,
+            &workspace_package_config_patch,
+            &package_patch,
+            uses_default_packages,
+            workspace_toolchain.as_deref(),
+            &workspace_toml,
+            &write_lock,
+        ) {
+            Ok(Some(cx)) => cxs.push(cx),
+            Ok(None) => (),
+            Err(report) => {
+                summary.packages.push(PackageSummary::failed(package.name.as_str(), &report));
+
+                if !keep_going {
+                    // the summary is written even though we stop early
+                    if let Some(path) = &cli.cfg.output_json {
+                        summary.write(path)?;
+                    }
+
+                    return Err(report);
+                }
+
+                errors.push(report);
+            }
+        }
+    }
+
+    if cli.cfg.print_config {
+        #[derive(Serialize)]
+        struct WorkspaceAndPackageConfigPatch<'a> {
+            #[serde(flatten)]
+            workspace: &'a WorkspaceConfigPatch,
+            #[serde(flatten)]
+            package: &'a PackageConfigPatch,
+        }
+
+        #[derive(Serialize)]
+        struct WorkspaceAndPackageConfig<'a> {
+            #[serde(flatten)]
+            workspace: &'a WorkspaceConfig,
+            #[serde(flatten)]
+            package: &'a PackageConfig,
+        }
+
+        #[derive(Serialize)]
+        struct PerPackage<'a> {
+            package: HashMap<&'a str, &'a PackageConfigPatch>,
+            resolved: HashMap<&'a str, WorkspaceAndPackageConfig<'a>>,
+        }
+
+        #[derive(Serialize)]
+        struct Table<'a> {
+            cli: WorkspaceAndPackageConfigPatch<'a>,
+            env: &'a PackageConfigPatch,
+            workspace: WorkspaceAndPackageConfigPatch<'a>,
+        }
+
+        let table = Table {
+            cli: WorkspaceAndPackageConfigPatch {
+                workspace: &cli.workspace_patch,
+                package: &cli.package_patch,
+            },
+            env: &env_patch,
+            workspace: WorkspaceAndPackageConfigPatch {
+                workspace: &workspace_workspace_config_patch,
+                package: &workspace_package_config_patch,
+            },
+        };
+
+        let out = match cli.cfg.print_config_format {
+            PrintConfigFormat::Toml => {
+                let mut out = toml::to_string(&table).wrap_err("toml serialization failed")?;
+
+                for cx in &cxs {
+                    let name = cx.package.name.as_str();
+
+                    out.push('\n');
+
+                    out.push_str(
+                        &toml::to_string(&PerPackage {
+                            package: HashMap::from_iter([(name, &cx.cfg_patch)]),
+                            resolved: HashMap::from_iter([(
+                                name,
+                                WorkspaceAndPackageConfig {
+                                    workspace: &workspace,
+                                    package: &cx.cfg,
+                                },
+                            )]),
+                        })
+                        .wrap_err("toml serialization failed")?,
+                    );
+                }
+
+                config::colorize_toml(&out)
+            }
+            PrintConfigFormat::Json => {
+                // the same tables as the toml, but in a single object
+                #[derive(Serialize)]
+                struct Json<'a> {
+                    #[serde(flatten)]
+                    table: Table<'a>,
+                    #[serde(flatten)]
+                    per_package: PerPackage<'a>,
+                }
+
+                let json = Json {
+                    table,
+                    per_package: PerPackage {
+                        package: cxs
+                            .iter()
+                            .map(|cx| (cx.package.name.as_str(), &cx.cfg_patch))
+                            .collect(),
+                        resolved: cxs
+                            .iter()
+                            .map(|cx| {
+                                let config = WorkspaceAndPackageConfig {
+                                    workspace: &workspace,
+                                    package: &cx.cfg,
+                                };
+                                (cx.package.name.as_str(), config)
+                            })
+                            .collect(),
+                    },
+                };
+
+                serde_json::to_string_pretty(&json).wrap_err("json serialization failed")?
+            }
+        };
+
+        print_reports(log, errors);
+        log.foreign_write_incoming();
+        let mut stdout = anstream::AutoStream::new(io::stdout(), cli.cfg.color);
+        io::Write::write_fmt(&mut stdout, format_args!("{out}\n"))?;
+        return Ok(());
+    }
+
+    if cxs.is_empty() && !errors.is_empty() {
+        print_reports(log, errors);
+
+        if let Some(path) = &cli.cfg.output_json {
+            summary.write(path)?;
+        }
+
+        return Ok(());
+    }
+
+    if cxs.is_empty() {
+        let _span = workspace_package_config_patch
+            .finish()
+            .target_selection
+            .map(|filter| error_span!("", %filter).entered());
+        bail!("no target found to document");
+    }
+
+    if cli.cfg.init {
+        print_reports(log, errors);
+        check_version_control(&cxs, None)?;
+        init_packages(&cxs);
+        return Ok(());
+    }
+
+    if cli.cfg.clean {
+        print_reports(log, errors);
+        check_version_control(&cxs, None)?;
+        clean_packages(&cxs);
+        return Ok(());
+    }
+
+    if cli.cfg.print_links {
+        print_reports(log, errors);
+        return print_links(&cxs, log, cli.cfg.output_json.as_deref());
+    }
+
+    if cli.cfg.print_link_index {
+        print_reports(log, errors);
+        return print_link_index(&cxs, log, cli.cfg.output_json.as_deref());
+    }
+
+    if cli.cfg.print_feature_docs {
+        print_reports(log, errors);
+        return print_feature_docs(&cxs, log);
+    }
+
+    if let Some(options) = &cli.cfg.check_links {
+        print_reports(log, errors);
+        return check_links(&cxs, options, cli.cfg.jobs, log);
+    }
+
+    // a dry run doesn't touch the files under version control
+    if !cli.cfg.dry_run
+        && let Err(report) = check_version_control(&cxs, workspace.workspace_readme.as_deref())
+    {
+        print_reports(log, errors);
+
+        if let Some(path) = &cli.cfg.output_json {
+            summary.write(path)?;
+        }
+
+        return Err(report);
+    }
+
+    run_packages(&cxs, cli.cfg.jobs, log, &mut summary);
+
+    if let Some(readme_path) = &workspace.workspace_readme {
+        let pkg = workspace_package_config_patch.apply(&package_patch).finish();
+        let section_name = &workspace.workspace_readme_section_name;
+
+        let _span =
+            info_span!("", task = "insert workspace documentation into workspace readme").entered();
+
+        let mut workspace_summary = PackageSummary::new("workspace");
+
+        if let Err(report) = section_delimiters(&pkg).and_then(|delimiters| {
+            insert_workspace_docs(
+                &cxs,
+                readme_path,
+                section_name,
+                &delimiters,
+                &mut workspace_summary,
+            )
+        }) {
+            let report = report.wrap_err("could not insert workspace documentation");
+            workspace_summary.push_error(&report);
+            log.print_report(&report);
+        }
+
+        workspace_summary.finish(cxs.first().is_some_and(|cx| cx.write_mode() == WriteMode::Check));
+        summary.workspace = Some(workspace_summary);
+    }
+
+    if cli.cfg.dry_run {
+        print_dry_run_summary(&cxs, &write_lock);
+    }
+
+    print_reports(log, errors);
+
+    if let Some(path) = &cli.cfg.output_json {
+        summary.write(path)?;
+    }
+
+    Ok(())
+}
+
+fn print_reports(log: &PrettyLog, reports: Vec<Report>) {
+    for report in reports {
+        log.print_report(&report);
+    }
+}
+
+/// Prints the intra-doc links of each package and what they resolve to without writing any files.
+fn print_links(cxs: &[PackageContext], log: &PrettyLog, output_json: Option<&Path>) -> Result<()> {
+    #[derive(Serialize)]
+    struct PackageLinks<'a> {
+        name: &'a str,
+        links: Vec<extract_crate_docs::DocLink>,
+    }
+
+    #[derive(Serialize)]
+    struct Links<'a> {
+        packages: Vec<PackageLinks<'a>>,
+    }
+
+    let mut packages = vec![];
+
+    for cx in cxs {
+        let name = cx.package.name.as_str();
+        let _span = error_span!("", package = name).entered();
+        packages.push(PackageLinks { name, links: extract_crate_docs::extract_links(cx)? });
+    }
+
+    if let Some(path) = output_json {
+        return run_summary::write_json(path, &Links { packages });
+    }
+
+    let mut out = String::new();
+
+    for package in &packages {
+        if packages.len() > 1 {
+            writeln!(out, "{}:", package.name)?;
+        }
+
+        let width = package.links.iter().map(|link| link.link.len()).max().unwrap_or(0);
+
+        for link in &package.links {
+            let status = match link.status {
+                extract_crate_docs::LinkStatus::Index => "INDEX",
+                extract_crate_docs::LinkStatus::Paths => "PATHS",
+                extract_crate_docs::LinkStatus::Failed => "FAILED",
+            };
+
+            let target = link.url.as_deref().or(link.error.as_deref()).unwrap_or_default();
+            writeln!(out, "{status:<6}  {:<width$}  {target}", link.link)?;
+        }
+    }
+
+    log.foreign_write_incoming();
+    print!("{out}");
+    Ok(())
+}
+
+fn print_link_index(
+    cxs: &[PackageContext],
+    log: &PrettyLog,
+    output_json: Option<&Path>,
+) -> Result<()> {
+    #[derive(Serialize)]
+    struct PackageItems<'a> {
+        name: &'a str,
+        items: Vec<extract_crate_docs::IndexItem>,
+    }
+
+    #[derive(Serialize)]
+    struct LinkIndex<'a> {
+        packages: Vec<PackageItems<'a>>,
+    }
+
+    let mut packages = vec![];
+
+    for cx in cxs {
+        let name = cx.package.name.as_str();
+        let _span = error_span!("", package = name).entered();
+        packages.push(PackageItems { name, items: extract_crate_docs::extract_link_index(cx)? });
+    }
+
+    if let Some(path) = output_json {
+        return run_summary::write_json(path, &LinkIndex { packages });
+    }
+
+    let mut out = String::new();
+
+    for package in &packages {
+        if packages.len() > 1 {
+            writeln!(out, "{}:", package.name)?;
+        }
+
+        let width = package.items.iter().map(|item| item.path.len()).max().unwrap_or(0);
+
+        for item in &package.items {
+            writeln!(out, "{:<width$}  {}", item.path, item.url)?;
+        }
+    }
+
+    log.foreign_write_incoming();
+    print!("{out}");
+    Ok(())
+}
+
+/// Checks the docs.rs links of the crate documentation that would be inserted into the readmes.
+fn check_links(
+    cxs: &[PackageContext],
+    options: &check_links::CheckLinksOptions,
+    jobs: usize,
+    log: &PrettyLog,
+) -> Result<()> {
+    let mut urls = IndexSet::new();
+
+    for cx in cxs {
+        let _span = error_span!("", package = cx.package.name.as_str()).entered();
+        let docs = extract_crate_docs::extract(cx)?;
+        urls.extend(check_links::docs_rs_urls(&docs).into_iter().map(str::to_string));
+    }
+
+    check_links::check(&urls.into_iter().collect::<Vec<_>>(), options, jobs, log)
+}
+
+/// Lists the files that `--dry-run` wrote into the mirror directory instead.
+fn print_dry_run_summary(cxs: &[PackageContext], write_lock: &WriteLock) {
+    let written = write_lock.mirrored.lck().clone();
+
+    if written.is_empty() {
+        info!("dry run: no files would change");
+        return;
+    }
+
+    for (path, mirror_path) in written {
+        let path = path
+            .relative_to(cxs[0].metadata.workspace_root.as_std_path())
+            .map(|p| p.to_string())
+            .unwrap_or_else(|_| path.display().to_string());
+
+        info!("dry run: {path} would change, see {}", mirror_path.display());
+    }
+}
+
+fn print_feature_docs(cxs: &[PackageContext], log: &PrettyLog) -> Result<()> {
+    let mut out = String::new();
+
+    for cx in cxs {
+        let name = cx.package.name.as_str();
+        let _span = error_span!("", package = name).entered();
+
+        // nest group headings like they would be in the crate docs, if the section is there
+        let target_path = cx.target.src_path.as_std_path();
+        let target_src = read_to_string(&cx.write_destination.read_path(target_path))?;
+        let heading_level = edit_crate_docs::FeatureDocsSection::find(
+            &target_src,
+            target_path,
+            &cx.cfg.feature_section_name,
+            &cx.section_delimiters,
+        )?
+        .map_or(0, |section| section.heading_level());
+
+        let feature_docs = feature_docs(cx, heading_level)?;
+
+        if cxs.len() > 1 {
+            writeln!(out, "=== {name} ({}) ===", cx.cfg.feature_section_name)?;
+        }
+
+        out.push_str(&feature_docs);
+
+        if !feature_docs.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+
+    log.foreign_write_incoming();
+    print!("{out}");
+    Ok(())
+}
+
+#[expect(clippy::too_many_arguments)]
+fn prepare_package<'a>(
+    cli: &'a Cli,
+    log: &PrettyLog,
+    package: &'a Package,
+    workspace_package_config_patch: &PackageConfigPatch,
+    package_patch: &PackageConfigPatch,
+    uses_default_packages: bool,
+    workspace_toolchain: Option<&str>,
+    workspace_toml: &'a str,
+    write_lock: &'a WriteLock,
+) -> Result<Option<PackageContext<'a>>> {
+    let manifest_path = ManifestPath::new(package.manifest_path.as_ref())?;
+    let toml = manifest_path.get().read_to_string()?;
+
+    let cfg_patch = config::read_package_config(&toml)?;
+
+    let mut final_patch = workspace_package_config_patch.apply(&cfg_patch).apply(package_patch);
+
+    if final_patch.bin.is_some() && final_patch.lib.is_some() {
+        bail!("`lib` and `bin` are both set, you have to choose one or the other");
+    }
+
+    if final_patch.readme_path.is_some() && final_patch.readme_paths.is_some() {
+        bail!("`readme-path` and `readme-paths` are both set, you have to choose one or the other");
+    }
+
+    if final_patch.crate_section_name.is_some() && final_patch.section_heading.is_some() {
+        bail!(
+            "`crate-section-name` and `section-heading` are both set, you have to choose one or the other"
+        );
+    }
+
+    // `toolchain` takes precedence over `toolchain-file` which takes precedence over
+    // the `rust-toolchain.toml` of the workspace
+    if final_patch.toolchain.is_none() {
+        final_patch.toolchain = match &final_patch.toolchain_file {
+            Some(path) => Some(toolchain_file::read(&manifest_path.relative(path).full_path)?),
+            None => workspace_toolchain.map(str::to_string),
+        };
+    }
+
+    let cfg = final_patch.finish();
+
+    let section_delimiters = section_delimiters(&cfg)?;
+
+    if cfg.no_link_resolution && cfg.link_to_latest {
+        bail!("`no-link-resolution` and `link-to-latest` are both set, links can't be resolved");
+    }
+
+    if cfg.no_link_resolution && cfg.source_module.is_some() {
+        bail!(
+            "`no-link-resolution` and `source-module` are both set, modules are only found in the rustdoc json"
+        );
+    }
+
+    for (name, prefix) in [
+        ("feature-comment-prefix", &cfg.feature_comment_prefix),
+        ("in-between-comment-prefix", &cfg.in_between_comment_prefix),
+    ] {
+        if !prefix.starts_with('#') {
+            bail!("`{name}` \"{prefix}\" must start with `#` to be a toml comment");
+        }
+    }
+
+    if cfg.feature_comment_prefix.starts_with(&cfg.in_between_comment_prefix)
+        || cfg.in_between_comment_prefix.starts_with(&cfg.feature_comment_prefix)
+    {
+        bail!(
+            "`feature-comment-prefix` and `in-between-comment-prefix` must not start with one another"
+        );
+    }
+
+    let enabled_features =
+        cfg.features.iter().filter(|&f| package.features.contains_key(f)).cloned().collect();
+
+    let target = match &cfg.target_selection {
+        Some(target_selection) => match target_selection {
+            config::TargetSelection::Lib => {
+                package.targets.iter().find(|t| t.doc && is_lib_like(t))
+            }
+            config::TargetSelection::Bin(bin) => match bin {
+                Some(bin_name) => {
+                    package.targets.iter().find(|t| t.doc && t.is_bin() && t.name == *bin_name)
+                }
+                None => package.targets.iter().find(|t| t.doc && t.is_bin()),
+            },
+        },
+        None => {
+            let lib = package.targets.iter().find(|t| t.doc && is_lib_like(t));
+            let bin = || package.targets.iter().find(|t| t.doc && t.is_bin());
+            lib.or_else(bin)
+        }
+    };
+
+    let Some(target) = target else {
+        return Ok(None);
+    };
+
+    let relative_readme_path = if let Some(path) = cfg.readme_path.as_deref() {
+        path
+    } else if let Some(path) = package.readme.as_deref() {
+        path.as_std_path()
+    } else {
+        Path::new("README.md")
+    };
+
+    let readmes = if cfg.readme_paths.is_empty() {
+        vec![Readme {
+            path: manifest_path.relative(relative_readme_path),
+            section_name: cfg.crate_section_name.clone(),
+        }]
+    } else {
+        cfg.readme_paths
+            .iter()
+            .map(|target| Readme {
+                path: manifest_path.relative(target.path()),
+                section_name: target.section_name().unwrap_or(&cfg.crate_section_name).to_string(),
+            })
+            .collect()
+    };
+    let readme_template_path =
+        cfg.readme_template.as_deref().map(|path| manifest_path.relative(path));
+
+    let section_encoding = match cfg.section_encoding.as_deref() {
+        Some(label) => Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| eyre!("unknown `section-encoding` \"{label}\""))?,
+        None => encoding_rs::UTF_8,
+    };
+
+    let mut cmd = MetadataCommand::new();
+    cmd.manifest_path(&package.manifest_path);
+
+    if cfg.no_default_features {
+        cmd.features(cargo_metadata::CargoOpt::NoDefaultFeatures);
+    }
+
+    if cfg.all_features {
+        cmd.features(cargo_metadata::CargoOpt::AllFeatures);
+    }
+
+    if cfg.features.is_empty() {
+        cmd.features(cargo_metadata::CargoOpt::SomeFeatures(cfg.features.clone()));
+    }
+
+    let metadata = cmd.exec()?;
+
+    let write_destination = if cli.cfg.dry_run {
+        let canonicalize = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        WriteDestination::Mirror(Mirror {
+            dir: cli.cfg.dry_run_output_dir.clone().unwrap_or_else(|| {
+                metadata.target_directory.as_std_path().join("insert-docs").join("dry-run")
+            }),
+            package_name: package.name.to_string(),
+            package_root: canonicalize(manifest_path.0.parent().unwrap()),
+            workspace_root: canonicalize(metadata.workspace_root.as_std_path()),
+            written: &write_lock.mirrored,
+        })
+    } else {
+        WriteDestination::InPlace
+    };
+
+    Ok(Some(PackageContext {
+        cli,
+        cfg,
+        cfg_patch,
+        package,
+        target,
+        enabled_features,
+        manifest_path,
+        readmes,
+        readme_template_path,
+        section_encoding,
+        section_delimiters,
+        uses_default_packages,
+        metadata,
+        workspace_toml,
+        log: log.clone(),
+        rustdoc_json: Mutex::new(None),
+        summary: Mutex::new(PackageSummary::new(package.name.as_str())),
+        write_lock,
+        write_destination,
+    }))
+}
+
+fn section_delimiters(cfg: &PackageConfig) -> Result<markdown::SectionDelimiters> {
+    match cfg.section_delimiter_template.as_slice() {
+        [start, end] => markdown::SectionDelimiters::new(start, end),
+        _ => bail!("`section-delimiter-template` must be a start and an end template"),
+    }
+}
+
+// Modified from `fn check_version_control` in `rust-lang/cargo/src/cargo/ops/fix/mod.rs`.
+/// Errors if files we are going to write have uncommitted changes.
+///
+/// The `workspace_readme`, relative to the workspace root, is written with the settings
+/// of the first package.
+fn check_version_control(cxs: &[PackageContext], workspace_readme: Option<&Path>) -> Result<()> {
+    if cxs.is_empty() {
+        return Ok(());
+    }
+
+    check_shared_readmes(cxs)?;
+
+    // files included with `include_str!` into the crate docs are written too
+    let mut included_files: Vec<(PathBuf, bool)> = vec![];
+
+    for cx in cxs {
+        if cx.write_mode() != WriteMode::Write || cx.cfg.no_vcs || cx.cfg.allow_dirty {
+            continue;
+        }
+
+        if cx.cfg.feature_into_crate {
+            let path = cx.target.src_path.as_std_path();
+
+            if let Ok(src) = fs::read_to_string(path) {
+                for included in edit_crate_docs::included_files(&src, path) {
+                    included_files.push((included, cx.cfg.allow_staged));
+                }
+            }
+        }
+    }
+
+    // bool: allow_staged
+    let mut files: Vec<(&Path, bool)> = vec![];
+
+    for (path, allow_staged) in &included_files {
+        files.push((path, *allow_staged));
+    }
+
+    let first = &cxs[0];
+    let workspace_readme = workspace_readme
+        .filter(|_| first.write_mode() == WriteMode::Write)
+        .filter(|_| !first.cfg.no_vcs && !first.cfg.allow_dirty)
+        .map(|path| first.metadata.workspace_root.as_std_path().join(path));
+
+    if let Some(path) = &workspace_readme {
+        files.push((path, first.cfg.allow_staged));
+    }
+
+    for cx in cxs {
+        if cx.write_mode() != WriteMode::Write || cx.cfg.no_vcs || cx.cfg.allow_dirty {
+            continue;
+        }
+
+        if cx.cfg.feature_into_crate {
+            let path = cx.target.src_path.as_std_path();
+            files.push((path, cx.cfg.allow_staged));
+        }
+
+        if cx.cfg.crate_into_readme {
+            for readme in &cx.readmes {
+                files.push((readme.path.full_path.as_path(), cx.cfg.allow_staged));
+            }
+        }
+    }
+
+    let status = git::file_status(files.iter().map(|f| f.0));
+
+    let error_files = files
+        .iter()
+        .zip(status.iter())
+        .filter_map(|((path, _), status)| match status {
+            git::Status::Error(error) => Some((path, error)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let dirty_files = files
+        .iter()
+        .zip(status.iter())
+        .filter_map(|((path, _), status)| match status {
+            git::Status::Dirty => Some(path),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let staged_files = files
+        .iter()
+        .zip(status.iter())
+        .filter_map(|((path, allow_staged), status)| match status {
+            git::Status::Staged if !allow_staged => Some(path),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if error_files.is_empty() && dirty_files.is_empty() && staged_files.is_empty() {
+        return Ok(());
+    }
+
+    let display_path = |path: &Path| -> String {
+        path.relative_to(cxs[0].metadata.workspace_root.as_std_path())
+            .map(|p| p.to_string())
+            .unwrap_or_else(|_| path.display().to_string())
+    };
+
+    let mut files_list = String::new();
+
+    for (path, error) in error_files {
+        let path = display_path(path);
+        _ = files_list.write_fmt(format_args!("  * {path} (error: {error})\n"));
+    }
+
+    for path in dirty_files {
+        let path = display_path(path);
+        _ = files_list.write_fmt(format_args!("  * {path} (dirty)\n"));
+    }
+
+    for path in staged_files {
+        let path = display_path(path);
+        _ = files_list.write_fmt(format_args!("  * {path} (staged)\n"));
+    }
+
+    bail!(
+        "the working directory of this package has uncommitted changes, and \n\
+            `cargo insert-docs` can potentially perform destructive changes;\n\
+            if you'd like to suppress this error pass `--allow-dirty`, \n\
+            or commit the changes to these files:\n\
+            \n\
+            {files_list}\n\
+         "
+    );
+}
+
+/// Errors if packages would insert their crate documentation into the same section of the same readme,
+/// like when several packages have `readme = "../../README.md"`.
+fn check_shared_readmes(cxs: &[PackageContext]) -> Result<()> {
+    let Some(first) = cxs.first() else {
+        return Ok(());
+    };
+
+    let readmes = cxs
+        .iter()
+        .filter(|cx| cx.cfg.crate_into_readme)
+        .flat_map(|cx| cx.readmes.iter().map(|readme| (cx.package.name.as_str(), readme)));
+
+    check_shared_readme_sections(readmes, first.metadata.workspace_root.as_std_path())
+}
+
+/// Errors if several of the `(package name, readme)` pairs have the same readme section.
+fn check_shared_readme_sections<'a>(
+    readmes: impl IntoIterator<Item = (&'a str, &'a Readme)>,
+    workspace_root: &Path,
+) -> Result<()> {
+    let mut packages_by_readme = HashMap::<(PathBuf, &str), Vec<&str>>::new();
+
+    for (package_name, readme) in readmes {
+        let full_path = &readme.path.full_path;
+        let path = full_path.canonicalize().unwrap_or_else(|_| full_path.clone());
+        let key = (path, readme.section_name.as_str());
+        packages_by_readme.entry(key).or_default().push(package_name);
+    }
+
+    let mut conflicts = packages_by_readme
+        .into_iter()
+        .filter(|(_, packages)| packages.len() > 1)
+        .collect::<Vec<_>>();
+
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+
+    conflicts.sort();
+
+    let display_path = |path: &Path| -> String {
+        path.relative_to(workspace_root)
+            .map(|p| p.to_string())
+            .unwrap_or_else(|_| path.display().to_string())
+    };
+
+    let mut list = String::new();
+
+    for ((path, section_name), packages) in conflicts {
+        let path = display_path(&path);
+        let packages = packages.iter().map(|p| format!("`{p}`")).collect::<Vec<_>>().join(", ");
+        _ = writeln!(list, "  * {path} (section \"{section_name}\"): {packages}");
+    }
+
+    bail!(
+        "several packages insert their crate documentation into the same readme section:\n\
+        \n\
+        {list}\n\
+        Give each package a distinct `crate-section-name`, so they insert into different sections."
+    )
+}
+
+fn run_packages(cxs: &[PackageContext], jobs: usize, log: &PrettyLog, summary: &mut RunSummary) {
+    let jobs = match jobs {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+
+    // Only building the rustdoc json happens in parallel.
+    // Reading it and writing the files stays sequential,
+    // so two packages never write the same file at once.
+    let needs_rustdoc_json = cxs
+        .iter()
+        .filter(|cx| cx.cfg.crate_into_readme && !cx.cfg.no_link_resolution)
+        .collect::<Vec<_>>();
+    let threads = jobs.min(needs_rustdoc_json.len());
+
+    if threads > 1 {
+        let next = AtomicUsize::new(0);
+        log.begin_progress(needs_rustdoc_json.len(), "building rustdoc json");
+
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| {
+                    while let Some(cx) =
+                        needs_rustdoc_json.get(next.fetch_add(1, Ordering::Relaxed))
+                    {
+                        let _span = cx.package_span();
+                        log.set_progress_message(cx.package.name.as_str());
+                        let result = extract_crate_docs::generate_rustdoc_json(cx, true);
+                        *cx.rustdoc_json.lck() = Some(result);
+                        log.tick_progress();
+                    }
+                });
+            }
+        });
+
+        log.finish_progress();
+    }
+
+    if cxs.len() > 1 {
+        log.begin_progress(cxs.len(), "inserting documentation");
+    }
+
+    for cx in cxs {
+        run_package(cx, summary);
+    }
+
+    log.finish_progress();
+}
+
+fn run_package(cx: &PackageContext, summary: &mut RunSummary) {
+    let _span = cx.package_span();
+
+    cx.log.set_progress_message(cx.package.name.as_str());
+
+    if cx.cfg.feature_into_crate {
+        crate_docs_task(cx, "feature documentation", insert_features_into_docs);
+    }
+
+    if cx.cfg.crate_into_readme {
+        if cx.readme_template_path.is_some() {
+            readme_task(cx, "readme template", expand_readme_template);
+        } else {
+            readme_task(cx, "crate documentation", insert_docs_into_readme);
+        }
+    }
+
+    let mut package = mem::take(&mut *cx.summary.lck());
+    package.finish(cx.write_mode() == WriteMode::Check);
+    summary.packages.push(package);
+
+    cx.log.tick_progress();
+}
+
+/// Adds the empty sections of `cargo insert-docs init`.
+fn init_packages(cxs: &[PackageContext]) {
+    for cx in cxs {
+        let _span = cx.package_span();
+
+        if cx.cfg.feature_into_crate {
+            crate_docs_task(cx, "feature section", init_crate_docs);
+        }
+
+        if cx.cfg.crate_into_readme && cx.readme_template_path.is_none() {
+            readme_task(cx, "crate section", init_readme);
+        }
+    }
+}
+
+fn init_crate_docs(cx: &PackageContext) -> Result<()> {
+    let path = cx.target.src_path.as_std_path();
+    let source = read_to_string(&cx.write_destination.read_path(path))?;
+    let section_name = &cx.cfg.feature_section_name;
+
+    let Some(new_source) = init::crate_docs(&source, path, section_name, &cx.section_delimiters)?
+    else {
+        let _span = info_span!("", path = %path.display(), section_name).entered();
+        warn!("section already exists, skipping");
+        return Ok(());
+    };
+
+    match cx.write_mode() {
+        WriteMode::Write => {
+            let written = write(path, new_source.as_bytes(), &cx.write_destination)?;
+            cx.summary.lck().files_written.push(written);
+        }
+        WriteMode::Check | WriteMode::Diff => print_diff(cx, path, &source, &new_source)?,
+    }
+
+    Ok(())
+}
+
+fn init_readme(cx: &PackageContext, target: &Readme) -> Result<()> {
+    let readme_path = &target.path;
+    let readme = readme_path
+        .read_from(&cx.write_destination)
+        .read_to_string_with_encoding(cx.section_encoding)?;
+    let section_name = &target.section_name;
+
+    let Some(new_readme) = init::readme(&readme, section_name, &cx.section_delimiters)? else {
+        let _span = info_span!("", path = %readme_path.full_path.display(), section_name).entered();
+        warn!("section already exists, skipping");
+        return Ok(());
+    };
+
+    match cx.write_mode() {
+        WriteMode::Write => {
+            let written = readme_path.write_with_encoding(
+                &new_readme,
+                cx.section_encoding,
+                &cx.write_destination,
+            )?;
+            cx.summary.lck().files_written.push(written);
+        }
+        WriteMode::Check | WriteMode::Diff => {
+            print_diff(cx, &readme_path.full_path, &readme, &new_readme)?
+        }
+    }
+
+    Ok(())
+}
+
+/// Empties the sections for `cargo insert-docs clean`.
+fn clean_packages(cxs: &[PackageContext]) {
+    for cx in cxs {
+        let _span = cx.package_span();
+
+        if cx.cfg.feature_into_crate {
+            crate_docs_task(cx, "empty feature section", clean_crate_docs);
+        }
+
+        if cx.cfg.crate_into_readme && cx.readme_template_path.is_none() {
+            readme_task(cx, "empty crate section", clean_readme);
+        }
+    }
+}
+
+fn clean_crate_docs(cx: &PackageContext) -> Result<()> {
+    let target_path = cx.target.src_path.as_std_path();
+    let target_src = read_to_string(&cx.write_destination.read_path(target_path))?;
+    let feature_docs_section = find_feature_docs_section(cx, target_path, &target_src)?;
+
+    if feature_docs_section.content().trim().is_empty() {
+        warn!("section is already empty");
+        return Ok(());
+    }
+
+    if cx.write_mode() == WriteMode::Check {
+        cx.summary.lck().stale = true;
+        bail!("feature documentation section is not empty");
+    }
+
+    let replaced = feature_docs_section.replace("")?;
+
+    match cx.write_mode() {
+        WriteMode::Diff => print_diff(cx, replaced.path, replaced.old, &replaced.new)?,
+        _ => {
+            let written = write(replaced.path, replaced.new.as_bytes(), &cx.write_destination)?;
+            cx.summary.lck().files_written.push(written);
+        }
+    }
+
+    Ok(())
+}
+
+fn clean_readme(cx: &PackageContext, target: &Readme) -> Result<()> {
+    let readme_path = &target.path;
+    let readme = readme_path
+        .read_from(&cx.write_destination)
+        .read_to_string_with_encoding(cx.section_encoding)?;
+    let section = find_readme_section(cx, target, &readme)?;
+
+    if readme[section.content_span.clone()].trim().is_empty() {
+        warn!("section is already empty");
+        return Ok(());
+    }
+
+    if cx.write_mode() == WriteMode::Check {
+        cx.summary.lck().stale = true;
+        bail!("crate documentation section is not empty");
+    }
+
+    let mut new_readme = StringReplacer::new(&readme);
+    new_readme.replace(section.content_span, line_ending::detect_line_ending(&readme).as_str());
+    let new_readme = new_readme.finish();
+
+    match cx.write_mode() {
+        WriteMode::Diff => print_diff(cx, &readme_path.full_path, &readme, &new_readme)?,
+        _ => {
+            let written = readme_path.write_with_encoding(
+                &new_readme,
+                cx.section_encoding,
+                &cx.write_destination,
+            )?;
+            cx.summary.lck().files_written.push(written);
+        }
+    }
+
+    Ok(())
+}
+
+fn find_packages_by_name(
+    metadata: &Metadata,
+    package_names: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Result<Vec<&Package>> {
+    package_names.into_iter().map(|name| find_package_by_name(metadata, name.as_ref())).collect()
+}
+
+fn find_package_by_name<'a>(metadata: &'a Metadata, package_name: &str) -> Result<&'a Package> {
+    for workspace_member in &metadata.workspace_members {
+        let package = &metadata[workspace_member];
+
+        if package.name.as_str() == package_name {
+            return Ok(package);
+        }
+    }
+
+    bail!("no package named \"{package_name}\" found")
+}
+
+/// Removes the packages whose manifest is in one of `dirs`, which are relative to `root`.
+fn retain_outside_dirs(packages: &mut Vec<&Package>, root: &Path, dirs: &[PathBuf]) -> Result<()> {
+    let dirs = dirs
+        .iter()
+        .map(|dir| {
+            root.join(dir)
+                .canonicalize()
+                .wrap_err_with(|| format!("failed to find excluded directory {}", dir.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    packages.retain(|package| {
+        let manifest_path = package.manifest_path.as_std_path();
+        let manifest_path = manifest_path.canonicalize().unwrap_or_else(|_| manifest_path.into());
+        !dirs.iter().any(|dir| manifest_path.starts_with(dir))
+    });
+
+    Ok(())
+}
+
+struct PackageContext<'a> {
+    cli: &'a Cli,
+    cfg: PackageConfig,
+    cfg_patch: PackageConfigPatch, // just for `--print-config`
+    package: &'a Package,
+    enabled_features: Vec<String>,
+    manifest_path: ManifestPath,
+    target: &'a Target,
+    /// The readmes the crate documentation is inserted into, from `readme-path` or `readme-paths`.
+    readmes: Vec<Readme>,
+    readme_template_path: Option<RelativePath>,
+    /// The encoding of the readme and readme template.
+    section_encoding: &'static Encoding,
+    section_delimiters: markdown::SectionDelimiters,
+    uses_default_packages: bool,
+    metadata: Metadata,
+    /// The contents of the workspace manifest, for `[workspace.metadata.insert-docs.feature-docs]`.
+    workspace_toml: &'a str,
+    log: PrettyLog,
+    /// The rustdoc json path if it was already generated in parallel by [`run_packages`].
+    rustdoc_json: Mutex<Option<Result<PathBuf>>>,
+    /// What happened while running this package, for `--output-json`.
+    summary: Mutex<PackageSummary>,
+    /// Shared by all packages of this run.
+    write_lock: &'a WriteLock,
+    write_destination: WriteDestination<'a>,
+}
+
+impl PackageContext<'_> {
+    /// Enters a span with the package name, unless only the default package is run.
+    fn package_span(&self) -> Option<EnteredSpan> {
+        (!self.uses_default_packages || (*self.metadata.workspace_default_members).len() > 1)
+            .then(|| info_span!("", package = self.package.name.as_str()).entered())
+    }
+
+    fn write_mode(&self) -> WriteMode {
+        if self.cli.cfg.print_diff {
+            WriteMode::Diff
+        } else if self.cfg.check {
+            WriteMode::Check
+        } else {
+            WriteMode::Write
+        }
+    }
+}
+
+/// A readme and the name of its crate documentation section.
+struct Readme {
+    path: RelativePath,
+    section_name: String,
+}
+
+/// What to do with the new content of a file.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WriteMode {
+    Write,
+    /// Error if the file is stale.
+    Check,
+    /// Print the changes instead of writing.
+    Diff,
+}
+
+/// Where files are written to.
+enum WriteDestination<'a> {
+    /// Overwrite the files themselves.
+    InPlace,
+    /// Write into a mirror directory instead, for `--dry-run`.
+    Mirror(Mirror<'a>),
+}
+
+struct Mirror<'a> {
+    /// Files of the package are written to `{dir}/{package}/`,
+    /// other files of the workspace like the workspace readme to `{dir}/`.
+    dir: PathBuf,
+    package_name: String,
+    package_root: PathBuf,
+    workspace_root: PathBuf,
+    /// Shared by all packages of this run, see [`WriteLock::mirrored`].
+    written: &'a Mutex<Vec<(PathBuf, PathBuf)>>,
+}
+
+impl WriteDestination<'_> {
+    /// Returns the path to write `path` to, creating the directories of the mirror.
+    fn path(&self, path: &Path) -> Result<PathBuf> {
+        let Self::Mirror(mirror) = self else {
+            return Ok(path.to_path_buf());
+        };
+
+        // the path may be like `foo/../README.md`
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        let mirror_path = if let Ok(relative) = path.strip_prefix(&mirror.package_root) {
+            mirror.dir.join(&mirror.package_name).join(relative)
+        } else if let Ok(relative) = path.strip_prefix(&mirror.workspace_root) {
+            mirror.dir.join(relative)
+        } else {
+            mirror.dir.join(path.file_name().ok_or_eyre("path has no file name")?)
+        };
+
+        if let Some(parent) = mirror_path.parent() {
+            fs::create_dir_all(parent).wrap_err("failed to create the dry run directory")?;
+        }
+
+        let mut written = mirror.written.lck();
+
+        if !written.iter().any(|(p, _)| *p == path) {
+            written.push((path, mirror_path.clone()));
+        }
+
+        Ok(mirror_path)
+    }
+
+    /// Returns the path to read `path` from, which is its mirror if it was already written.
+    fn read_path(&self, path: &Path) -> PathBuf {
+        let Self::Mirror(mirror) = self else {
+            return path.to_path_buf();
+        };
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let written = mirror.written.lck();
+
+        match written.iter().find(|(p, _)| *p == canonical) {
+            Some((_, mirror_path)) => mirror_path.clone(),
+            None => path.to_path_buf(),
+        }
+    }
+}
+
+struct ManifestPath(PathBuf);
+
+impl ManifestPath {
+    fn new(path: &Path) -> Result<Self> {
+        let path = path.canonicalize()?;
+        path.parent().ok_or_eyre("path has no parent")?;
+        path.file_name().ok_or_eyre("path has no file name")?;
+        Ok(Self(path))
+    }
+
+    fn get(&self) -> RelativePath {
+        RelativePath {
+            full_path: self.0.clone(),
+            relative_to_manifest: self.0.file_name().unwrap().into(),
+        }
+    }
+
+    fn relative(&self, relative: impl Into<PathBuf>) -> RelativePath {
+        let relative_to_manifest = relative.into();
+
+        RelativePath {
+            full_path: self.0.parent().unwrap().join(&relative_to_manifest),
+            relative_to_manifest,
+        }
+    }
+}
+
+// for better error messages when reading / writing files
+struct RelativePath {
+    full_path: PathBuf,
+    relative_to_manifest: PathBuf,
+}
+
+impl RelativePath {
+    fn read_to_string(&self) -> Result<String> {
+        let _span = error_span!("", path = %self.full_path.display()).entered();
+        let relative_path = self.relative_to_manifest.display();
+        fs::read_to_string(&self.full_path)
+            .with_context(|| format!("failed to read {relative_path}"))
+    }
+
+    /// Returns this path but reading from where `destination` wrote it, if it did.
+    fn read_from(&self, destination: &WriteDestination) -> RelativePath {
+        RelativePath {
+            full_path: destination.read_path(&self.full_path),
+            relative_to_manifest: self.relative_to_manifest.clone(),
+        }
+    }
+
+    /// Returns the path that was written to.
+    fn write(&self, contents: &str, destination: &WriteDestination) -> Result<PathBuf> {
+        let _span = error_span!("", path = %self.full_path.display()).entered();
+        let relative_path = self.relative_to_manifest.display();
+        let path = destination.path(&self.full_path)?;
+        atomic_write::write(&path, contents.as_bytes())
+            .with_context(|| format!("failed to write {relative_path}"))?;
+        Ok(path)
+    }
+
+    /// Like [`read_to_string`](Self::read_to_string) but decodes the file with `encoding`.
+    fn read_to_string_with_encoding(&self, encoding: &'static Encoding) -> Result<String> {
+        if encoding == encoding_rs::UTF_8 {
+            return self.read_to_string();
+        }
+
+        let _span = error_span!("", path = %self.full_path.display()).entered();
+        let relative_path = self.relative_to_manifest.display();
+        let bytes =
+            fs::read(&self.full_path).with_context(|| format!("failed to read {relative_path}"))?;
+
+        let (string, had_errors) = encoding.decode_without_bom_handling(&bytes);
+
+        if had_errors {
+            bail!("{relative_path} is not valid {}", encoding.name());
+        }
+
+        Ok(string.into_owned())
+    }
+
+    /// Like [`write`](Self::write) but encodes the contents with `encoding`.
+    ///
+    /// Errors if `contents` has characters that `encoding` can't represent.
+    fn write_with_encoding(
+        &self,
+        contents: &str,
+        encoding: &'static Encoding,
+        destination: &WriteDestination,
+    ) -> Result<PathBuf> {
+        if encoding == encoding_rs::UTF_8 {
+            return self.write(contents, destination);
+        }
+
+        let _span = error_span!("", path = %self.full_path.display()).entered();
+        let relative_path = self.relative_to_manifest.display();
+        let (bytes, output_encoding, had_unmappable) = encoding.encode(contents);
+
+        if had_unmappable || output_encoding != encoding {
+            bail!(
+                "{relative_path} would contain characters that can't be encoded as {}",
+                encoding.name()
+            );
+        }
+
+        let path = destination.path(&self.full_path)?;
+        atomic_write::write(&path, &bytes)
+            .with_context(|| format!("failed to write {relative_path}"))?;
+        Ok(path)
+    }
+}
+
+/// The sections written or checked in this run, so no section is processed by two packages.
+///
+/// Keyed by the canonical file path and the section name, since several packages may insert
+/// into distinct sections of the same file.
+#[derive(Default)]
+struct WriteLock {
+    sections: Mutex<HashSet<(PathBuf, String)>>,
+    /// With `--dry-run`, the canonical paths of the files and where they were written to instead.
+    ///
+    /// Later reads of these files read the mirror, so that the sections of one file
+    /// written by several packages all end up in the same mirrored file.
+    mirrored: Mutex<Vec<(PathBuf, PathBuf)>>,
+}
+
+impl WriteLock {
+    /// Claims the section `section_name` of `path` or errors if it was already claimed.
+    fn claim(&self, path: &Path, section_name: &str) -> Result<()> {
+        let _span = error_span!("", path = %path.display()).entered();
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        if !self.sections.lck().insert((path, section_name.to_string())) {
+            bail!(
+                "section \"{section_name}\" was already processed by another package in this run\n  \
+                hint: give each package distinct section names with `crate-section-name` and \
+                `feature-section-name`"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn task(cx: &PackageContext, from: &str, to: &str, f: impl FnOnce(&PackageContext) -> Result<()>) {
+    let task_name = match cx.write_mode() {
+        WriteMode::Write => format!("insert {from} into {to}"),
+        WriteMode::Check => format!("checking {from} in {to}"),
+        WriteMode::Diff => format!("diffing {from} in {to}"),
+    };
+
+    let _span = info_span!("", task = task_name).entered();
+
+    trace!("starting task");
+
+    let start = Instant::now();
+
+    if let Err(report) = f(cx) {
+        let context = match cx.write_mode() {
+            WriteMode::Write => format!("could not {task_name}"),
+            WriteMode::Check => format!("checking {from} failed"),
+            WriteMode::Diff => format!("diffing {from} failed"),
+        };
+
+        let report = report.wrap_err(context);
+
+        if report.severity() == Level::ERROR {
+            cx.summary.lck().push_error(&report);
+        }
+
+        cx.log.print_report(&report);
+    }
+
+    trace!("finished in {:?}", start.elapsed());
+}
+
+/// Runs `f` for each readme, an error for one readme doesn't stop the others.
+fn readme_task(cx: &PackageContext, from: &str, f: fn(&PackageContext, &Readme) -> Result<()>) {
+    for readme in &cx.readmes {
+        let _span = (cx.readmes.len() > 1).then(|| {
+            info_span!("", readme = %readme.path.relative_to_manifest.display()).entered()
+        });
+
+        task(cx, from, "readme", |cx| {
+            cx.write_lock.claim(&readme.path.full_path, &readme.section_name)?;
+            f(cx, readme)
+        });
+    }
+}
+
+/// Runs `f` for the feature section of the crate documentation.
+fn crate_docs_task(cx: &PackageContext, from: &str, f: fn(&PackageContext) -> Result<()>) {
+    task(cx, from, "crate documentation", |cx| {
+        cx.write_lock.claim(cx.target.src_path.as_std_path(), &cx.cfg.feature_section_name)?;
+        f(cx)
+    });
+}
+
+fn insert_features_into_docs(cx: &PackageContext) -> Result<()> {
+    let target_path = cx.target.src_path.as_std_path();
+    let target_src = read_to_string(&cx.write_destination.read_path(target_path))?;
+    let feature_docs_section = find_feature_docs_section(cx, target_path, &target_src)?;
+    let feature_docs = feature_docs(cx, feature_docs_section.heading_level())?;
+
+    if cx.write_mode() == WriteMode::Check {
+        if is_unpopulated(feature_docs_section.content(), &feature_docs)
+            && !cx.cfg.allow_missing_section
+        {
+            warn!("feature documentation section is empty; run without --check to populate it");
+        }
+
+        if !feature_docs_eq(feature_docs_section.content(), &feature_docs) {
+            cx.summary.lck().stale = true;
+
+            if cx.cli.cfg.show_diff {
+                let replaced = feature_docs_section.replace(&feature_docs)?;
+                show_diff(cx, replaced.path, replaced.old, &replaced.new)?;
+            }
+
+            bail!("feature documentation is stale");
+        }
+
+        return Ok(());
+    }
+
+    let replaced = feature_docs_section.replace(&feature_docs)?;
+
+    if replaced.new != replaced.old {
+        match cx.write_mode() {
+            WriteMode::Diff => print_diff(cx, replaced.path, replaced.old, &replaced.new)?,
+            _ => {
+                let written = write(replaced.path, replaced.new.as_bytes(), &cx.write_destination)?;
+                cx.summary.lck().files_written.push(written);
+                show_diff(cx, replaced.path, replaced.old, &replaced.new)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the feature documentation section, a missing section is an error unless `allow-missing-section` is set.
+fn find_feature_docs_section<'a>(
+    cx: &PackageContext,
+    target_path: &'a Path,
+    target_src: &'a str,
+) -> Result<edit_crate_docs::FeatureDocsSection<'a>> {
+    let not_found_level = if cx.cfg.allow_missing_section { Level::WARN } else { Level::ERROR };
+
+    let Some(feature_docs_section) = edit_crate_docs::FeatureDocsSection::find(
+        target_src,
+        target_path,
+        &cx.cfg.feature_section_name,
+        &cx.section_delimiters,
+    )?
+    else {
+        let target_name = target_path
+            .file_name()
+            .map(|n| Path::new(n).display().to_string())
+            .unwrap_or_else(|| "crate docs".into());
+
+        let _span = info_span!("",
+            path = %target_path.display(),
+            section_name = cx.cfg.feature_section_name,
+        )
+        .entered();
+
+        let section_name = &cx.cfg.feature_section_name;
+        let start = cx.section_delimiters.start(section_name);
+        let end = cx.section_delimiters.end(section_name);
+        let example = format!("//! {start}\n//! {end}");
+
+        return Err(section_not_found(&target_name, &example)).with_severity(not_found_level);
+    };
+
+    Ok(feature_docs_section)
+}
+
+/// The error for a missing section, with a hint on how to add it.
+fn section_not_found(path: &str, example: &str) -> Report {
+    let example = example.lines().map(|line| format!("    {line}")).collect::<Vec<_>>().join("\n");
+
+    eyre!(
+        "section not found\n  \
+        hint: add the following to {path} or run `cargo insert-docs init`:\n\
+        \n\
+        {example}"
+    )
+}
+
+fn feature_docs(cx: &PackageContext, heading_level: u8) -> Result<String> {
+    let cargo_toml = cx.manifest_path.get().read_to_string()?;
+
+    let features_file = match &cx.cfg.features_file {
+        Some(path) => Some(cx.manifest_path.relative(path).read_to_string()?),
+        None => None,
+    };
+
+    extract_feature_docs::extract(
+        &cargo_toml,
+        Some(cx.workspace_toml),
+        &extract_feature_docs::ExtractFeatureDocsOptions {
+            feature_label: &cx.cfg.feature_label,
+            default_marker: &cx.cfg.default_marker,
+            heading_level,
+            sort_features: cx.cfg.sort_features,
+            feature_format: cx.cfg.feature_format,
+            feature_comment_prefix: &cx.cfg.feature_comment_prefix,
+            in_between_comment_prefix: &cx.cfg.in_between_comment_prefix,
+            include_optional_deps: cx.cfg.include_optional_deps,
+            show_feature_deps: cx.cfg.show_feature_deps,
+            show_feature_rdeps: cx.cfg.show_feature_rdeps,
+            hidden_features: &cx.cfg.hidden_features,
+            features_file: features_file.as_deref(),
+        },
+    )
+    .wrap_err("failed to parse Cargo.toml")
+}
+
+/// Whether the feature documentation section is empty although there are features to document.
+fn is_unpopulated(section: &str, feature_docs: &str) -> bool {
+    section.trim().is_empty() && !feature_docs.trim().is_empty()
+}
+
+/// Compares feature documentation ignoring surrounding whitespace and trailing whitespace of lines.
+fn feature_docs_eq(a: &str, b: &str) -> bool {
+    a.trim().lines().map(str::trim_end).eq(b.trim().lines().map(str::trim_end))
+}
+
+fn insert_docs_into_readme(cx: &PackageContext, target: &Readme) -> Result<()> {
+    let not_found_level = if cx.cfg.allow_missing_section { Level::WARN } else { Level::ERROR };
+
+    let readme_path = &target.path;
+    let readme = readme_path
+        .read_from(&cx.write_destination)
+        .read_to_string_with_encoding(cx.section_encoding)
+        .with_severity(not_found_level)?;
+
+    let section_name = &target.section_name;
+    let delimiters = &cx.section_delimiters;
+    let subsections = markdown::find_subsections(&readme, section_name, delimiters)?;
+
+    let line_ending = line_ending::detect_line_ending(&readme);
+
+    let new_readme = if !subsections.is_empty() {
+        let crate_docs = extract_crate_docs::extract(cx)?;
+        let crate_docs = line_ending.apply(&crate_docs);
+        let [without_definitions, definitions] = markdown::extract_definitions(&crate_docs);
+
+        let mut new_readme = StringReplacer::new(&readme);
+        let last_subsection_i = subsections.len().saturating_sub(1);
+
+        for (i, (section, name)) in subsections.into_iter().enumerate() {
+            let replace_with_section = markdown::find_section(&without_definitions, &format!("{section_name} {name}"), delimiters)?.ok_or_else(|| eyre!("\"{section_name}\" subsection \"{name}\" is contained in readme but missing from crate docs"))?;
+
+            if i == last_subsection_i {
+                let replace_with = &without_definitions[replace_with_section.content_span];
+                new_readme.insert(
+                    section.span.start,
+                    delimiters.start(&format!("{section_name} {name}")),
+                );
+                new_readme.replace(section.span.clone(), replace_with);
+                new_readme.insert(section.span.end, line_ending.as_str());
+                new_readme.insert(section.span.end, &definitions);
+                new_readme
+                    .insert(section.span.end, delimiters.end(&format!("{section_name} {name}")));
+            } else {
+                let replace_with = &without_definitions[replace_with_section.span];
+                new_readme.replace(section.span.clone(), replace_with);
+            }
+        }
+
+        new_readme.finish()
+    } else {
+        let section = find_readme_section(cx, target, &readme)?;
+        let crate_docs = extract_crate_docs::extract(cx)?;
+        let crate_docs = line_ending.apply(&crate_docs);
+        markdown::replace_section_content(&readme, &section, &crate_docs, line_ending.as_str())
+    };
+
+    if readme != new_readme {
+        match cx.write_mode() {
+            WriteMode::Write => {
+                let written = readme_path.write_with_encoding(
+                    &new_readme,
+                    cx.section_encoding,
+                    &cx.write_destination,
+                )?;
+                cx.summary.lck().files_written.push(written);
+                show_diff(cx, &readme_path.full_path, &readme, &new_readme)?;
+            }
+            WriteMode::Check => {
+                cx.summary.lck().stale = true;
+                show_diff(cx, &readme_path.full_path, &readme, &new_readme)?;
+                bail!("crate documentation is stale");
+            }
+            WriteMode::Diff => print_diff(cx, &readme_path.full_path, &readme, &new_readme)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the crate documentation section, a missing section is an error unless `allow-missing-section` is set.
+///
+/// Without section markers the content of the `section-heading` heading is the section.
+fn find_readme_section(
+    cx: &PackageContext,
+    target: &Readme,
+    readme: &str,
+) -> Result<markdown::Section> {
+    let not_found_level = if cx.cfg.allow_missing_section { Level::WARN } else { Level::ERROR };
+    let readme_path = &target.path;
+
+    let section = markdown::find_section(readme, &target.section_name, &cx.section_delimiters)?;
+    let section = section.or_else(|| {
+        let heading = cx.cfg.section_heading.as_deref()?;
+        markdown::find_heading_section(readme, heading)
+    });
+
+    let Some(section) = section else {
+        let relative_path = readme_path.relative_to_manifest.display();
+
+        let _span = info_span!("",
+            path = %readme_path.full_path.display(),
+            section_name = target.section_name,
+        )
+        .entered();
+
+        let example = match &cx.cfg.section_heading {
+            Some(heading) => format!("# {heading}"),
+            None => {
+                let start = cx.section_delimiters.start(&target.section_name);
+                let end = cx.section_delimiters.end(&target.section_name);
+                format!("{start}\n{end}")
+            }
+        };
+
+        return Err(section_not_found(&relative_path.to_string(), &example))
+            .with_severity(not_found_level);
+    };
+
+    Ok(section)
+}
+
+/// Inserts the crate documentation of all packages into the workspace readme,
+/// each under a `## package-name` heading.
+fn insert_workspace_docs(
+    cxs: &[PackageContext],
+    readme_path: &Path,
+    section_name: &str,
+    delimiters: &markdown::SectionDelimiters,
+    summary: &mut PackageSummary,
+) -> Result<()> {
+    let Some(first) = cxs.first() else {
+        return Ok(());
+    };
+
+    let readme_path = RelativePath {
+        full_path: first.metadata.workspace_root.as_std_path().join(readme_path),
+        relative_to_manifest: readme_path.to_path_buf(),
+    };
+
+    let readme = readme_path
+        .read_from(&first.write_destination)
+        .read_to_string_with_encoding(first.section_encoding)?;
+
+    let Some(section) = markdown::find_section(&readme, section_name, delimiters)? else {
+        let relative_path = readme_path.relative_to_manifest.display();
+
+        let _span = info_span!("",
+            path = %readme_path.full_path.display(),
+            section_name,
+        )
+        .entered();
+
+        bail!("section not found in {relative_path}");
+    };
+
+    let mut workspace_docs = String::new();
+
+    for cx in cxs.iter().filter(|cx| cx.cfg.crate_into_readme) {
+        let _span = error_span!("", package = cx.package.name.as_str()).entered();
+        let crate_docs = extract_crate_docs::extract(cx)?;
+        // the crate docs go below the `## package-name` heading
+        let crate_docs = markdown::shrink_headings_below(crate_docs.trim(), 2);
+        write!(workspace_docs, "\n## {}\n\n{}\n", cx.package.name, crate_docs)?;
+    }
+
+    let line_ending = line_ending::detect_line_ending(&readme);
+    let mut new_readme = StringReplacer::new(&readme);
+    new_readme.replace(section.content_span, line_ending.apply(&workspace_docs));
+    let new_readme = new_readme.finish();
+
+    if readme != new_readme {
+        match first.write_mode() {
+            WriteMode::Write => {
+                let written = readme_path.write_with_encoding(
+                    &new_readme,
+                    first.section_encoding,
+                    &first.write_destination,
+                )?;
+                summary.files_written.push(written);
+                show_diff(first, &readme_path.full_path, &readme, &new_readme)?;
+            }
+            WriteMode::Check => {
+                summary.stale = true;
+                show_diff(first, &readme_path.full_path, &readme, &new_readme)?;
+                bail!("workspace documentation is stale");
+            }
+            WriteMode::Diff => print_diff(first, &readme_path.full_path, &readme, &new_readme)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn expand_readme_template(cx: &PackageContext, target: &Readme) -> Result<()> {
+    let template_path = cx.readme_template_path.as_ref().expect("readme template path is set");
+    let template = template_path.read_to_string_with_encoding(cx.section_encoding)?;
+
+    let new_readme = readme_template::expand(&template, |name| match name {
+        readme_template::CRATE_DOCS => extract_crate_docs::extract(cx),
+        readme_template::FEATURE_DOCS => feature_docs(cx, 0),
+        _ => unreachable!("`expand` only calls us with known placeholders"),
+    })?;
+
+    let readme_path = &target.path;
+
+    // the readme might not exist yet
+    let readme = readme_path
+        .read_from(&cx.write_destination)
+        .read_to_string_with_encoding(cx.section_encoding)
+        .ok();
+
+    if readme.as_deref() != Some(new_readme.as_str()) {
+        let readme = readme.as_deref().unwrap_or("");
+
+        match cx.write_mode() {
+            WriteMode::Write => {
+                let written = readme_path.write_with_encoding(
+                    &new_readme,
+                    cx.section_encoding,
+                    &cx.write_destination,
+                )?;
+                cx.summary.lck().files_written.push(written);
+                show_diff(cx, &readme_path.full_path, readme, &new_readme)?;
+            }
+            WriteMode::Check => {
+                cx.summary.lck().stale = true;
+                show_diff(cx, &readme_path.full_path, readme, &new_readme)?;
+                bail!("readme is stale");
+            }
+            WriteMode::Diff => print_diff(cx, &readme_path.full_path, readme, &new_readme)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the changes to the file at `path` to stdout.
+fn print_diff(cx: &PackageContext, path: &Path, old: &str, new: &str) -> Result<()> {
+    let path = path.strip_prefix(&cx.metadata.workspace_root).unwrap_or(path);
+    let diff = diff::unified(&path.display().to_string(), old, new);
+
+    cx.log.foreign_write_incoming();
+    let mut stdout = anstream::AutoStream::new(io::stdout(), cx.cli.cfg.color);
+    io::Write::write_all(&mut stdout, diff.as_bytes())?;
+    Ok(())
+}
+
+/// Prints the changes to the file at `path` to stderr if `--show-diff` is set.
+fn show_diff(cx: &PackageContext, path: &Path, old: &str, new: &str) -> Result<()> {
+    if !cx.cli.cfg.show_diff {
+        return Ok(());
+    }
+
+    let path = path.strip_prefix(&cx.metadata.workspace_root).unwrap_or(path);
+    let diff = diff::unified(&path.display().to_string(), old, new);
+
+    cx.log.foreign_write_incoming();
+    let mut stderr = anstream::AutoStream::new(io::stderr(), cx.cli.cfg.color);
+    io::Write::write_all(&mut stderr, diff.as_bytes())?;
+    Ok(())
+}
+
+fn read_to_string(path: &Path) -> Result<String> {
+    let _span = error_span!("", path = %path.display()).entered();
+
+    let file_name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| path.display().to_string());
+
+    fs::read_to_string(path).with_context(|| format!("failed to read {file_name}"))
+}
+
+/// Returns the path that was written to.
+fn write(path: &Path, content: &[u8], destination: &WriteDestination) -> Result<PathBuf> {
+    let _span = error_span!("", path = %path.display()).entered();
+
+    let file_name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| path.display().to_string());
+
+    let path = destination.path(path)?;
+    atomic_write::write(&path, content)
+        .with_context(|| format!("failed to write to {file_name}"))?;
+    Ok(path)
+}
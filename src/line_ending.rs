@@ -0,0 +1,40 @@
+//! Keeps the line endings of a file when we insert generated content into it.
+//!
+//! The content we generate is always `\n`-terminated. If the file we write to mostly uses `\r\n`
+//! we convert the content to match, otherwise git would see the whole section as modified.
+
+#[cfg(test)]
+mod tests;
+
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+
+    /// Converts the `\n` line endings of `s` to this line ending.
+    pub fn apply(self, s: &str) -> Cow<'_, str> {
+        match self {
+            LineEnding::Lf => Cow::Borrowed(s),
+            LineEnding::Crlf => Cow::Owned(s.replace("\r\n", "\n").replace('\n', "\r\n")),
+        }
+    }
+}
+
+/// Returns [`LineEnding::Crlf`] if more than half of the newlines in `s` are `\r\n`.
+pub fn detect_line_ending(s: &str) -> LineEnding {
+    let newlines = s.matches('\n').count();
+    let crlfs = s.matches("\r\n").count();
+
+    if crlfs * 2 > newlines { LineEnding::Crlf } else { LineEnding::Lf }
+}
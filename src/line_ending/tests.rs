@@ -0,0 +1,19 @@
+use super::{LineEnding, detect_line_ending};
+
+#[test]
+fn test_detect() {
+    assert_eq!(detect_line_ending(""), LineEnding::Lf);
+    assert_eq!(detect_line_ending("no newline"), LineEnding::Lf);
+    assert_eq!(detect_line_ending("a\nb\n"), LineEnding::Lf);
+    assert_eq!(detect_line_ending("a\r\nb\r\n"), LineEnding::Crlf);
+    assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), LineEnding::Crlf);
+    assert_eq!(detect_line_ending("a\r\nb\nc\n"), LineEnding::Lf);
+    assert_eq!(detect_line_ending("a\r\nb\n"), LineEnding::Lf);
+}
+
+#[test]
+fn test_apply() {
+    assert_eq!(LineEnding::Lf.apply("a\nb\r\n"), "a\nb\r\n");
+    assert_eq!(LineEnding::Crlf.apply("a\nb\n"), "a\r\nb\r\n");
+    assert_eq!(LineEnding::Crlf.apply("a\r\nb\n"), "a\r\nb\r\n");
+}
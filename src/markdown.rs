@@ -10,7 +10,10 @@ use percent_encoding::percent_encode_byte;
 
 use crate::{markdown_rs::event::Name, string_replacer::StringReplacer};
 
-pub use section::{find_section, find_subsections};
+pub use section::{
+    DEFAULT_SECTION_END_TEMPLATE, DEFAULT_SECTION_START_TEMPLATE, Section, SectionDelimiters,
+    find_heading_section, find_section, find_subsections,
+};
 pub use tree::Tree;
 
 pub fn extract_definitions(markdown: &str) -> [String; 2] {
@@ -34,6 +37,129 @@ pub fn extract_definitions(markdown: &str) -> [String; 2] {
     [without_definitions, definitions]
 }
 
+/// Returns the level of the last heading.
+pub fn last_heading_level(markdown: &str) -> Option<u8> {
+    let tree = Tree::new(markdown);
+    let mut level = None;
+
+    for node in tree.depth_first() {
+        match node.name() {
+            Name::HeadingAtx => {
+                if let Some(hashes) = node.child(Name::HeadingAtxSequence) {
+                    level = Some(hashes.byte_range().len() as u8);
+                }
+            }
+            Name::HeadingSetext => {
+                if let Some(underline) = node.child(Name::HeadingSetextUnderline) {
+                    level = Some(if underline.str().trim_start().starts_with('=') { 1 } else { 2 });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    level
+}
+
+/// Returns the destinations of the links, images, link reference definitions and autolinks.
+pub fn link_destinations(markdown: &str) -> Vec<&str> {
+    let tree = Tree::new(markdown);
+
+    tree.depth_first()
+        .filter(|node| {
+            matches!(
+                node.name(),
+                Name::ResourceDestinationString
+                    | Name::DefinitionDestinationString
+                    | Name::AutolinkProtocol
+            )
+        })
+        .map(|node| node.str())
+        .collect()
+}
+
+/// Shrinks the headings so that none is of `level` or higher, keeping their relative levels.
+///
+/// The highest heading becomes a heading of `level + 1`, headings can't shrink past level 6.
+/// Setext headings are turned into ATX headings.
+pub fn shrink_headings_below(markdown: &str, level: u8) -> String {
+    let tree = Tree::new(markdown);
+
+    let headings = tree
+        .depth_first()
+        .filter_map(|node| {
+            let heading_level = match node.name() {
+                Name::HeadingAtx => node.child(Name::HeadingAtxSequence)?.byte_range().len() as u8,
+                Name::HeadingSetext => {
+                    let underline = node.child(Name::HeadingSetextUnderline)?;
+                    if underline.str().trim_start().starts_with('=') { 1 } else { 2 }
+                }
+                _ => return None,
+            };
+
+            Some((node, heading_level))
+        })
+        .collect::<Vec<_>>();
+
+    let Some(highest) = headings.iter().map(|(_, level)| *level).min() else {
+        return markdown.to_string();
+    };
+
+    if highest > level {
+        return markdown.to_string();
+    }
+
+    let shift = level + 1 - highest;
+    let mut out = StringReplacer::new(markdown);
+
+    for (node, heading_level) in headings {
+        let hashes = "#".repeat((heading_level + shift).min(6) as usize);
+
+        if node.name() == Name::HeadingAtx {
+            if let Some(sequence) = node.child(Name::HeadingAtxSequence) {
+                out.replace(sequence.byte_range(), hashes);
+            }
+        } else if let Some(text) = node.child(Name::HeadingSetextText) {
+            let text = text.str().lines().map(str::trim).collect::<Vec<_>>().join(" ");
+            out.replace(node.byte_range(), format!("{hashes} {text}"));
+        }
+    }
+
+    out.finish()
+}
+
+/// Replaces the content of `section` with `content` surrounded by newlines.
+///
+/// If the section is the content of a heading the headings of `content` are shrunk
+/// below it, so the section still ends at the next heading when it is replaced again.
+pub fn replace_section_content(
+    markdown: &str,
+    section: &Section,
+    content: &str,
+    newline: &str,
+) -> String {
+    let content = match section.heading_level {
+        Some(level) => Cow::Owned(shrink_headings_below(content, level)),
+        None => Cow::Borrowed(content),
+    };
+
+    let mut out = StringReplacer::new(markdown);
+    out.replace(section.content_span.clone(), format!("{newline}{content}{newline}"));
+    out.finish()
+}
+
+/// Returns the index after the line that ends the first paragraph.
+pub fn end_of_first_paragraph(markdown: &str) -> Option<usize> {
+    let tree = Tree::new(markdown);
+    let paragraph = tree.depth_first().find(|node| node.name() == Name::Paragraph)?;
+    Some(end_of_line(markdown, paragraph.byte_range().end))
+}
+
+/// Returns the 1-based line number of the byte at `index`.
+pub fn line_number(markdown: &str, index: usize) -> usize {
+    markdown[..index].bytes().filter(|&b| b == b'\n').count() + 1
+}
+
 pub fn end_of_line(markdown: &str, index: usize) -> usize {
     match markdown[index..].bytes().position(|b| b == b'\n') {
         Some(i) => index + i + 1,
@@ -7,6 +7,7 @@ use core::{
     marker::PhantomData,
     str::{CharIndices, FromStr},
 };
+use std::{collections::HashSet, sync::LazyLock};
 
 pub fn is_rust(lang: &str) -> Result<bool, Vec<String>> {
     let extra = ExtraInfo::new();
@@ -15,6 +16,115 @@ pub fn is_rust(lang: &str) -> Result<bool, Vec<String>> {
     if errors.is_empty() { Ok(parsed.rust) } else { Err(errors) }
 }
 
+/// Language tokens that mark a code block as rust, matched case-insensitively.
+///
+/// Rustdoc itself only knows `rust`, but some documentation generators emit `rs`.
+pub const RUST_LANG_ALIASES: &[&str] = &["rust", "rs"];
+
+/// Languages that are common enough in docs that they are not a misspelled `rust`.
+static KNOWN_NON_RUST_LANGS: LazyLock<HashSet<&str>> = LazyLock::new(|| {
+    HashSet::from_iter([
+        "text",
+        "txt",
+        "plain",
+        "plaintext",
+        "console",
+        "shell",
+        "sh",
+        "bash",
+        "zsh",
+        "fish",
+        "powershell",
+        "ps1",
+        "bat",
+        "cmd",
+        "python",
+        "py",
+        "javascript",
+        "js",
+        "typescript",
+        "ts",
+        "jsx",
+        "tsx",
+        "json",
+        "jsonc",
+        "json5",
+        "toml",
+        "yaml",
+        "yml",
+        "xml",
+        "html",
+        "css",
+        "scss",
+        "markdown",
+        "md",
+        "c",
+        "cpp",
+        "c++",
+        "h",
+        "hpp",
+        "csharp",
+        "cs",
+        "java",
+        "kotlin",
+        "go",
+        "ruby",
+        "rb",
+        "php",
+        "swift",
+        "zig",
+        "haskell",
+        "ocaml",
+        "sql",
+        "diff",
+        "patch",
+        "ini",
+        "dockerfile",
+        "makefile",
+        "make",
+        "cmake",
+        "nix",
+        "lua",
+        "wasm",
+        "wat",
+        "asm",
+        "llvm",
+        "mermaid",
+        "dot",
+        "graphql",
+        "proto",
+        "protobuf",
+        "regex",
+        "latex",
+        "tex",
+        "math",
+        "csv",
+        "ron",
+        "ebnf",
+        "bnf",
+        "svg",
+        "glsl",
+        "wgsl",
+        "hlsl",
+    ])
+});
+
+/// Returns the language of a non-rust code block if it is not in a list of common languages.
+///
+/// Such a language is likely a misspelled `rust`, like `ruxt`.
+pub fn unknown_lang(lang: &str) -> Option<String> {
+    let parsed = LangString::parse(lang, None);
+
+    if parsed.rust {
+        return None;
+    }
+
+    parsed
+        .unknown
+        .into_iter()
+        .find(|lang| !KNOWN_NON_RUST_LANGS.contains(lang.to_ascii_lowercase().as_str()))
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 struct Edition;
 
@@ -418,7 +528,9 @@ impl LangString {
                         ignores.push(x.strip_prefix("ignore-").unwrap().to_owned());
                         seen_rust_tags = !seen_other_tags;
                     }
-                    LangStringToken::LangToken("rust") => {
+                    LangStringToken::LangToken(x)
+                        if RUST_LANG_ALIASES.iter().any(|alias| x.eq_ignore_ascii_case(alias)) =>
+                    {
                         data.rust = true;
                         seen_rust_tags = true;
                     }
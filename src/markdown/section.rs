@@ -1,8 +1,94 @@
-use std::ops::Range;
+use std::{collections::HashMap, ops::Range};
 
 use color_eyre::eyre::{self, bail};
 
-use crate::{markdown::Tree, markdown_rs::event::Name};
+use crate::{
+    markdown::{Tree, line_number},
+    markdown_rs::event::Name,
+};
+
+pub const DEFAULT_SECTION_START_TEMPLATE: &str = "<!-- {name} start -->";
+pub const DEFAULT_SECTION_END_TEMPLATE: &str = "<!-- {name} end -->";
+
+/// The markers that start and end a section, like `<!-- {name} start -->` and `<!-- {name} end -->`.
+///
+/// Markers that are html comments are only searched for in html.
+/// Other markers like `[//]: # (begin {name})` must make up a whole line outside of code blocks.
+#[derive(Debug, Clone)]
+pub struct SectionDelimiters {
+    start: String,
+    end: String,
+}
+
+impl Default for SectionDelimiters {
+    fn default() -> Self {
+        Self {
+            start: DEFAULT_SECTION_START_TEMPLATE.to_string(),
+            end: DEFAULT_SECTION_END_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl SectionDelimiters {
+    pub fn new(start: &str, end: &str) -> eyre::Result<Self> {
+        for template in [start, end] {
+            if !template.contains("{name}") {
+                bail!("section delimiter template \"{template}\" does not contain `{{name}}`");
+            }
+        }
+
+        if start == end {
+            bail!("section start and end delimiter templates must be different");
+        }
+
+        Ok(Self { start: start.to_string(), end: end.to_string() })
+    }
+
+    /// Returns the marker that starts the section `name`.
+    pub fn start(&self, name: &str) -> String {
+        self.start.replace("{name}", name)
+    }
+
+    /// Returns the marker that ends the section `name`.
+    pub fn end(&self, name: &str) -> String {
+        self.end.replace("{name}", name)
+    }
+
+    fn are_html_comments(&self) -> bool {
+        [&self.start, &self.end].iter().all(|t| t.starts_with("<!--") && t.ends_with("-->"))
+    }
+
+    /// Returns the kind and name of a marker.
+    ///
+    /// If both templates match, the one with the shorter name wins, so with the templates
+    /// `<!-- {name} -->` and `<!-- {name} end -->`, `<!-- foo end -->` ends `foo`.
+    fn parse<'a>(&self, marker: &'a str) -> Option<(SectionTagKind, &'a str)> {
+        let start = match_template(&self.start, marker).map(|name| (SectionTagKind::Start, name));
+        let end = match_template(&self.end, marker).map(|name| (SectionTagKind::End, name));
+
+        match (start, end) {
+            (Some(start), Some(end)) => Some(if end.1.len() < start.1.len() { end } else { start }),
+            (start, end) => start.or(end),
+        }
+    }
+}
+
+/// Returns what `{name}` stands for if `str` matches `template`.
+fn match_template<'a>(template: &str, str: &'a str) -> Option<&'a str> {
+    const NAME: &str = "{name}";
+
+    let count = template.matches(NAME).count();
+    let name_len = str.len().checked_sub(template.len() - count * NAME.len())?;
+
+    if count == 0 || name_len % count != 0 {
+        return None;
+    }
+
+    let start = template.find(NAME)?;
+    let name = str.get(start..start + name_len / count)?;
+
+    (template.replace(NAME, name) == str).then_some(name)
+}
 
 /// Finds sections like these:
 /// ```md
@@ -10,45 +96,119 @@ use crate::{markdown::Tree, markdown_rs::event::Name};
 /// This is the section content.
 /// <!-- section_name end -->
 /// ```
-pub fn find_section(markdown: &str, section_name: &str) -> Option<Section> {
-    fn parts_eq(mut str: &str, parts: &[&str]) -> bool {
-        for &part in parts {
-            str = match str.strip_prefix(part) {
-                Some(rest) => rest,
-                None => return false,
+///
+/// If there are multiple sections, the first one wins.
+///
+/// Errors if the markers up to the first section are unbalanced, like an end marker
+/// without a preceding start marker. Returns `None` if there are no markers at all.
+pub fn find_section(
+    markdown: &str,
+    section_name: &str,
+    delimiters: &SectionDelimiters,
+) -> eyre::Result<Option<Section>> {
+    let mut start = None::<Range<usize>>;
+
+    for tag in section_tags(markdown, section_name, delimiters) {
+        if tag.subsection.is_some() {
+            continue;
+        }
+
+        let marker = &markdown[tag.range.clone()];
+        let line = line_number(markdown, tag.range.start);
+
+        match (tag.kind, &start) {
+            (SectionTagKind::Start, None) => start = Some(tag.range),
+            (SectionTagKind::End, Some(start)) => {
+                return Ok(Some(Section {
+                    span: start.start..tag.range.end,
+                    content_span: start.end..tag.range.start,
+                    heading_level: None,
+                }));
+            }
+            (SectionTagKind::Start, Some(_)) => {
+                let end = delimiters.end(section_name);
+                bail!("found `{marker}` at line {line} without a preceding `{end}`");
+            }
+            (SectionTagKind::End, None) => {
+                let start = delimiters.start(section_name);
+                bail!("found `{marker}` at line {line} without a preceding `{start}`");
             }
         }
+    }
 
-        str.is_empty()
+    if let Some(start) = start {
+        let marker = &markdown[start.clone()];
+        let line = line_number(markdown, start.start);
+        let end = delimiters.end(section_name);
+        bail!("found `{marker}` at line {line} without a following `{end}`");
     }
 
-    let is_start = |s| parts_eq(s, &["<!-- ", section_name, " start -->"]);
-    let is_end = |s| parts_eq(s, &["<!-- ", section_name, " end -->"]);
+    Ok(None)
+}
 
-    let mut start = None::<Range<usize>>;
+/// Finds a section that is the content of a heading, like this:
+/// ```md
+/// # Crate Documentation
+/// This is the section content.
+///
+/// # Next Heading
+/// ```
+///
+/// The section ends at the next heading of the same or a higher level, or at the end.
+/// The `span` starts at the heading and the `content_span` after it.
+/// Content replacing it should be shrunk below the `heading_level`, otherwise
+/// its headings would end the section, see [`replace_section_content`](super::replace_section_content).
+///
+/// If there are multiple headings with the text, the first one wins.
+/// Returns `None` if there is no such heading.
+pub fn find_heading_section(markdown: &str, heading: &str) -> Option<Section> {
+    let tree = Tree::new(markdown);
 
-    for comment in find_html_comments(markdown) {
-        let comment_str = &markdown[comment.clone()];
+    let headings = tree
+        .depth_first()
+        .filter_map(|node| {
+            let (level, text) = match node.name() {
+                Name::HeadingAtx => (
+                    node.child(Name::HeadingAtxSequence)?.byte_range().len(),
+                    node.child(Name::HeadingAtxText).map_or("", |text| text.str()),
+                ),
+                Name::HeadingSetext => {
+                    let underline = node.child(Name::HeadingSetextUnderline)?;
+                    let level = if underline.str().trim_start().starts_with('=') { 1 } else { 2 };
+                    (level, node.child(Name::HeadingSetextText)?.str())
+                }
+                _ => return None,
+            };
 
-        if let Some(start) = start.clone() {
-            if is_end(comment_str) {
-                return Some(Section {
-                    span: start.start..comment.end,
-                    content_span: start.end..comment.start,
-                });
-            }
-        } else if is_start(comment_str) {
-            start = Some(comment);
-        }
-    }
+            Some((level as u8, text.trim(), node.byte_range()))
+        })
+        .collect::<Vec<_>>();
+
+    let index = headings.iter().position(|(_, text, _)| *text == heading.trim())?;
+    let (level, _, range) = &headings[index];
+
+    let end = headings[index + 1..]
+        .iter()
+        .find(|(next_level, ..)| next_level <= level)
+        .map_or(markdown.len(), |(.., next)| start_of_line(markdown, next.start));
+
+    Some(Section {
+        span: range.start..end,
+        content_span: range.end..end,
+        heading_level: Some(*level),
+    })
+}
 
-    None
+fn start_of_line(markdown: &str, index: usize) -> usize {
+    markdown[..index].rfind('\n').map_or(0, |i| i + 1)
 }
 
 #[derive(Debug)]
 pub struct Section {
     pub span: Range<usize>,
     pub content_span: Range<usize>,
+    /// The level of the heading if the section is the content of a heading.
+    pub heading_level: Option<u8>,
 }
 
 /// Finds subsections like these:
@@ -64,62 +224,93 @@ pub struct Section {
 pub fn find_subsections<'a>(
     markdown: &'a str,
     section_name: &str,
+    delimiters: &SectionDelimiters,
 ) -> eyre::Result<Vec<(Section, &'a str)>> {
     let mut sections = vec![];
     let mut start = None::<(Range<usize>, &'a str)>;
+    let mut seen = HashMap::<&'a str, Range<usize>>::new();
+
+    let subsection_name = |name: &str| format!("{section_name} {name}");
+
+    for tag in section_tags(markdown, section_name, delimiters) {
+        let Some(name) = tag.subsection else {
+            continue;
+        };
+
+        let SectionTag { range, kind, .. } = tag;
+        let marker = &markdown[range.clone()];
+        let line = line_number(markdown, range.start);
 
-    for (range, kind, name) in find_subsection_tags(markdown, section_name) {
         if let Some((start_range, start_name)) = start {
             if name == start_name && kind == SectionTagKind::End {
-                sections.push((
-                    Section {
-                        span: start_range.start..range.end,
-                        content_span: start_range.end..range.start,
-                    },
-                    name,
-                ));
+                let span = start_range.start..range.end;
+
+                if let Some(first) = seen.insert(name, span.clone()) {
+                    bail!(
+                        "duplicate subsection name \"{name}\" in \"{section_name}\" (at bytes {first:?} and {span:?})"
+                    );
+                }
+
+                let content_span = start_range.end..range.start;
+                sections.push((Section { span, content_span, heading_level: None }, name));
                 start = None;
             } else {
-                bail!("subsections must be disjoint");
+                // subsections must be disjoint
+                let end = delimiters.end(&subsection_name(start_name));
+                bail!("found `{marker}` at line {line} without a preceding `{end}`");
             }
         } else {
             if kind == SectionTagKind::Start {
                 start = Some((range, name));
             } else {
-                bail!("subsection end without start");
+                let start = delimiters.start(&subsection_name(name));
+                bail!("found `{marker}` at line {line} without a preceding `{start}`");
             }
         }
     }
 
+    if let Some((range, name)) = start {
+        let marker = &markdown[range.clone()];
+        let line = line_number(markdown, range.start);
+        let end = delimiters.end(&subsection_name(name));
+        bail!("found `{marker}` at line {line} without a following `{end}`");
+    }
+
     Ok(sections)
 }
 
-fn find_subsection_tags<'a>(
+/// A marker that starts or ends a section or one of its subsections.
+struct SectionTag<'a> {
+    range: Range<usize>,
+    kind: SectionTagKind,
+    /// `None` for the section itself, the name for a subsection.
+    subsection: Option<&'a str>,
+}
+
+/// Classifies the markers of the markdown, yielding the tags of the section `section_name`.
+///
+/// This is the single scanning pass that [`find_section`] and [`find_subsections`] share
+/// before they match up the start and end tags.
+fn section_tags<'a>(
     markdown: &'a str,
     section_name: &str,
-) -> impl Iterator<Item = (Range<usize>, SectionTagKind, &'a str)> {
-    fn parse_name_and_kind(str: &str) -> Option<(&str, SectionTagKind)> {
-        if let Some(name) = str.strip_suffix(" start") {
-            return Some((name, SectionTagKind::Start));
-        }
+    delimiters: &SectionDelimiters,
+) -> impl Iterator<Item = SectionTag<'a>> {
+    let markers = if delimiters.are_html_comments() {
+        find_html_comments(markdown).collect::<Vec<_>>()
+    } else {
+        find_lines_outside_code(markdown)
+    };
 
-        if let Some(name) = str.strip_suffix(" end") {
-            return Some((name, SectionTagKind::End));
-        }
-
-        None
-    }
+    markers.into_iter().filter_map(move |range| {
+        let (kind, name) = delimiters.parse(&markdown[range.clone()])?;
 
-    find_html_comments(markdown).filter_map(move |comment| {
-        let name_and_kind = markdown[comment.clone()]
-            .strip_prefix("<!-- ")?
-            .strip_suffix(" -->")?
-            .strip_prefix(section_name)?
-            .strip_prefix(' ')?;
+        let subsection = match name.strip_prefix(section_name)? {
+            "" => None,
+            rest => Some(rest.strip_prefix(' ')?),
+        };
 
-        let (name, kind) = parse_name_and_kind(name_and_kind)?;
-
-        Some((comment, kind, name))
+        Some(SectionTag { range, kind, subsection })
     })
 }
 
@@ -130,7 +321,7 @@ enum SectionTagKind {
 }
 
 fn find_html_comments(markdown: &str) -> impl Iterator<Item = Range<usize>> {
-    find_html(markdown).flat_map(|html| {
+    find_html(markdown).into_iter().flat_map(|html| {
         comments(&markdown[html.clone()])
             .map(move |comment| comment.start + html.start..comment.end + html.start)
     })
@@ -149,16 +340,50 @@ fn comments(html: &str) -> impl Iterator<Item = Range<usize>> {
     })
 }
 
-fn find_html(markdown: &str) -> impl Iterator<Item = Range<usize>> {
+/// Returns the ranges of html that is not inside a code block or block quote,
+/// so markers shown as an example aren't mistaken for section markers.
+fn find_html(markdown: &str) -> Vec<Range<usize>> {
+    let tree = Tree::new(markdown);
+
+    let excluded = tree
+        .depth_first()
+        .filter(|node| {
+            matches!(node.name(), Name::CodeFenced | Name::CodeIndented | Name::BlockQuote)
+        })
+        .map(|node| node.byte_range())
+        .collect::<Vec<_>>();
+
+    tree.depth_first()
+        .filter(|node| matches!(node.name(), Name::HtmlFlow | Name::HtmlText))
+        .map(|node| node.byte_range())
+        .filter(|html| !excluded.iter().any(|range| range.contains(&html.start)))
+        .collect()
+}
+
+/// Returns the ranges of the non-empty lines without surrounding whitespace
+/// that are not part of a code block.
+fn find_lines_outside_code(markdown: &str) -> Vec<Range<usize>> {
     let tree = Tree::new(markdown);
 
-    tree.indices().filter_map(move |index| {
-        let node = tree.at(index)?;
+    let code_blocks = tree
+        .indices()
+        .filter_map(|index| tree.at(index))
+        .filter(|node| matches!(node.name(), Name::CodeFenced | Name::CodeIndented))
+        .map(|node| node.byte_range())
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![];
+    let mut line_start = 0;
+
+    for line in markdown.split_inclusive('\n') {
+        let start = line_start + (line.len() - line.trim_start().len());
+        let end = start + line.trim().len();
+        line_start += line.len();
 
-        if !matches!(node.name(), Name::HtmlFlow | Name::HtmlText) {
-            return None;
+        if start != end && !code_blocks.iter().any(|code| code.contains(&start)) {
+            lines.push(start..end);
         }
+    }
 
-        Some(node.byte_range())
-    })
+    lines
 }
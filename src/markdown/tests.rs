@@ -2,10 +2,14 @@ use expect_test::expect;
 
 use crate::markdown::format_link_destination;
 
-use super::{find_section, find_subsections};
+use super::{
+    SectionDelimiters, Tree, find_heading_section, find_section, find_subsections,
+    last_heading_level, replace_section_content, shrink_headings_below,
+};
 
 fn replace_section(markdown: &str, replacement: &str) -> String {
-    let section = find_section(markdown, "section").unwrap();
+    let section =
+        find_section(markdown, "section", &SectionDelimiters::default()).unwrap().unwrap();
     let mut out = markdown.to_string();
     out.replace_range(section.content_span, replacement);
     out
@@ -21,7 +25,8 @@ inside section
 after section
     "#;
 
-    let section = find_section(markdown, "my section").unwrap();
+    let section =
+        find_section(markdown, "my section", &SectionDelimiters::default()).unwrap().unwrap();
 
     expect![[r#"
         (
@@ -32,6 +37,203 @@ after section
     .assert_debug_eq(&(&markdown[section.span], &markdown[section.content_span]));
 }
 
+#[test]
+fn test_find_section_in_code_block() {
+    let markdown = r#"
+Add this to your readme:
+```md
+<!-- my section start -->
+<!-- my section end -->
+```
+
+    <!-- my section start -->
+
+> <!-- my section start -->
+> <!-- my section end -->
+
+<!-- my section start -->
+inside section
+<!-- my section end -->
+"#;
+
+    let section =
+        find_section(markdown, "my section", &SectionDelimiters::default()).unwrap().unwrap();
+
+    expect![[r#"
+        (
+            "<!-- my section start -->\ninside section\n<!-- my section end -->",
+            "\ninside section\n",
+        )
+    "#]]
+    .assert_debug_eq(&(&markdown[section.span], &markdown[section.content_span]));
+
+    let markdown = "```\n<!-- my section start -->\n```\n";
+    assert!(find_section(markdown, "my section", &SectionDelimiters::default()).unwrap().is_none());
+}
+
+#[test]
+fn test_find_heading_section() {
+    let markdown = r#"# My Crate
+
+## Crate Documentation
+old docs
+
+### Nested heading
+still in section
+
+## License
+MIT
+"#;
+
+    let section = find_heading_section(markdown, "Crate Documentation").unwrap();
+
+    expect![[r###"
+        (
+            "## Crate Documentation\nold docs\n\n### Nested heading\nstill in section\n\n",
+            "\nold docs\n\n### Nested heading\nstill in section\n\n",
+        )
+    "###]]
+    .assert_debug_eq(&(&markdown[section.span], &markdown[section.content_span]));
+
+    let markdown = "Crate Documentation\n===================\nold docs\n";
+    let section = find_heading_section(markdown, "Crate Documentation").unwrap();
+    assert_eq!(&markdown[section.content_span], "\nold docs\n");
+
+    assert!(find_heading_section("# Other\n", "Crate Documentation").is_none());
+}
+
+#[test]
+fn test_replace_heading_section_is_idempotent() {
+    let readme = "# My Crate\n\n## Crate Documentation\nold docs\n\n## License\nMIT\n";
+    let docs = "Some docs.\n\n# Examples\nAn example.\n\n## Details\nSome details.";
+
+    let replace = |readme: &str| {
+        let section = find_heading_section(readme, "Crate Documentation").unwrap();
+        replace_section_content(readme, &section, docs, "\n")
+    };
+
+    let once = replace(readme);
+    let twice = replace(&once);
+
+    expect![[r#"
+        # My Crate
+
+        ## Crate Documentation
+        Some docs.
+
+        ### Examples
+        An example.
+
+        #### Details
+        Some details.
+        ## License
+        MIT
+    "#]]
+    .assert_eq(&once);
+
+    assert_eq!(once, twice);
+}
+
+#[test]
+fn test_find_section_custom_delimiters() {
+    let markdown = r#"
+before section
+```md
+[//]: # (begin my section)
+in code block
+[//]: # (end my section)
+```
+[//]: # (begin my section)
+inside section
+[//]: # (end my section)
+after section
+"#;
+
+    let delimiters =
+        SectionDelimiters::new("[//]: # (begin {name})", "[//]: # (end {name})").unwrap();
+    let section = find_section(markdown, "my section", &delimiters).unwrap().unwrap();
+
+    expect![[r#"
+        (
+            "[//]: # (begin my section)\ninside section\n[//]: # (end my section)",
+            "\ninside section\n",
+        )
+    "#]]
+    .assert_debug_eq(&(&markdown[section.span], &markdown[section.content_span]));
+
+    let markdown = "<!-- begin:my section foo -->\nfoo\n<!-- end:my section foo -->\n";
+    let delimiters =
+        SectionDelimiters::new("<!-- begin:{name} -->", "<!-- end:{name} -->").unwrap();
+    let subsections = find_subsections(markdown, "my section", &delimiters).unwrap();
+
+    assert_eq!(subsections.len(), 1);
+    assert_eq!(subsections[0].1, "foo");
+    assert_eq!(&markdown[subsections[0].0.content_span.clone()], "\nfoo\n");
+    assert!(find_section(markdown, "my section", &SectionDelimiters::default()).unwrap().is_none());
+}
+
+#[test]
+fn test_section_delimiters_validation() {
+    let err = |start, end| SectionDelimiters::new(start, end).unwrap_err().to_string();
+
+    assert_eq!(
+        err("<!-- start -->", "<!-- {name} end -->"),
+        "section delimiter template \"<!-- start -->\" does not contain `{name}`"
+    );
+    assert_eq!(
+        err("<!-- {name} -->", "<!-- {name} -->"),
+        "section start and end delimiter templates must be different"
+    );
+}
+
+#[test]
+fn test_section_delimiters_markers() {
+    let delimiters = SectionDelimiters::new("<!-- {name} -->", "<!-- /{name} -->").unwrap();
+    let markdown = "<!-- my section -->\ncontent\n<!-- /my section -->\n";
+    let section = find_section(markdown, "my section", &delimiters).unwrap().unwrap();
+
+    assert_eq!(delimiters.start("my section"), "<!-- my section -->");
+    assert_eq!(delimiters.end("my section"), "<!-- /my section -->");
+    assert_eq!(&markdown[section.content_span], "\ncontent\n");
+}
+
+#[test]
+fn test_find_section_unbalanced() {
+    let err = |markdown| {
+        find_section(markdown, "my section", &SectionDelimiters::default()).unwrap_err().to_string()
+    };
+
+    assert_eq!(
+        err("before\n<!-- my section end -->\nafter\n"),
+        "found `<!-- my section end -->` at line 2 without a preceding `<!-- my section start -->`"
+    );
+    assert_eq!(
+        err("<!-- my section start -->\n<!-- my section start -->\n<!-- my section end -->\n"),
+        "found `<!-- my section start -->` at line 2 without a preceding `<!-- my section end -->`"
+    );
+    assert_eq!(
+        err("<!-- my section start -->\ncontent\n"),
+        "found `<!-- my section start -->` at line 1 without a following `<!-- my section end -->`"
+    );
+
+    let markdown = "<!-- other section end -->\n";
+    assert!(find_section(markdown, "my section", &SectionDelimiters::default()).unwrap().is_none());
+}
+
+#[test]
+fn test_find_section_reversed() {
+    let markdown = "before\n<!-- my section end -->\ncontent\n<!-- my section start -->\nafter\n";
+
+    expect![
+        "found `<!-- my section end -->` at line 2 without a preceding `<!-- my section start -->`"
+    ]
+    .assert_eq(
+        &find_section(markdown, "my section", &SectionDelimiters::default())
+            .unwrap_err()
+            .to_string(),
+    );
+}
+
 #[test]
 fn test_find_subsections() {
     let markdown = r#"
@@ -49,7 +251,7 @@ bar
 after sections
     "#;
 
-    let result = find_subsections(markdown, "my section")
+    let result = find_subsections(markdown, "my section", &SectionDelimiters::default())
         .unwrap()
         .into_iter()
         .map(|(range, name)| (name, &markdown[range.span], &markdown[range.content_span]))
@@ -86,7 +288,8 @@ fn test_find_subsections_multiple_in_flow() {
 </div>
 "#;
 
-    let foo = find_section(markdown, "my section foo").unwrap();
+    let foo =
+        find_section(markdown, "my section foo", &SectionDelimiters::default()).unwrap().unwrap();
     expect![[r#"
         (
             "foo",
@@ -96,7 +299,8 @@ fn test_find_subsections_multiple_in_flow() {
     "#]]
     .assert_debug_eq(&("foo", &markdown[foo.span], &markdown[foo.content_span]));
 
-    let bar = find_section(markdown, "my section bar").unwrap();
+    let bar =
+        find_section(markdown, "my section bar", &SectionDelimiters::default()).unwrap().unwrap();
     expect![[r#"
         (
             "bar",
@@ -106,7 +310,7 @@ fn test_find_subsections_multiple_in_flow() {
     "#]]
     .assert_debug_eq(&("bar", &markdown[bar.span], &markdown[bar.content_span]));
 
-    let result = find_subsections(markdown, "my section")
+    let result = find_subsections(markdown, "my section", &SectionDelimiters::default())
         .unwrap()
         .into_iter()
         .map(|(range, name)| (name, &markdown[range.span], &markdown[range.content_span]))
@@ -129,6 +333,47 @@ fn test_find_subsections_multiple_in_flow() {
     .assert_debug_eq(&result);
 }
 
+#[test]
+fn test_find_subsections_duplicate() {
+    let markdown = r#"
+<!-- my section foo start -->
+foo
+<!-- my section foo end -->
+<!-- my section foo start -->
+foo again
+<!-- my section foo end -->
+"#;
+
+    expect![[r#"duplicate subsection name "foo" in "my section" (at bytes 1..62 and 63..130)"#]]
+        .assert_eq(
+            &find_subsections(markdown, "my section", &SectionDelimiters::default())
+                .unwrap_err()
+                .to_string(),
+        );
+}
+
+#[test]
+fn test_find_subsections_unbalanced() {
+    let err = |markdown| {
+        find_subsections(markdown, "my section", &SectionDelimiters::default())
+            .unwrap_err()
+            .to_string()
+    };
+
+    assert_eq!(
+        err("<!-- my section foo end -->\n"),
+        "found `<!-- my section foo end -->` at line 1 without a preceding `<!-- my section foo start -->`"
+    );
+    assert_eq!(
+        err("<!-- my section foo start -->\n<!-- my section bar start -->\n"),
+        "found `<!-- my section bar start -->` at line 2 without a preceding `<!-- my section foo end -->`"
+    );
+    assert_eq!(
+        err("<!-- my section foo start -->\nfoo\n"),
+        "found `<!-- my section foo start -->` at line 1 without a following `<!-- my section foo end -->`"
+    );
+}
+
 #[test]
 fn test_replace_section_html() {
     expect![[r#"
@@ -171,3 +416,42 @@ fn test_format_link_destination() {
     assert_eq!(format_link_destination("foo bar"), "<foo bar>");
     assert_eq!(format_link_destination("foo()bar"), "<foo()bar>");
 }
+
+#[test]
+fn test_last_heading_level() {
+    assert_eq!(last_heading_level("no heading"), None);
+    assert_eq!(last_heading_level("# a\n\n### b\n\ntext"), Some(3));
+    assert_eq!(last_heading_level("### a\n\nb\n---\n"), Some(2));
+    assert_eq!(last_heading_level("## a\n\n```\n# not a heading\n```\n"), Some(2));
+}
+
+#[test]
+fn test_children() {
+    let tree = Tree::new("Some *text* and `code`.");
+    let paragraph = tree.at(0).unwrap();
+
+    let strs = |iter: &mut dyn Iterator<Item = &str>| iter.collect::<Vec<_>>().join("|");
+
+    let children = paragraph.children();
+    assert_eq!(children.len(), 5);
+    expect!["Some |*text*| and |`code`|."].assert_eq(&strs(&mut children.map(|n| n.str())));
+    expect![".|`code`| and |*text*|Some "]
+        .assert_eq(&strs(&mut paragraph.children().rev().map(|n| n.str())));
+
+    let mut children = paragraph.children();
+    assert_eq!(children.next().map(|n| n.str()), Some("Some "));
+    assert_eq!(children.next_back().map(|n| n.str()), Some("."));
+    assert_eq!(children.len(), 3);
+}
+
+#[test]
+fn test_shrink_headings_below() {
+    let markdown = "## Usage\ntext\n### Details\n\nSetext\n------\n";
+
+    assert_eq!(shrink_headings_below(markdown, 2), "### Usage\ntext\n#### Details\n\n### Setext\n");
+
+    // already below
+    assert_eq!(shrink_headings_below(markdown, 1), markdown);
+    assert_eq!(shrink_headings_below("no headings", 6), "no headings");
+    assert_eq!(shrink_headings_below("# a\n###### b\n", 2), "### a\n###### b\n");
+}
@@ -1,4 +1,4 @@
-use std::ops::Range;
+use std::{iter::FusedIterator, ops::Range};
 
 use crate::markdown_rs::{
     self,
@@ -63,26 +63,8 @@ impl<'m, 't> Node<'m, 't> {
         self.children().filter(move |n| n.name() == name)
     }
 
-    pub fn children(self) -> impl Iterator<Item = Self> {
-        let mut depth = 0;
-
-        (self.index + 1..self.tree.events.len())
-            .map_while(move |i| {
-                let kind = self.tree.events[i].kind.clone();
-
-                if depth == 0 && kind == Kind::Exit {
-                    return None;
-                }
-
-                match kind {
-                    Kind::Enter => depth += 1,
-                    Kind::Exit => depth -= 1,
-                }
-
-                Some((i, depth))
-            })
-            .filter_map(|(i, depth)| (depth == 1).then_some(i))
-            .filter_map(|index| self.tree.at(index))
+    pub fn children(self) -> Children<'m, 't> {
+        Children::new(self)
     }
 
     pub fn descendant(self, name: Name) -> Option<Self> {
@@ -122,29 +104,114 @@ impl<'m, 't> Node<'m, 't> {
     pub fn position(self) -> Position {
         let event = &self.tree.events[self.index];
         let start = event.point.to_unist();
-        let exit_index = self.exit(self.index);
+        let exit_index = exit(&self.tree.events, self.index);
         let end = self.tree.events[exit_index].point.to_unist();
         Position { start, end }
     }
+}
 
-    fn exit(&self, mut i: usize) -> usize {
-        let mut depth = 0;
+/// The direct children of a [`Node`], returned by [`Node::children`].
+#[derive(Clone)]
+pub struct Children<'m, 't> {
+    tree: &'t Tree<'m>,
+    /// The enter event of the next child from the front.
+    front: usize,
+    /// One past the exit event of the next child from the back.
+    back: usize,
+    len: usize,
+}
 
-        loop {
-            i += 1;
+impl<'m, 't> Children<'m, 't> {
+    fn new(parent: Node<'m, 't>) -> Self {
+        let events = &parent.tree.events;
+        let front = parent.index + 1;
+        let back = exit(events, parent.index);
+
+        let mut len = 0;
+        let mut i = front;
+
+        while i < back {
+            i = exit(events, i) + 1;
+            len += 1;
+        }
 
-            let Some(event) = self.tree.events.get(i) else {
-                unreachable!("unpaired enter/exit event")
-            };
+        Self { tree: parent.tree, front, back, len }
+    }
+}
+
+impl<'m, 't> Iterator for Children<'m, 't> {
+    type Item = Node<'m, 't>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
 
-            if depth == 0 && event.kind == Kind::Exit {
-                return i;
-            }
+        let index = self.front;
+        self.front = exit(&self.tree.events, index) + 1;
+        self.len -= 1;
+        Some(Node { tree: self.tree, index })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl DoubleEndedIterator for Children<'_, '_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let index = enter(&self.tree.events, self.back - 1);
+        self.back = index;
+        self.len -= 1;
+        Some(Node { tree: self.tree, index })
+    }
+}
+
+impl ExactSizeIterator for Children<'_, '_> {}
+
+impl FusedIterator for Children<'_, '_> {}
+
+/// Returns the index of the exit event that belongs to the enter event at `i`.
+fn exit(events: &[Event], mut i: usize) -> usize {
+    let mut depth = 0;
+
+    loop {
+        i += 1;
+
+        let Some(event) = events.get(i) else { unreachable!("unpaired enter/exit event") };
+
+        if depth == 0 && event.kind == Kind::Exit {
+            return i;
+        }
+
+        match event.kind {
+            Kind::Enter => depth += 1,
+            Kind::Exit => depth -= 1,
+        }
+    }
+}
+
+/// Returns the index of the enter event that belongs to the exit event at `i`.
+fn enter(events: &[Event], mut i: usize) -> usize {
+    let mut depth = 0;
+
+    loop {
+        let Some(new_i) = i.checked_sub(1) else { unreachable!("unpaired enter/exit event") };
+        i = new_i;
+
+        let event = &events[i];
+
+        if depth == 0 && event.kind == Kind::Enter {
+            return i;
+        }
 
-            match event.kind {
-                Kind::Enter => depth += 1,
-                Kind::Exit => depth -= 1,
-            }
+        match event.kind {
+            Kind::Enter => depth -= 1,
+            Kind::Exit => depth += 1,
         }
     }
 }
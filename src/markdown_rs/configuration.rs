@@ -11,7 +11,7 @@ use alloc::{boxed::Box, fmt, string::String};
 ///
 /// ## Examples
 ///
-/// ```
+/// ```rust ignore
 /// use markdown::Constructs;
 /// # fn main() {
 ///
@@ -443,7 +443,7 @@ impl Constructs {
 ///
 /// ## Examples
 ///
-/// ```
+/// ```rust ignore
 /// use markdown::CompileOptions;
 /// # fn main() {
 ///
@@ -484,7 +484,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html_with_options, CompileOptions, Options};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -529,7 +529,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html, to_html_with_options, CompileOptions, Options};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -583,7 +583,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html, to_html_with_options, CompileOptions, Options};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -629,7 +629,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html, to_html_with_options, CompileOptions, LineEnding, Options};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -669,7 +669,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html_with_options, CompileOptions, Options, ParseOptions};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -725,7 +725,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html_with_options, CompileOptions, Options, ParseOptions};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -773,7 +773,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html_with_options, CompileOptions, Options, ParseOptions};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -818,7 +818,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html_with_options, CompileOptions, Options, ParseOptions};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -863,7 +863,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html_with_options, CompileOptions, Options, ParseOptions};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -902,7 +902,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html_with_options, CompileOptions, Options, ParseOptions};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -939,7 +939,7 @@ pub struct CompileOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html_with_options, CompileOptions, Options, ParseOptions};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -1011,7 +1011,7 @@ impl CompileOptions {
 ///
 /// ## Examples
 ///
-/// ```
+/// ```rust ignore
 /// use markdown::ParseOptions;
 /// # fn main() {
 ///
@@ -1031,7 +1031,7 @@ pub struct ParseOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html, to_html_with_options, Constructs, Options, ParseOptions};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -1076,7 +1076,7 @@ pub struct ParseOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html_with_options, Constructs, Options, ParseOptions};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -1131,7 +1131,7 @@ pub struct ParseOptions {
     ///
     /// ## Examples
     ///
-    /// ```
+    /// ```rust ignore
     /// use markdown::{to_html_with_options, Constructs, Options, ParseOptions};
     /// # fn main() -> Result<(), markdown::message::Message> {
     ///
@@ -1278,7 +1278,7 @@ impl ParseOptions {
 ///
 /// ## Examples
 ///
-/// ```
+/// ```rust ignore
 /// use markdown::Options;
 /// # fn main() {
 ///
@@ -7,7 +7,7 @@ use alloc::{str::FromStr, string::String};
 ///
 /// ## Examples
 ///
-/// ```
+/// ```rust ignore
 /// use markdown::LineEnding;
 /// # fn main() {
 ///
@@ -10,8 +10,12 @@
 //!   messages. Those won't be shown unless the `RUST_LOG` env var is used.
 //! - Adds our own [`PrettyLog`] as a layer with a filter so only our own crate's message are pretty
 //!   printed.
+//!
+//! With [`LogFormat::Json`] events, reports and the tally are instead printed as newline
+//! delimited JSON objects for tools to consume.
 
 mod pretty_eyre;
+mod progress;
 #[cfg(test)]
 pub(crate) mod tests;
 mod visit_str;
@@ -24,7 +28,9 @@ use std::{
 };
 
 use anstyle::{AnsiColor, Color, Effects, Style};
+use clap::ValueEnum;
 use color_eyre::eyre::{self, Report};
+use serde::{Serialize, Serializer};
 use tracing::{
     Event, Level, Metadata, Subscriber,
     field::{Field, Visit},
@@ -38,13 +44,18 @@ use tracing_subscriber::{
     registry::LookupSpan,
 };
 
+use progress::Progress;
 use visit_str::{VisitAsStr, VisitStr};
 
 pub trait AnyWrite: Any + io::Write + Send {}
 
 impl<T: Any + io::Write + Send> AnyWrite for T {}
 
-trait Mutx {
+/// Locks a mutex, ignoring poisoning.
+///
+/// A panic while holding the lock does not leave our data in an invalid state,
+/// so the panic is reported instead of a second one from `unwrap`.
+pub trait Mutx {
     type Out;
 
     fn lck(&self) -> MutexGuard<'_, Self::Out>;
@@ -58,19 +69,32 @@ impl<T> Mutx for Mutex<T> {
     }
 }
 
+/// How events are printed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human readable messages
+    #[default]
+    Pretty,
+    /// One JSON object per line
+    Json,
+}
+
 #[derive(Clone)]
 pub struct PrettyLog {
     inner: Arc<Mutex<PrettyLogInner>>,
 }
 
 impl PrettyLog {
-    pub fn new(sink: Box<dyn AnyWrite>) -> Self {
+    pub fn new(sink: Box<dyn AnyWrite>, format: LogFormat) -> Self {
         Self {
             inner: Arc::new(Mutex::new(PrettyLogInner {
                 sink,
+                format,
                 tally: Default::default(),
                 last_print_kind: None,
                 format_source_info: false,
+                progress_enabled: false,
+                progress: None,
             })),
         }
     }
@@ -79,6 +103,49 @@ impl PrettyLog {
         self.inner.lck().format_source_info = enabled;
     }
 
+    /// Whether [`begin_progress`](Self::begin_progress) shows a progress bar.
+    pub fn progress(&self, enabled: bool) {
+        self.inner.lck().progress_enabled = enabled;
+    }
+
+    /// Shows a progress bar with `total` steps until [`finish_progress`](Self::finish_progress).
+    pub fn begin_progress(&self, total: usize, message: &str) {
+        let mut inner = self.inner.lck();
+
+        if inner.progress_enabled {
+            inner.progress = Some(Progress::new(total, message));
+        }
+    }
+
+    pub fn set_progress_message(&self, message: &str) {
+        if let Some(progress) = &self.inner.lck().progress {
+            progress.set_message(message);
+        }
+    }
+
+    pub fn tick_progress(&self) {
+        if let Some(progress) = &self.inner.lck().progress {
+            progress.tick();
+        }
+    }
+
+    /// Hides the progress bar while `f` runs, like a child process writing to stderr.
+    pub fn suspend_progress<R>(&self, f: impl FnOnce() -> R) -> R {
+        // not holding the lock while `f` runs, so `f` can log
+        let progress = self.inner.lck().progress.clone();
+
+        match progress {
+            Some(progress) => progress.suspend(f),
+            None => f(),
+        }
+    }
+
+    pub fn finish_progress(&self) {
+        if let Some(progress) = self.inner.lck().progress.take() {
+            progress.finish();
+        }
+    }
+
     pub fn subscriber(&self, filter: &str) -> impl Subscriber + Send + Sync + 'static {
         tracing_subscriber::registry()
             .with(ErrorLayer::default())
@@ -136,7 +203,7 @@ impl PrettyLog {
     pub fn foreign_write_incoming(&self) {
         let mut inner = self.inner.lck();
         let out = inner.begin_print(PrintKind::Foreign);
-        _ = inner.sink.write_all(out.as_bytes());
+        inner.write_sink(out.as_bytes());
     }
 }
 
@@ -167,16 +234,29 @@ impl PrintKind {
 
 struct PrettyLogInner {
     sink: Box<dyn AnyWrite>,
+    format: LogFormat,
     tally: Tally,
     last_print_kind: Option<PrintKind>,
     format_source_info: bool,
+    progress_enabled: bool,
+    progress: Option<Progress>,
 }
 
 impl PrettyLogInner {
+    fn write_sink(&mut self, bytes: &[u8]) {
+        let sink = &mut self.sink;
+
+        match &self.progress {
+            Some(progress) => progress.suspend(|| _ = sink.write_all(bytes)),
+            None => _ = sink.write_all(bytes),
+        }
+    }
+
     fn begin_print(&mut self, print_kind: PrintKind) -> String {
         let mut out = String::new();
 
         if let Some(last_print_kind) = self.last_print_kind
+            && self.format == LogFormat::Pretty
             && (print_kind.always_wants_separator() || last_print_kind != print_kind)
         {
             out.push('\n');
@@ -190,17 +270,21 @@ impl PrettyLogInner {
     fn write_direct(&mut self, string: &[u8]) {
         let mut out = self.begin_print(PrintKind::Direct).into_bytes();
         out.extend_from_slice(string);
-        _ = self.sink.write_all(&out);
+        self.write_sink(&out);
     }
 
     fn print_event(&mut self, level: Level, message: &str) {
         let mut out = self.begin_print(PrintKind::Pretty);
         self.tally.inc(level);
         out.push_str(message);
-        _ = self.sink.write_all(out.as_bytes());
+        self.write_sink(out.as_bytes());
     }
 
     fn print_report(&mut self, report: &Report) {
+        if self.format == LogFormat::Json {
+            return self.print_report_json(report);
+        }
+
         let mut out = self.begin_print(PrintKind::Pretty);
         let level = pretty_eyre::extract_severity(report);
         self.tally.inc(level);
@@ -237,12 +321,61 @@ impl PrettyLogInner {
             }
         }
 
-        _ = self.sink.write_all(out.as_bytes());
+        self.write_sink(out.as_bytes());
+    }
+
+    fn print_report_json(&mut self, report: &Report) {
+        let mut out = self.begin_print(PrintKind::Pretty);
+        let level = pretty_eyre::extract_severity(report);
+        self.tally.inc(level);
+
+        let chain = report.chain().map(|error| error.to_string()).collect::<Vec<_>>();
+        let mut fields = JsonFields::default();
+
+        if let Some(span) = pretty_eyre::extract_span(report) {
+            span.with_subscriber(|(id, sub)| {
+                if let Some(reg) = sub.downcast_ref::<Registry>() {
+                    let span =
+                        reg.span(id).expect("registry should have a span for the current ID");
+
+                    for span in span.scope() {
+                        if let Some(span_fields) = span.extensions().get::<JsonFields>() {
+                            fields.extend(span_fields);
+                        }
+                    }
+                }
+            });
+        }
+
+        if self.format_source_info {
+            if let Some(location) = pretty_eyre::extract_location(report) {
+                let file = location.file();
+                let line = location.line();
+                fields.push("source", &format!("{file}:{line}"));
+            }
+        }
+
+        let record = JsonRecord {
+            level: level_name(level),
+            message: &chain[0],
+            chain: Some(&chain),
+            fields,
+        };
+
+        format_json(&mut out, &record);
+        self.write_sink(out.as_bytes());
     }
 
     fn print_tally(&mut self) {
         let Tally { warnings, errors, .. } = self.tally;
 
+        if self.format == LogFormat::Json {
+            let mut out = self.begin_print(PrintKind::Pretty);
+            format_json(&mut out, &JsonSummary { summary: JsonTally { errors, warnings } });
+            self.write_sink(out.as_bytes());
+            return;
+        }
+
         let mut out = String::new();
 
         if errors != 0 || warnings != 0 {
@@ -270,7 +403,19 @@ impl PrettyLogInner {
             out.push('\n');
         }
 
-        _ = self.sink.write_all(out.as_bytes());
+        self.write_sink(out.as_bytes());
+    }
+
+    fn format_metadata_json(&self, fields: &mut JsonFields, metadata: &Metadata) {
+        if self.format_source_info {
+            if let Some(module) = metadata.module_path() {
+                fields.push("module", module);
+            }
+
+            if let (Some(file), Some(line)) = (metadata.file(), metadata.line()) {
+                fields.push("source", &format!("{file}:{line}"));
+            }
+        }
     }
 
     fn format_metadata(&self, out: &mut String, metadata: &Metadata) {
@@ -293,6 +438,14 @@ where
     S: for<'lookup> LookupSpan<'lookup>,
 {
     fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if self.inner.lck().format == LogFormat::Json {
+            let mut fields = JsonFields::default();
+            fields.span(attrs.metadata().name());
+            attrs.record(&mut VisitAsStr(&mut fields));
+            ctx.span(id).unwrap().extensions_mut().insert(fields);
+            return;
+        }
+
         let mut fmt = PrettyFields::new();
         fmt.span(attrs.metadata().name());
         attrs.record(&mut fmt.visit());
@@ -300,6 +453,34 @@ where
     }
 
     fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if self.inner.lck().format == LogFormat::Json {
+            let mut fmt = JsonEvent::default();
+            let level = *event.metadata().level();
+            event.record(&mut VisitAsStr(&mut fmt));
+
+            if let Some(scope) = ctx.event_scope(event) {
+                for span in scope {
+                    if let Some(span_fields) = span.extensions().get::<JsonFields>() {
+                        fmt.fields.extend(span_fields);
+                    }
+                }
+            }
+
+            let mut inner = self.inner.lck();
+            inner.format_metadata_json(&mut fmt.fields, event.metadata());
+
+            let mut out = String::new();
+            let record = JsonRecord {
+                level: level_name(level),
+                message: &fmt.message,
+                chain: None,
+                fields: fmt.fields,
+            };
+            format_json(&mut out, &record);
+            inner.print_event(level, &out);
+            return;
+        }
+
         let mut fmt = PrettyEvent::new();
         let level = *event.metadata().level();
         event.record(&mut fmt.visit());
@@ -387,6 +568,88 @@ impl PrettyFields {
     }
 }
 
+#[derive(Default)]
+struct JsonEvent {
+    message: String,
+    fields: JsonFields,
+}
+
+impl VisitStr for JsonEvent {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            if !self.message.is_empty() {
+                self.message.push('\n');
+            }
+
+            self.message.push_str(value);
+        } else {
+            self.fields.push(field.name(), value);
+        }
+    }
+}
+
+/// Fields in the order they were recorded.
+/// A field that is already present is not overwritten, so the innermost span wins.
+#[derive(Default, Clone)]
+struct JsonFields(Vec<(String, String)>);
+
+impl JsonFields {
+    fn push(&mut self, name: &str, value: &str) {
+        if !self.0.iter().any(|(n, _)| n == name) {
+            self.0.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    fn extend(&mut self, other: &JsonFields) {
+        for (name, value) in &other.0 {
+            self.push(name, value);
+        }
+    }
+
+    fn span(&mut self, name: &str) {
+        if !name.is_empty() {
+            self.push("span", name);
+        }
+    }
+}
+
+impl VisitStr for JsonFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field.name(), value);
+    }
+}
+
+impl Serialize for JsonFields {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(name, value)| (name, value)))
+    }
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    level: &'static str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chain: Option<&'a [String]>,
+    fields: JsonFields,
+}
+
+#[derive(Serialize)]
+struct JsonSummary {
+    summary: JsonTally,
+}
+
+#[derive(Serialize)]
+struct JsonTally {
+    errors: usize,
+    warnings: usize,
+}
+
+fn format_json(out: &mut String, value: &impl Serialize) {
+    out.push_str(&serde_json::to_string(value).expect("json record is serializable"));
+    out.push('\n');
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Tally {
     pub warnings: usize,
@@ -424,14 +687,18 @@ const INDENT: &str = match str::from_utf8(INDENT_BYTES) {
     Err(_) => unreachable!(),
 };
 
-fn format_level(out: &mut String, level: Level) {
-    let name = match level {
+fn level_name(level: Level) -> &'static str {
+    match level {
         Level::ERROR => "error",
         Level::WARN => "warning",
         Level::INFO => "info",
         Level::DEBUG => "debug",
         Level::TRACE => "trace",
-    };
+    }
+}
+
+fn format_level(out: &mut String, level: Level) {
+    let name = level_name(level);
 
     let style = match level {
         Level::ERROR => &ERROR,
@@ -489,6 +756,7 @@ impl<T> WithResultSeverity<T> for Result<T, Report> {
 
 pub trait WithErrorSeverity {
     fn with_severity(self, level: Level) -> Report;
+    fn severity(&self) -> Level;
 }
 
 impl WithErrorSeverity for Report {
@@ -496,4 +764,8 @@ impl WithErrorSeverity for Report {
         pretty_eyre::set_severity(&mut self, level);
         self
     }
+
+    fn severity(&self) -> Level {
+        pretty_eyre::extract_severity(self)
+    }
 }
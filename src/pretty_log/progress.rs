@@ -0,0 +1,65 @@
+//! A progress bar shown below the log output.
+//!
+//! Without the `progress` feature this is a no-op.
+
+#[cfg(feature = "progress")]
+#[derive(Clone)]
+pub struct Progress {
+    bar: indicatif::ProgressBar,
+}
+
+#[cfg(feature = "progress")]
+impl Progress {
+    pub fn new(total: usize, message: &str) -> Self {
+        let style = indicatif::ProgressStyle::with_template("{bar:30.cyan/blue} {pos}/{len} {msg}")
+            .expect("progress template is valid")
+            .progress_chars("=> ");
+
+        let bar = indicatif::ProgressBar::with_draw_target(
+            Some(total as u64),
+            indicatif::ProgressDrawTarget::stderr(),
+        )
+        .with_style(style)
+        .with_message(message.to_string());
+
+        Self { bar }
+    }
+
+    pub fn set_message(&self, message: &str) {
+        self.bar.set_message(message.to_string());
+    }
+
+    pub fn tick(&self) {
+        self.bar.inc(1);
+    }
+
+    /// Hides the bar while `f` writes to stderr.
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.bar.suspend(f)
+    }
+
+    pub fn finish(self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+#[cfg(not(feature = "progress"))]
+#[derive(Clone)]
+pub struct Progress;
+
+#[cfg(not(feature = "progress"))]
+impl Progress {
+    pub fn new(_total: usize, _message: &str) -> Self {
+        Self
+    }
+
+    pub fn set_message(&self, _message: &str) {}
+
+    pub fn tick(&self) {}
+
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    pub fn finish(self) {}
+}
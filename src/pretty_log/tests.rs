@@ -16,7 +16,7 @@ use tracing_subscriber::{
 };
 use unindent::unindent;
 
-use super::{PrettyLog, Tally, WithErrorSeverity as _, pretty_eyre};
+use super::{LogFormat, PrettyLog, Tally, WithErrorSeverity as _, pretty_eyre};
 
 fn prepend_newline(str: &str) -> String {
     format!("\n{str}")
@@ -33,6 +33,15 @@ pub fn prepare_for_compare(str: &str) -> String {
 }
 
 pub fn with_log(pretty_filter: &str, rustlog_filter: &str, f: impl FnOnce(PrettyLog)) -> String {
+    with_log_format(LogFormat::Pretty, pretty_filter, rustlog_filter, f)
+}
+
+fn with_log_format(
+    format: LogFormat,
+    pretty_filter: &str,
+    rustlog_filter: &str,
+    f: impl FnOnce(PrettyLog),
+) -> String {
     if let Ok((panic_hook, eyre_hook)) = color_eyre::config::HookBuilder::default()
         .capture_span_trace_by_default(true)
         .try_into_hooks()
@@ -41,7 +50,7 @@ pub fn with_log(pretty_filter: &str, rustlog_filter: &str, f: impl FnOnce(Pretty
         _ = eyre::set_hook(pretty_eyre::wrap_hook(eyre_hook.into_eyre_hook()));
     }
 
-    let log = PrettyLog::new(Box::new(Vec::<u8>::new()));
+    let log = PrettyLog::new(Box::new(Vec::<u8>::new()), format);
 
     let guard = tracing_subscriber::registry()
         .with(ErrorLayer::default().boxed())
@@ -75,6 +84,11 @@ pub fn simple_log(f: impl FnOnce(PrettyLog)) -> String {
     with_log("info", "", f)
 }
 
+fn json_log(f: impl FnOnce(PrettyLog)) -> Vec<serde_json::Value> {
+    let out = with_log_format(LogFormat::Json, "info", "", f);
+    out.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
 #[test]
 fn test_event() {
     let out = simple_log(|log| {
@@ -237,3 +251,48 @@ fn test_regular_logs_between_pretty() {
     "#]]
     .assert_eq(&prepare_for_compare(&out));
 }
+
+#[test]
+fn test_json_event() {
+    let out = json_log(|log| {
+        let _span = info_span!("pets", cat = "cute").entered();
+        debug!("i'm a debug");
+        warn!(dog = "too", "i'm a warning");
+        log.print_tally();
+    });
+
+    assert_eq!(
+        out,
+        [
+            serde_json::json!({
+                "level": "warning",
+                "message": "i'm a warning",
+                "fields": { "dog": "too", "span": "pets", "cat": "cute" },
+            }),
+            serde_json::json!({ "summary": { "errors": 0, "warnings": 1 } }),
+        ]
+    );
+}
+
+#[test]
+#[ignore = "needs to be run separately because of hooks"]
+fn test_json_report() {
+    let out = json_log(|log| {
+        let _span = info_span!("", coffee = "missing").entered();
+        log.print_report(&eyre!("coffee machine broke").wrap_err("i'm tired"));
+        log.print_tally();
+    });
+
+    assert_eq!(
+        out,
+        [
+            serde_json::json!({
+                "level": "error",
+                "message": "i'm tired",
+                "chain": ["i'm tired", "coffee machine broke"],
+                "fields": { "coffee": "missing" },
+            }),
+            serde_json::json!({ "summary": { "errors": 1, "warnings": 0 } }),
+        ]
+    );
+}
@@ -0,0 +1,56 @@
+//! Expands readme templates like this one:
+//! ```md
+//! # my-crate-name
+//!
+//! {{ crate_docs }}
+//!
+//! ## Features
+//!
+//! {{ feature_docs }}
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use core::ops::Range;
+
+use color_eyre::eyre::Result;
+use tracing::warn;
+
+use crate::string_replacer::StringReplacer;
+
+pub const CRATE_DOCS: &str = "crate_docs";
+pub const FEATURE_DOCS: &str = "feature_docs";
+
+/// Replaces the `{{ name }}` placeholders with the output of `value`.
+///
+/// Unknown placeholders are left as they are, so the template can contain other `{{ }}` text.
+pub fn expand(template: &str, mut value: impl FnMut(&str) -> Result<String>) -> Result<String> {
+    let mut out = StringReplacer::new(template);
+
+    for (range, name) in placeholders(template) {
+        if !matches!(name, CRATE_DOCS | FEATURE_DOCS) {
+            warn!("unknown readme template placeholder \"{name}\" is left as is");
+            continue;
+        }
+
+        out.replace(range, value(name)?);
+    }
+
+    Ok(out.finish())
+}
+
+fn placeholders(template: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    const START: &str = "{{";
+    const END: &str = "}}";
+
+    let mut end = 0;
+
+    std::iter::from_fn(move || {
+        let start = template[end..].find(START)? + end;
+        let name_start = start + START.len();
+        let name_end = template[name_start..].find(END)? + name_start;
+        end = name_end + END.len();
+        Some((start..end, template[name_start..name_end].trim()))
+    })
+}
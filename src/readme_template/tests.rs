@@ -0,0 +1,45 @@
+use expect_test::expect;
+
+use super::{expand, placeholders};
+
+#[test]
+fn test_expand() {
+    let template = "# title\n\n{{ crate_docs }}\n\n## Features\n\n{{feature_docs}}\n";
+
+    let out = expand(template, |name| {
+        Ok(match name {
+            "crate_docs" => "These are the crate docs.".into(),
+            "feature_docs" => "- **`std`**".into(),
+            _ => unreachable!(),
+        })
+    })
+    .unwrap();
+
+    expect![[r#"
+        # title
+
+        These are the crate docs.
+
+        ## Features
+
+        - **`std`**
+    "#]]
+    .assert_eq(&out);
+}
+
+#[test]
+fn test_placeholders() {
+    let names =
+        placeholders("{{ a }} {{b}} {{  c  }} }} {{ d").map(|(_, name)| name).collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b", "c"]);
+}
+
+#[test]
+fn test_unknown_placeholder() {
+    let out = expand("{{ crate_documentation }} {{ crate_docs }}", |name| {
+        assert_eq!(name, "crate_docs");
+        Ok("docs".into())
+    })
+    .unwrap();
+    assert_eq!(out, "{{ crate_documentation }} docs");
+}
@@ -0,0 +1,139 @@
+//! Selects the packages released since a version or git tag,
+//! for `--since-version`, `--since-git-tag` and `--exclude-prereleases`.
+
+#[cfg(test)]
+mod tests;
+
+use std::path::Path;
+
+use cargo_metadata::{Package, semver::Version};
+use color_eyre::eyre::{OptionExt as _, Result, WrapErr as _, bail};
+use tracing::error_span;
+
+/// What the package versions are compared against.
+pub enum Baseline<'a> {
+    Version(&'a Version),
+    GitTag(&'a str),
+}
+
+/// Keeps the packages whose version is greater than the baseline.
+///
+/// Packages with a pre-release version are kept unless `exclude_prereleases` is set.
+pub fn retain_released(
+    packages: &mut Vec<&Package>,
+    workspace_root: &Path,
+    baseline: Option<Baseline>,
+    exclude_prereleases: bool,
+) -> Result<()> {
+    let baselines = match baseline {
+        None => vec![None; packages.len()],
+        Some(Baseline::Version(version)) => vec![Some(version.clone()); packages.len()],
+        Some(Baseline::GitTag(tag)) => versions_at_tag(workspace_root, tag, packages)?,
+    };
+
+    let mut baselines = baselines.into_iter();
+
+    packages.retain(|package| {
+        let baseline = baselines.next().flatten();
+        is_released(&package.version, baseline.as_ref(), exclude_prereleases)
+    });
+
+    Ok(())
+}
+
+/// Whether `version` is greater than `baseline`.
+///
+/// `baseline` is `None` if there is nothing to compare against,
+/// like for a package that did not exist yet at the git tag.
+fn is_released(version: &Version, baseline: Option<&Version>, exclude_prereleases: bool) -> bool {
+    if !version.pre.is_empty() {
+        return !exclude_prereleases;
+    }
+
+    baseline.is_none_or(|baseline| version > baseline)
+}
+
+/// Returns the versions of the packages at the git tag, `None` for packages that did not exist.
+fn versions_at_tag(
+    workspace_root: &Path,
+    tag: &str,
+    packages: &[&Package],
+) -> Result<Vec<Option<Version>>> {
+    let _span = error_span!("", tag).entered();
+
+    let repo =
+        gix::discover(workspace_root).wrap_err("the workspace is not inside a git repository")?;
+    let workdir = repo.workdir().ok_or_eyre("the git repository has no work tree")?;
+    let workdir = workdir.canonicalize().wrap_err("failed to find the git work tree")?;
+
+    let tree = repo
+        .find_reference(format!("refs/tags/{tag}").as_str())
+        .wrap_err("git tag not found")?
+        .peel_to_commit()
+        .wrap_err("git tag does not point to a commit")?
+        .tree()
+        .wrap_err("failed to read the files at the git tag")?;
+
+    // reads a file as it was at the tag
+    let read = |path: &Path| -> Result<Option<String>> {
+        let _span = error_span!("", path = %path.display()).entered();
+        let path = path.canonicalize().wrap_err("failed to find file")?;
+        let relative_path =
+            path.strip_prefix(&workdir).wrap_err("file is not in the git repository")?;
+
+        let Some(entry) = tree
+            .lookup_entry_by_path(relative_path)
+            .wrap_err("failed to find file at the git tag")?
+        else {
+            return Ok(None);
+        };
+
+        let object = entry.object().wrap_err("failed to read file at the git tag")?;
+        let contents =
+            String::from_utf8(object.data.clone()).wrap_err("file at the git tag is not utf-8")?;
+        Ok(Some(contents))
+    };
+
+    let workspace_toml = read(&workspace_root.join("Cargo.toml"))?;
+
+    packages
+        .iter()
+        .map(|package| {
+            let _span = error_span!("", package = package.name.as_str()).entered();
+
+            match read(package.manifest_path.as_std_path())? {
+                Some(toml) => manifest_version(&toml, workspace_toml.as_deref()).map(Some),
+                None => Ok(None),
+            }
+        })
+        .collect()
+}
+
+/// Returns the `package.version` of a manifest's contents (`Cargo.toml`).
+///
+/// `workspace_toml` is the workspace manifest for an inherited `version.workspace = true`.
+fn manifest_version(toml: &str, workspace_toml: Option<&str>) -> Result<Version> {
+    let manifest = toml::from_str::<toml::Table>(toml).wrap_err("failed to parse manifest")?;
+
+    let version = match manifest.get("package").and_then(|package| package.get("version")) {
+        // cargo defaults to this version if there is none
+        None => return Ok(Version::new(0, 0, 0)),
+        Some(toml::Value::String(version)) => version.clone(),
+        Some(version) if version.get("workspace").and_then(toml::Value::as_bool) == Some(true) => {
+            let workspace_toml = workspace_toml.ok_or_eyre("workspace manifest not found")?;
+            let workspace = toml::from_str::<toml::Table>(workspace_toml)
+                .wrap_err("failed to parse workspace manifest")?;
+
+            workspace
+                .get("workspace")
+                .and_then(|workspace| workspace.get("package"))
+                .and_then(|package| package.get("version"))
+                .and_then(toml::Value::as_str)
+                .ok_or_eyre("workspace manifest has no `workspace.package.version`")?
+                .to_string()
+        }
+        Some(_) => bail!("`package.version` is not a string"),
+    };
+
+    Version::parse(&version).wrap_err_with(|| format!("invalid package version {version:?}"))
+}
@@ -0,0 +1,47 @@
+use cargo_metadata::semver::Version;
+use indoc::indoc;
+
+use super::{is_released, manifest_version};
+
+#[test]
+fn test_is_released() {
+    let version = |v| Version::parse(v).unwrap();
+    let baseline = version("1.2.0");
+
+    assert!(is_released(&version("1.2.1"), Some(&baseline), false));
+    assert!(!is_released(&version("1.2.0"), Some(&baseline), false));
+    assert!(!is_released(&version("1.1.0"), Some(&baseline), false));
+
+    // pre-releases are included unconditionally unless excluded
+    assert!(is_released(&version("1.0.0-rc.1"), Some(&baseline), false));
+    assert!(!is_released(&version("2.0.0-rc.1"), Some(&baseline), true));
+
+    // nothing to compare against
+    assert!(is_released(&version("0.1.0"), None, false));
+    assert!(is_released(&version("0.1.0"), None, true));
+}
+
+#[test]
+fn test_manifest_version() {
+    let workspace_toml = indoc! {r#"
+        [workspace]
+        members = ["foo"]
+
+        [workspace.package]
+        version = "2.0.0"
+    "#};
+
+    let version = |toml| manifest_version(toml, Some(workspace_toml)).unwrap().to_string();
+
+    assert_eq!(version("[package]\nname = \"foo\"\nversion = \"1.2.3\""), "1.2.3");
+    assert_eq!(version("[package]\nname = \"foo\"\nversion.workspace = true"), "2.0.0");
+    assert_eq!(version("[package]\nname = \"foo\""), "0.0.0");
+    assert_eq!(
+        manifest_version("[package]\nversion = \"1.2\"", None).unwrap_err().to_string(),
+        "invalid package version \"1.2\""
+    );
+    assert_eq!(
+        manifest_version("[package]\nversion.workspace = true", None).unwrap_err().to_string(),
+        "workspace manifest not found"
+    );
+}
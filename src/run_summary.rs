@@ -0,0 +1,99 @@
+//! The machine-readable summary written by `--output-json`.
+//!
+//! Unlike `--log-format json`, which prints every message as it happens,
+//! this is a single JSON document written after all packages ran.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Report, Result, WrapErr as _};
+use serde::Serialize;
+
+#[derive(Default, Serialize)]
+pub struct RunSummary {
+    pub packages: Vec<PackageSummary>,
+    /// The insertion of the workspace documentation into the workspace readme, if configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<PackageSummary>,
+}
+
+impl RunSummary {
+    pub fn write(&self, path: &Path) -> Result<()> {
+        write_json(path, self)
+    }
+}
+
+/// Writes `value` as JSON to `path`, or to stdout if `path` is `-`.
+pub fn write_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    let mut json = serde_json::to_string_pretty(value).wrap_err("json serialization failed")?;
+    json.push('\n');
+
+    if path == Path::new("-") {
+        io::Write::write_all(&mut io::stdout(), json.as_bytes())
+            .wrap_err("failed to write json to stdout")
+    } else {
+        let _span = tracing::error_span!("", path = %path.display()).entered();
+        fs::write(path, json).wrap_err("failed to write json")
+    }
+}
+
+#[derive(Default, Serialize)]
+pub struct PackageSummary {
+    pub name: String,
+    pub status: PackageStatus,
+    pub files_written: Vec<PathBuf>,
+    pub links_resolved: usize,
+    pub links_unresolved: usize,
+    pub errors: Vec<String>,
+    /// Set when a check found stale documentation, which makes the status [`PackageStatus::CheckedStale`].
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl PackageSummary {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Default::default() }
+    }
+
+    /// The summary of a package that failed before it ran.
+    pub fn failed(name: &str, report: &Report) -> Self {
+        let mut this = Self::new(name);
+        this.push_error(report);
+        this.finish(false);
+        this
+    }
+
+    /// Records the error and its causes as a single line.
+    pub fn push_error(&mut self, report: &Report) {
+        let chain = report.chain().map(|error| error.to_string()).collect::<Vec<_>>();
+        self.errors.push(chain.join(": "));
+    }
+
+    /// Sets the status from what happened while running the package.
+    pub fn finish(&mut self, check: bool) {
+        self.status = if self.stale {
+            PackageStatus::CheckedStale
+        } else if !self.errors.is_empty() {
+            PackageStatus::Error
+        } else if check {
+            PackageStatus::CheckedOk
+        } else {
+            PackageStatus::Updated
+        };
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageStatus {
+    #[default]
+    Updated,
+    CheckedOk,
+    CheckedStale,
+    Error,
+}
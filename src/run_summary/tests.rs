@@ -0,0 +1,52 @@
+use color_eyre::eyre::eyre;
+use expect_test::expect;
+
+use super::{PackageStatus, PackageSummary, RunSummary};
+
+fn status(summary: impl FnOnce(&mut PackageSummary), check: bool) -> PackageStatus {
+    let mut package = PackageSummary::new("foo");
+    summary(&mut package);
+    package.finish(check);
+    package.status
+}
+
+#[test]
+fn test_status() {
+    assert_eq!(status(|_| (), false), PackageStatus::Updated);
+    assert_eq!(status(|_| (), true), PackageStatus::CheckedOk);
+    assert_eq!(status(|p| p.stale = true, true), PackageStatus::CheckedStale);
+    assert_eq!(status(|p| p.push_error(&eyre!("oh no")), false), PackageStatus::Error);
+    assert_eq!(status(|p| p.push_error(&eyre!("oh no")), true), PackageStatus::Error);
+    assert_eq!(PackageSummary::failed("foo", &eyre!("oh no")).status, PackageStatus::Error);
+}
+
+#[test]
+fn test_json() {
+    let mut package = PackageSummary::new("foo");
+    package.files_written.push("foo/README.md".into());
+    package.links_resolved = 42;
+    package.links_unresolved = 3;
+    package.push_error(&eyre!("file not found").wrap_err("failed to read"));
+    package.finish(false);
+
+    let summary = RunSummary { packages: vec![package], workspace: None };
+
+    expect![[r#"
+        {
+          "packages": [
+            {
+              "name": "foo",
+              "status": "error",
+              "files_written": [
+                "foo/README.md"
+              ],
+              "links_resolved": 42,
+              "links_unresolved": 3,
+              "errors": [
+                "failed to read: file not found"
+              ]
+            }
+          ]
+        }"#]]
+    .assert_eq(&serde_json::to_string_pretty(&summary).unwrap());
+}
@@ -1,3 +1,6 @@
+#[cfg(test)]
+mod tests;
+
 use std::{
     path::{Path, PathBuf},
     process::{Command, Output, Stdio},
@@ -8,7 +11,13 @@ use cargo_metadata::{Metadata, Package, Target};
 use color_eyre::eyre::{Context, Result, bail};
 use rustdoc_types::Crate;
 use serde::Deserialize;
-use tracing::error_span;
+use tracing::{error_span, warn, warn_span};
+
+/// The version of the `rustdoc-types` dependency, keep this in sync with the `Cargo.toml`.
+pub const RUSTDOC_TYPES_VERSION: &str = "0.58.0";
+
+const CHANGELOG_HELP: &str = "see https://github.com/bluurryy/cargo-insert-docs/blob/main/CHANGELOG.md \
+    for the toolchain each version supports";
 
 pub struct Options<'a> {
     // metadata
@@ -18,6 +27,7 @@ pub struct Options<'a> {
 
     // flags for cargo
     pub toolchain: Option<&'a str>,
+    pub no_rustup: bool,
     pub all_features: bool,
     pub no_default_features: bool,
     pub features: &'a mut dyn Iterator<Item = &'a str>,
@@ -29,6 +39,8 @@ pub struct Options<'a> {
 
     // flags for rustdoc
     pub document_private_items: bool,
+    /// Each argument is split on whitespace.
+    pub rustdoc_args: &'a [String],
 
     // process handling
     pub output: CommandOutput,
@@ -41,6 +53,12 @@ pub enum CommandOutput {
     Collect,
 }
 
+/// Returns the `rustdoc` in the directory of `rustc`, if there is one.
+fn rustdoc_next_to(rustc: &Path) -> Option<PathBuf> {
+    let rustdoc = rustc.with_file_name(format!("rustdoc{}", std::env::consts::EXE_SUFFIX));
+    rustdoc.is_file().then_some(rustdoc)
+}
+
 /// Package must have a `lib` target.
 pub fn generate(options: Options) -> Result<(Output, PathBuf)> {
     let Options {
@@ -48,10 +66,12 @@ pub fn generate(options: Options) -> Result<(Output, PathBuf)> {
         package,
         package_target,
         toolchain,
+        no_rustup,
         all_features,
         no_default_features,
         features,
         document_private_items,
+        rustdoc_args,
         manifest_path,
         target,
         target_dir,
@@ -60,11 +80,29 @@ pub fn generate(options: Options) -> Result<(Output, PathBuf)> {
         output: output_option,
     } = options;
 
-    let mut command = Command::new("cargo");
+    let mut command = if no_rustup {
+        // Without rustup the `+toolchain` syntax is not available,
+        // so we use the cargo that invoked us or the one in the PATH.
+        let mut command = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
 
-    if let Some(toolchain) = toolchain {
-        command.arg(format!("+{toolchain}"));
-    }
+        // The rustdoc must come from the same toolchain as `RUSTC`.
+        if let Some(rustc) = std::env::var_os("RUSTC")
+            && std::env::var_os("RUSTDOC").is_none()
+            && let Some(rustdoc) = rustdoc_next_to(Path::new(&rustc))
+        {
+            command.env("RUSTDOC", rustdoc);
+        }
+
+        command
+    } else {
+        let mut command = Command::new("cargo");
+
+        if let Some(toolchain) = toolchain {
+            command.arg(format!("+{toolchain}"));
+        }
+
+        command
+    };
 
     command.arg("rustdoc");
 
@@ -114,13 +152,7 @@ pub fn generate(options: Options) -> Result<(Output, PathBuf)> {
     }
 
     command.arg("--package").arg(&package.id.repr);
-    command.arg("--");
-    command.arg("-Z").arg("unstable-options");
-    command.arg("--output-format").arg("json");
-
-    if document_private_items {
-        command.arg("--document-private-items");
-    }
+    push_rustdoc_args(&mut command, document_private_items, rustdoc_args);
 
     if matches!(output_option, CommandOutput::Ignore) {
         command.stdout(Stdio::null());
@@ -135,19 +167,46 @@ pub fn generate(options: Options) -> Result<(Output, PathBuf)> {
 
     let output = result.wrap_err_with(|| format!("failed to run {command:?}"))?;
 
-    let mut path = match target_dir {
-        Some(path) => path.to_path_buf(),
+    let target_dir = match target_dir {
+        // A relative `--target-dir` is relative to the current directory, just like for cargo.
+        // The directory exists now that cargo has run, so we can resolve symlinks too.
+        Some(path) => path.canonicalize().or_else(|_| std::path::absolute(path))?,
         None => metadata.target_directory.as_std_path().to_path_buf(),
     };
 
-    path.push("doc");
-    path.push(package_target.name.replace('-', "_"));
+    Ok((output, output_path(&target_dir, &package_target.name)))
+}
+
+/// The path rustdoc writes the json to.
+///
+/// Rustdoc names the file after the crate, so `-` is replaced by `_`.
+pub fn output_path(target_dir: &Path, target_name: &str) -> PathBuf {
+    let mut path = target_dir.join("doc");
+    path.push(target_name.replace('-', "_"));
     path.set_extension("json");
+    path
+}
+
+/// Pushes the `--` separator and the arguments for rustdoc.
+///
+/// `RUSTDOCFLAGS` is not added here, cargo already passes it to rustdoc.
+fn push_rustdoc_args(command: &mut Command, document_private_items: bool, rustdoc_args: &[String]) {
+    command.arg("--");
+    command.arg("-Z").arg("unstable-options");
+    command.arg("--output-format").arg("json");
+
+    if document_private_items {
+        command.arg("--document-private-items");
+    }
 
-    Ok((output, path))
+    command.args(rustdoc_args);
 }
 
-pub fn parse(rustdoc_json: &str, toolchain: &str) -> Result<Crate> {
+/// Parses the rustdoc json.
+///
+/// Unless `strict`, a format version that differs by one from the supported one
+/// is parsed anyway with a warning.
+pub fn parse(rustdoc_json: &str, toolchain: &str, strict: bool) -> Result<Crate> {
     #[derive(Deserialize)]
     struct CrateWithJustTheFormatVersion {
         format_version: u32,
@@ -156,14 +215,32 @@ pub fn parse(rustdoc_json: &str, toolchain: &str) -> Result<Crate> {
     let krate: CrateWithJustTheFormatVersion =
         serde_json::from_str(rustdoc_json).wrap_err("failed to parse generated rustdoc json")?;
 
-    if krate.format_version != rustdoc_types::FORMAT_VERSION {
-        let expected = rustdoc_types::FORMAT_VERSION;
-        let actual = krate.format_version;
+    let expected = rustdoc_types::FORMAT_VERSION;
+    let actual = krate.format_version;
+
+    if actual != expected {
+        if !strict && actual.abs_diff(expected) == 1 {
+            let _span = warn_span!("",
+                %toolchain,
+                expected = format!("rustdoc json version {expected}"),
+                actual = format!("rustdoc json version {actual}"),
+                help = CHANGELOG_HELP,
+            )
+            .entered();
+
+            warn!("the chosen rust toolchain may not be compatible, trying anyway");
+
+            return serde_json::from_str(rustdoc_json).wrap_err(
+                "failed to parse generated rustdoc json of a different version, \
+                try the supported toolchain",
+            );
+        }
 
         let _span = error_span!("",
             %toolchain,
             expected = format!("rustdoc json version {expected}"),
             actual = format!("rustdoc json version {actual}"),
+            help = CHANGELOG_HELP,
         )
         .entered();
 
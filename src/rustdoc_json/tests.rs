@@ -0,0 +1,57 @@
+use std::{path::Path, process::Command};
+
+use super::{RUSTDOC_TYPES_VERSION, output_path, push_rustdoc_args, rustdoc_next_to};
+
+#[test]
+fn test_output_path() {
+    let target_dir = Path::new("/path/to/target");
+
+    assert_eq!(
+        output_path(target_dir, "my-package"),
+        Path::new("/path/to/target/doc/my_package.json")
+    );
+
+    assert_eq!(
+        output_path(target_dir, "my_package"),
+        Path::new("/path/to/target/doc/my_package.json")
+    );
+}
+
+#[test]
+fn test_rustdoc_next_to() {
+    let dir = tempfile::tempdir().unwrap();
+    let exe = |name: &str| dir.path().join(format!("{name}{}", std::env::consts::EXE_SUFFIX));
+
+    assert_eq!(rustdoc_next_to(&exe("rustc")), None);
+
+    std::fs::write(exe("rustdoc"), "").unwrap();
+    assert_eq!(rustdoc_next_to(&exe("rustc")), Some(exe("rustdoc")));
+}
+
+#[test]
+fn test_rustdoc_types_version() {
+    let manifest = include_str!("../../Cargo.toml");
+    let dependency = format!("rustdoc-types = {{ version = \"{RUSTDOC_TYPES_VERSION}\"");
+    assert!(manifest.contains(&dependency), "`RUSTDOC_TYPES_VERSION` is out of date");
+}
+
+fn rustdoc_args(document_private_items: bool, rustdoc_args: &[&str]) -> Vec<String> {
+    let rustdoc_args = rustdoc_args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let mut command = Command::new("cargo");
+    push_rustdoc_args(&mut command, document_private_items, &rustdoc_args);
+    command.get_args().map(|arg| arg.to_str().unwrap().to_string()).collect()
+}
+
+#[test]
+fn test_rustdoc_args() {
+    assert_eq!(
+        rustdoc_args(false, &[]),
+        ["--", "-Z", "unstable-options", "--output-format", "json"]
+    );
+
+    // the arguments are passed verbatim, only the cli and environment variable split them
+    assert_eq!(
+        rustdoc_args(true, &["--cfg", "docsrs", "--crate-version", "1.0 beta"])[5..],
+        ["--document-private-items", "--cfg", "docsrs", "--crate-version", "1.0 beta"]
+    );
+}
@@ -26,13 +26,22 @@ macro_rules! assert_le {
 #[derive(Debug)]
 pub struct StringReplacer<'a> {
     start: usize,
+    source: &'a str,
     string: &'a str,
     chunks: Vec<Cow<'a, str>>,
+    // The last replaced range, to report overlaps.
+    last_range: Range<usize>,
 }
 
 impl<'a> StringReplacer<'a> {
     pub fn new(string: &'a str) -> Self {
-        Self { start: string.as_ptr().addr(), string, chunks: Vec::new() }
+        Self {
+            start: string.as_ptr().addr(),
+            source: string,
+            string,
+            chunks: Vec::new(),
+            last_range: 0..0,
+        }
     }
 
     pub fn position(&self) -> usize {
@@ -60,9 +69,20 @@ impl<'a> StringReplacer<'a> {
         let offset = self.position();
 
         if range.start < offset {
-            panic!("tried to replace string out of order pos={offset:?} range={range:?}");
+            let source_at = |range: &Range<usize>| self.source.get(range.clone()).unwrap_or("");
+
+            panic!(
+                "tried to replace string out of order\n\
+                 previous: {previous:?} {previous_str:?}\n \
+                 current: {range:?} {range_str:?}",
+                previous = self.last_range,
+                previous_str = source_at(&self.last_range),
+                range_str = source_at(&range),
+            );
         }
 
+        self.last_range = range.clone();
+
         range.start -= offset;
         range.end -= offset;
 
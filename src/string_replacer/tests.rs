@@ -43,6 +43,21 @@ fn test_remove() {
     assert_eq!(replacer.finish(), "obrb");
 }
 
+#[test]
+fn test_adjacent() {
+    let str = "foobarbaz";
+    let mut replacer = StringReplacer::new(str);
+
+    replacer.insert(0, "<");
+    replacer.replace(0..3, "FOO");
+    replacer.replace(3..6, "BAR");
+    replacer.insert(6, "|");
+    replacer.remove(6..9);
+    replacer.insert(9, ">");
+
+    assert_eq!(replacer.finish(), "<FOOBAR|>");
+}
+
 #[test]
 fn test_grow() {
     let str = "foobarbaz";
@@ -85,3 +100,12 @@ fn test_panic_overlap() {
     replacer.replace(5..7, "b");
     replacer.replace(6..9, "whatever");
 }
+
+#[test]
+#[should_panic = "previous: 5..7 \"zq\"\n current: 6..9 \"qux\""]
+fn test_panic_overlap_message() {
+    let str = "foobazqux";
+    let mut replacer = StringReplacer::new(str);
+    replacer.replace(5..7, "b");
+    replacer.replace(6..9, "whatever");
+}
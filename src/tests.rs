@@ -1,5 +1,8 @@
 use core::ops::Range;
-use std::fmt::{self, Write as _};
+use std::{
+    fmt::{self, Write as _},
+    path::{Path, PathBuf},
+};
 
 use crate::{
     markdown::Tree,
@@ -164,3 +167,68 @@ impl TreeFormatter {
         self.out
     }
 }
+
+#[test]
+fn test_retain_outside_dirs() {
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .no_deps()
+        .exec()
+        .unwrap();
+
+    let names = |dirs: &[&str]| {
+        let mut packages =
+            metadata.workspace_members.iter().map(|id| &metadata[id]).collect::<Vec<_>>();
+        let dirs = dirs.iter().map(PathBuf::from).collect::<Vec<_>>();
+        crate::retain_outside_dirs(&mut packages, metadata.workspace_root.as_std_path(), &dirs)
+            .unwrap();
+        packages.iter().map(|p| p.name.to_string()).collect::<Vec<_>>()
+    };
+
+    let all = names(&[]);
+    let without_test_bin = names(&["tests/test-bin"]);
+    assert!(all.iter().any(|name| name == "test-bin"));
+    assert!(all.iter().any(|name| name == "test-bin-lib"));
+    assert_eq!(without_test_bin.len(), all.len() - 1);
+    assert!(!without_test_bin.iter().any(|name| name == "test-bin"));
+
+    assert!(names(&["tests"]).iter().all(|name| ["cargo-insert-docs", "xtask"].contains(&&**name)));
+}
+
+#[test]
+fn test_is_unpopulated() {
+    assert!(crate::is_unpopulated("\n", "- **`std`**\n"));
+    assert!(!crate::is_unpopulated("- **`std`**\n", "- **`std`**\n"));
+
+    // a package without features has nothing to populate the section with
+    assert!(!crate::is_unpopulated("\n", ""));
+    assert!(!crate::is_unpopulated("", "\n"));
+}
+
+#[test]
+fn test_check_shared_readme_sections() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let readme = |package: &str, section_name: &str| crate::Readme {
+        path: crate::ManifestPath::new(&root.join("tests").join(package).join("Cargo.toml"))
+            .unwrap()
+            .relative("../../README.md"),
+        section_name: section_name.to_string(),
+    };
+
+    let bin = readme("test-bin", "crate documentation");
+    let bin_lib = readme("test-bin-lib", "crate documentation");
+    let other_section = readme("test-bin-lib", "other documentation");
+
+    let check =
+        |readmes: [(&str, &crate::Readme); 2]| crate::check_shared_readme_sections(readmes, root);
+
+    assert!(check([("test-bin", &bin), ("test-bin-lib", &other_section)]).is_ok());
+
+    expect_test::expect![[r#"
+        several packages insert their crate documentation into the same readme section:
+
+          * README.md (section "crate documentation"): `test-bin`, `test-bin-lib`
+
+        Give each package a distinct `crate-section-name`, so they insert into different sections."#]]
+    .assert_eq(&check([("test-bin", &bin), ("test-bin-lib", &bin_lib)]).unwrap_err().to_string());
+}
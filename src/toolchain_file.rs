@@ -0,0 +1,63 @@
+//! Reads the toolchain from a `rust-toolchain.toml`, like
+//!
+//! ```toml
+//! [toolchain]
+//! channel = "nightly-2026-06-24"
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use std::{fs, path::Path};
+
+use color_eyre::eyre::{OptionExt as _, Result, WrapErr as _};
+use tracing::{error_span, info, trace, warn};
+
+pub const FILE_NAME: &str = "rust-toolchain.toml";
+
+/// Returns the channel of the `rust-toolchain.toml` at `path`.
+pub fn read(path: &Path) -> Result<String> {
+    let _span = error_span!("", path = %path.display()).entered();
+    let toml = fs::read_to_string(path).wrap_err("failed to read toolchain file")?;
+    channel(&toml)
+}
+
+/// Returns the channel of the `rust-toolchain.toml` in `dir` if it is a nightly channel.
+///
+/// A toolchain file that can't be read is only warned about, since we did not ask for it.
+pub fn find_nightly(dir: &Path) -> Option<String> {
+    let path = dir.join(FILE_NAME);
+
+    if !path.is_file() {
+        return None;
+    }
+
+    let channel = match read(&path) {
+        Ok(channel) => channel,
+        Err(err) => {
+            warn!(cause = %err, "ignoring {}, using the default toolchain", path.display());
+            return None;
+        }
+    };
+
+    if !channel.starts_with("nightly") {
+        trace!("ignoring {} with the channel {channel:?}, it is not nightly", path.display());
+        return None;
+    }
+
+    info!("using toolchain {channel:?} from {}", path.display());
+    Some(channel)
+}
+
+/// Returns the `toolchain.channel` of the contents of a `rust-toolchain.toml`.
+fn channel(toml: &str) -> Result<String> {
+    let doc = toml_edit::Document::parse(toml).wrap_err("failed to parse toolchain file")?;
+
+    let channel = doc
+        .get("toolchain")
+        .and_then(|toolchain| toolchain.get("channel"))
+        .and_then(|channel| channel.as_str())
+        .ok_or_eyre("toolchain file has no `toolchain.channel`")?;
+
+    Ok(channel.to_string())
+}
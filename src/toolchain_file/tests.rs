@@ -0,0 +1,39 @@
+use indoc::indoc;
+
+use super::{FILE_NAME, channel, find_nightly};
+
+#[test]
+fn test_channel() {
+    let toml = indoc! {r#"
+        [toolchain]
+        channel = "nightly-2026-06-24"
+        components = ["rustfmt", "clippy"]
+    "#};
+
+    assert_eq!(channel(toml).unwrap(), "nightly-2026-06-24");
+    assert_eq!(channel("toolchain.channel = \"stable\"").unwrap(), "stable");
+    assert_eq!(
+        channel("[toolchain]\ncomponents = []").unwrap_err().to_string(),
+        "toolchain file has no `toolchain.channel`"
+    );
+}
+
+#[test]
+fn test_find_nightly() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(FILE_NAME);
+    assert_eq!(find_nightly(dir.path()), None);
+
+    std::fs::write(&path, "[toolchain]\nchannel = \"nightly-2026-06-24\"\n").unwrap();
+    assert_eq!(find_nightly(dir.path()).as_deref(), Some("nightly-2026-06-24"));
+
+    std::fs::write(&path, "[toolchain]\nchannel = \"stable\"\n").unwrap();
+    assert_eq!(find_nightly(dir.path()), None);
+
+    // broken or incomplete toolchain files fall back to the default toolchain
+    std::fs::write(&path, "[toolchain\n").unwrap();
+    assert_eq!(find_nightly(dir.path()), None);
+
+    std::fs::write(&path, "[toolchain]\ncomponents = []\n").unwrap();
+    assert_eq!(find_nightly(dir.path()), None);
+}
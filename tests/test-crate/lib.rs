@@ -85,8 +85,8 @@
 //!
 //!   Multiple lines work too
 //! - **`something_undocumented`**
-//! - **`recurse`** — Actually used feature, enables recursive imports that will cause errors.
-//! - **`recurse-glob`** — Actually used feature, enables recursive glob imports that will cause errors.
+//! - **`recurse`** — Actually used feature, enables recursive imports.
+//! - **`recurse-glob`** — Actually used feature, enables recursive glob imports.
 //!
 //! Here you can write documentation that goes
 //! between the features
@@ -262,6 +262,21 @@ pub mod n {
 #[cfg(feature = "recurse")]
 pub use n::a::n::a::n::a::n::a::n::a::n::a::n::a::n::a::Batman;
 
+#[cfg(feature = "recurse")]
+pub mod cyclic {
+    pub mod a {
+        pub use super::b;
+        pub struct Robin;
+    }
+
+    pub mod b {
+        pub use super::a;
+    }
+}
+
+#[cfg(feature = "recurse")]
+pub use self::cyclic::a::b::a::b::a::Robin;
+
 #[cfg(feature = "recurse-glob")]
 pub mod glob_a {
     pub use super::glob_c::*;
@@ -294,6 +309,8 @@ pub union MyUnion {
 pub struct MyStruct {
     pub my_field: i32,
 }
+#[doc(hidden)]
+pub struct MyHiddenStruct;
 impl MyStruct {
     pub fn my_method(&self) {}
 }
@@ -0,0 +1 @@
+//! It works!
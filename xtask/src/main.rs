@@ -28,6 +28,7 @@ enum Command {
     CheckConfig,
     CheckBinLib,
     CheckTestCrate,
+    CheckCompletions,
 }
 
 fn main() -> Result {
@@ -49,6 +50,7 @@ fn main() -> Result {
         Command::CheckConfig => check_config(),
         Command::CheckBinLib => check_bin_lib_stderr(),
         Command::CheckTestCrate => check_test_crate(),
+        Command::CheckCompletions => check_completions(),
     }
 }
 
@@ -59,6 +61,7 @@ fn ci() -> Result {
     check_config()?;
     check_bin_lib_stderr()?;
     check_test_crate()?;
+    check_completions()?;
     OK
 }
 
@@ -118,10 +121,10 @@ fn check_simple() -> Result {
     cmd!("cargo run -- --check -p test-document-features crate-into-readme").output()?;
     cmd!("cargo run -- --check -p example-crate").output()?;
     cmd!("cargo run -- --check -p test-bin crate-into-readme").output()?;
+    cmd!("cargo run -- --check -p test-readme-paths crate-into-readme").output()?;
     cmd!(
         "cargo run -- --check --workspace",
         "--exclude test-crate",
-        "--exclude cargo-insert-docs",
         "--exclude test-bin-lib",
         "--exclude xtask",
         "--exclude test-crate-dep",
@@ -133,12 +136,9 @@ fn check_simple() -> Result {
 
 fn check_recurse() -> Result {
     fn test(feature: &str) -> Result {
-        let out =
-            cmd!("cargo run -- -p test-crate -F", feature, "--allow-dirty").unchecked().stderr()?;
+        let out = cmd!("cargo run -- -p test-crate -F", feature, "--check").unchecked().stderr()?;
 
-        println!("{out}");
-
-        if !out.contains("recursed too deep while resolving item paths") {
+        if out.contains("recursed too deep while resolving item paths") {
             println!("{out}");
             bail!("recurse test failed");
         }
@@ -167,9 +167,37 @@ fn check_config() -> Result {
         bail!("test-config failed");
     }
 
+    // the json output must contain the same values as the toml output
+    let json_out = cmd!(
+        "cargo run -- --manifest-path tests/test-config/Cargo.toml --print-config --print-config-format json"
+    )
+    .stdout()?;
+
+    let mut json: serde_json::Value = serde_json::from_str(&json_out)?;
+    remove_nulls(&mut json);
+
+    let toml: serde_json::Value = toml::from_str(&out)?;
+
+    if json != toml {
+        print_error("JSON CONFIG DIFFERS FROM TOML CONFIG");
+        bail!("test-config failed");
+    }
+
     OK
 }
 
+/// Toml has no null, so `None`s are left out there.
+fn remove_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        serde_json::Value::Array(array) => array.iter_mut().for_each(remove_nulls),
+        _ => (),
+    }
+}
+
 fn check_bin_lib_stderr() -> Result {
     let out = cmd!("cargo run -- -p test-bin-lib --allow-dirty").unchecked().stderr()?;
 
@@ -217,6 +245,21 @@ fn check_test_crate() -> Result {
     OK
 }
 
+fn check_completions() -> Result {
+    let out = cmd!("cargo run -q -- completions bash").stdout()?;
+
+    if out.trim().is_empty() {
+        print_error("COMPLETIONS ARE EMPTY");
+        bail!("completions failed");
+    }
+
+    // check that the script parses
+    write("target/completions.bash", &out)?;
+    cmd!("bash -n target/completions.bash").run()?;
+
+    OK
+}
+
 fn print_error(message: &str) {
     let style =
         anstyle::Style::new().fg_color(Some(anstyle::Color::Ansi(anstyle::AnsiColor::Red))).bold();